@@ -1,14 +1,46 @@
-#[cfg(feature = "self-update")]
-use clap::Subcommand;
+use crate::gate::{Assertion, MaxRegression};
+use crate::slo::{Slo, SloMetric};
 use clap::builder::Styles;
 use clap::builder::styling::{AnsiColor, Effects};
 use clap::error::ErrorKind;
-use clap::{CommandFactory, Parser, ValueEnum};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use reqwest::header::{HeaderName, HeaderValue};
+use serde_json::{Map, Value};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum ApiType {
     Chat,
     Responses,
+    Completions,
+    AnthropicMessages,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum ArrivalDistribution {
+    Poisson,
+    Constant,
+    Gamma,
+}
+
+impl ArrivalDistribution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArrivalDistribution::Poisson => "poisson",
+            ArrivalDistribution::Constant => "constant",
+            ArrivalDistribution::Gamma => "gamma",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum DatasetFormat {
+    Auto,
+    Jsonl,
+    Sharegpt,
+    Csv,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
@@ -18,10 +50,53 @@ pub enum OutputFormat {
     None,
 }
 
-#[cfg(feature = "self-update")]
+/// Flat file formats for per-request records.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// Statistic pulled from each metric when comparing saved runs.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum CompareStat {
+    Avg,
+    Min,
+    P50,
+    P90,
+    P95,
+    P99,
+    Max,
+}
+
 #[derive(Debug, Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum Command {
+    /// Run the benchmark at several load levels and report a latency/throughput curve
+    Sweep(SweepArgs),
+    /// Compare saved runs side by side against the first run
+    Compare(CompareArgs),
+    /// Recompute a saved run's summary from its per-request records
+    Report(ReportArgs),
+    /// Convert a saved run's per-request records to a flat file
+    Export(ExportArgs),
+    /// List, show and prune saved runs
+    Runs(RunsArgs),
+    /// Serve a mock OpenAI-compatible streaming endpoint for offline testing
+    MockServer(MockServerArgs),
     /// Update llmnop (standalone installs only)
+    #[cfg(feature = "self-update")]
     Update,
 }
 
@@ -33,12 +108,265 @@ const STYLES: Styles = Styles::styled()
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None, styles = STYLES)]
-#[cfg_attr(feature = "self-update", command(subcommand_negates_reqs = true))]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
-    #[cfg(feature = "self-update")]
     #[command(subcommand)]
     pub command: Option<Command>,
 
+    #[command(flatten)]
+    pub benchmark: BenchmarkArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SweepArgs {
+    #[command(flatten)]
+    pub benchmark: BenchmarkArgs,
+
+    #[arg(
+        long,
+        value_parser = parse_concurrency_levels,
+        required_unless_present = "request_rates",
+        conflicts_with_all = ["request_rates", "request_rate"],
+        help = "Concurrency levels: a list (1,2,4) or a doubling range (1..64)",
+        help_heading = "Sweep"
+    )]
+    pub concurrency: Option<SweepLevels>,
+
+    #[arg(
+        long,
+        value_parser = parse_request_rates,
+        help = "Open-loop request rates: a list (1,2.5,5) or a range with step (1..10:1)",
+        help_heading = "Sweep"
+    )]
+    pub request_rates: Option<SweepLevels>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CompareArgs {
+    #[arg(
+        required = true,
+        num_args = 2..,
+        value_name = "RUN",
+        help = "Run ids, run directories or summary files; the first run is the baseline"
+    )]
+    pub runs: Vec<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "avg,p50,p99",
+        help = "Statistics to compare for each metric"
+    )]
+    pub stats: Vec<CompareStat>,
+
+    #[arg(
+        long,
+        default_value_t = 0.05,
+        value_parser = parse_significance_level,
+        help = "Mann-Whitney U p-value below which a change counts as significant"
+    )]
+    pub alpha: f64,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ReportArgs {
+    #[arg(
+        value_name = "RUN",
+        help = "Run id, run directory or summary.json path"
+    )]
+    pub run: String,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Drop requests that started within this long of the run start, e.g. 30s"
+    )]
+    pub skip_first: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Drop requests that finished within this long of the run end"
+    )]
+    pub skip_last: Option<Duration>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "99,90,50",
+        value_parser = parse_percentile,
+        help = "Percentile columns of the table"
+    )]
+    pub percentiles: Vec<f64>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write the summary here instead of report_summary.json in the run directory"
+    )]
+    pub output: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Also write an offline HTML report with charts next to the summary"
+    )]
+    pub html: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExportArgs {
+    #[arg(
+        value_name = "RUN",
+        help = "Run id, run directory or summary.json path"
+    )]
+    pub run: String,
+
+    #[arg(long, value_enum, default_value = "csv", help = "File format")]
+    pub format: ExportFormat,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Output path [default: individual_responses.<format> in the run directory]"
+    )]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RunsArgs {
+    #[command(subcommand)]
+    pub command: RunsCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RunsCommand {
+    /// List saved runs, newest first
+    List,
+    /// Print the summary table of a saved run
+    Show(RunsShowArgs),
+    /// Delete saved runs by age or count
+    Prune(RunsPruneArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RunsShowArgs {
+    #[arg(
+        value_name = "RUN",
+        help = "Run id, run directory or summary.json path"
+    )]
+    pub run: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(group(
+    clap::ArgGroup::new("criteria")
+        .required(true)
+        .multiple(true)
+        .args(["older_than", "keep"])
+))]
+pub struct RunsPruneArgs {
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Delete runs older than this, e.g. 30d or 12h"
+    )]
+    pub older_than: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Never delete the N most recent runs; alone, delete all older ones"
+    )]
+    pub keep: Option<usize>,
+
+    #[arg(
+        long,
+        help = "List the runs that would be deleted without deleting them"
+    )]
+    pub dry_run: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct MockServerArgs {
+    #[arg(
+        long,
+        value_name = "ADDR",
+        default_value = "127.0.0.1:8000",
+        help = "Address to listen on"
+    )]
+    pub listen: SocketAddr,
+
+    #[arg(
+        long,
+        default_value = "100ms",
+        value_parser = parse_delay,
+        help = "Delay before the first streamed token"
+    )]
+    pub ttft: Duration,
+
+    #[arg(
+        long,
+        default_value = "20ms",
+        value_parser = parse_delay,
+        help = "Delay between streamed tokens"
+    )]
+    pub itl: Duration,
+
+    #[arg(
+        long,
+        default_value = "0",
+        value_parser = parse_fraction,
+        help = "Scale each delay by a random factor within this fraction, e.g. 0.1 for ±10%"
+    )]
+    pub jitter: f64,
+
+    #[arg(
+        long,
+        default_value = "128",
+        help = "Output tokens generated before the model stops on its own"
+    )]
+    pub output_tokens: u32,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "Reasoning tokens streamed before the output tokens"
+    )]
+    pub reasoning_tokens: u32,
+
+    #[arg(long, help = "Leave token usage out of responses")]
+    pub no_usage: bool,
+
+    #[arg(
+        long,
+        default_value = "0",
+        value_parser = parse_fraction,
+        help = "Fraction of requests answered with an error instead of a stream"
+    )]
+    pub error_rate: f64,
+
+    #[arg(
+        long,
+        default_value = "500",
+        value_parser = clap::value_parser!(u16).range(400..600),
+        help = "HTTP status of injected errors"
+    )]
+    pub error_status: u16,
+
+    #[arg(long, help = "Seed for jitter and error injection")]
+    pub seed: Option<u64>,
+}
+
+/// Load levels visited by `llmnop sweep`, in the order they run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepLevels(pub Vec<f64>);
+
+#[derive(clap::Args, Debug)]
+pub struct BenchmarkArgs {
     // Endpoint
     #[arg(
         long,
@@ -62,6 +390,24 @@ pub struct Args {
     )]
     pub api: ApiType,
 
+    #[arg(
+        long = "header",
+        value_name = "NAME:VALUE",
+        value_parser = parse_header,
+        help = "Extra HTTP header sent with every request (repeatable)",
+        help_heading = "Endpoint"
+    )]
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+
+    #[arg(
+        long,
+        value_name = "JSON|@FILE",
+        value_parser = parse_extra_body,
+        help = "JSON object merged into every request body, e.g. '{\"temperature\":0}'",
+        help_heading = "Endpoint"
+    )]
+    pub extra_body: Option<Map<String, Value>>,
+
     // Request Shaping
     #[arg(
         long,
@@ -94,6 +440,83 @@ pub struct Args {
     )]
     pub stddev_output_tokens: u32,
 
+    #[arg(
+        long,
+        help = "Ask the server to generate exactly the requested output length (vLLM/SGLang)",
+        help_heading = "Request Shaping"
+    )]
+    pub exact_output_tokens: bool,
+
+    #[arg(
+        long,
+        help = "Replay prompts from a JSONL, ShareGPT or CSV file",
+        help_heading = "Request Shaping"
+    )]
+    pub dataset: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "auto",
+        requires = "dataset",
+        help = "Dataset file format",
+        help_heading = "Request Shaping"
+    )]
+    pub dataset_format: DatasetFormat,
+
+    #[arg(
+        long,
+        requires = "dataset",
+        help = "Skip dataset prompts shorter than this",
+        help_heading = "Request Shaping"
+    )]
+    pub min_input_tokens: Option<u32>,
+
+    #[arg(
+        long,
+        requires = "dataset",
+        help = "Skip dataset prompts longer than this",
+        help_heading = "Request Shaping"
+    )]
+    pub max_input_tokens: Option<u32>,
+
+    #[arg(
+        long,
+        default_value = "1",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Turns per simulated chat session",
+        help_heading = "Request Shaping"
+    )]
+    pub num_turns: u32,
+
+    #[arg(
+        long,
+        conflicts_with = "dataset",
+        help = "Shared prefix length; --mean-input-tokens sets the unique suffix",
+        help_heading = "Request Shaping"
+    )]
+    pub prefix_tokens: Option<u32>,
+
+    #[arg(
+        long,
+        default_value = "1",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "prefix_tokens",
+        help = "Number of distinct shared prefixes",
+        help_heading = "Request Shaping"
+    )]
+    pub num_prefixes: u32,
+
+    #[arg(
+        long,
+        default_value = "0.0",
+        value_parser = parse_non_negative_f64,
+        requires = "prefix_tokens",
+        help = "Zipf exponent for prefix popularity (0 is uniform)",
+        help_heading = "Request Shaping"
+    )]
+    pub prefix_skew: f64,
+
     // Load Testing
     #[arg(
         long,
@@ -103,6 +526,25 @@ pub struct Args {
     )]
     pub max_num_completed_requests: u32,
 
+    #[arg(
+        long,
+        value_parser = parse_duration,
+        conflicts_with_all = ["max_num_completed_requests", "timeout"],
+        help = "Send requests for this long instead of a fixed count, e.g. 10m",
+        help_heading = "Load Testing"
+    )]
+    pub duration: Option<Duration>,
+
+    #[arg(
+        long,
+        default_value = "30s",
+        value_parser = parse_duration,
+        requires = "duration",
+        help = "How long to wait for in-flight requests after --duration ends",
+        help_heading = "Load Testing"
+    )]
+    pub grace_period: Duration,
+
     #[arg(
         long,
         default_value = "1",
@@ -111,14 +553,127 @@ pub struct Args {
     )]
     pub num_concurrent_requests: u32,
 
+    #[arg(
+        long,
+        default_value = "0",
+        help = "Requests sent before measuring, excluded from statistics",
+        help_heading = "Load Testing"
+    )]
+    pub warmup_requests: u32,
+
+    #[arg(
+        long,
+        value_parser = parse_positive_f64,
+        help = "Requests per second, dispatched open-loop [default: closed loop]",
+        help_heading = "Load Testing"
+    )]
+    pub request_rate: Option<f64>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "poisson",
+        help = "Inter-arrival distribution for --request-rate",
+        help_heading = "Load Testing"
+    )]
+    pub arrival_distribution: ArrivalDistribution,
+
+    #[arg(
+        long,
+        default_value = "1.0",
+        value_parser = parse_positive_f64,
+        help = "Gamma shape for --arrival-distribution gamma (lower is burstier)",
+        help_heading = "Load Testing"
+    )]
+    pub burstiness: f64,
+
     #[arg(
         long,
         default_value = "600",
-        help = "Request timeout",
+        help = "Stop a fixed-count run after this many seconds, abandoning in-flight requests",
         help_heading = "Load Testing"
     )]
     pub timeout: u64,
 
+    #[arg(
+        long,
+        value_parser = parse_duration,
+        help = "Fail a request with no first token after this long, e.g. 30s",
+        help_heading = "Load Testing"
+    )]
+    pub ttft_timeout: Option<Duration>,
+
+    #[arg(
+        long,
+        value_parser = parse_duration,
+        help = "Fail a request that has not finished after this long, e.g. 5m",
+        help_heading = "Load Testing"
+    )]
+    pub request_timeout: Option<Duration>,
+
+    #[arg(
+        long,
+        value_parser = parse_duration,
+        help = "Fail a request whose stream goes quiet for this long between events, e.g. 10s",
+        help_heading = "Load Testing"
+    )]
+    pub idle_timeout: Option<Duration>,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "Retry rate limits, 5xx responses, timeouts and dropped connections up to N times",
+        help_heading = "Load Testing"
+    )]
+    pub max_retries: u32,
+
+    #[arg(
+        long,
+        default_value = "500ms",
+        value_parser = parse_delay,
+        help = "Initial retry delay, doubled each attempt; a longer Retry-After wins",
+        help_heading = "Load Testing"
+    )]
+    pub retry_backoff: Duration,
+
+    // SLOs
+    #[arg(
+        long = "slo",
+        value_name = "METRIC=LIMIT",
+        value_parser = parse_slo,
+        help = "Latency SLO for goodput, e.g. ttft=500ms, itl=50ms, e2e=10s (repeatable)",
+        help_heading = "SLOs"
+    )]
+    pub slos: Vec<Slo>,
+
+    // Regression Gate
+    #[arg(
+        long,
+        value_name = "RUN",
+        help = "Baseline run for --max-regression: a summary file, run directory or run id",
+        help_heading = "Regression Gate"
+    )]
+    pub baseline: Option<String>,
+
+    #[arg(
+        long = "max-regression",
+        value_name = "METRIC.STAT=PERCENT",
+        value_parser = MaxRegression::parse,
+        requires = "baseline",
+        help = "Fail if a statistic is worse than the baseline by more than PERCENT, e.g. ttft.p99=10% (repeatable)",
+        help_heading = "Regression Gate"
+    )]
+    pub max_regressions: Vec<MaxRegression>,
+
+    #[arg(
+        long = "assert",
+        value_name = "METRIC.STAT<OP>VALUE",
+        value_parser = Assertion::parse,
+        help = "Fail unless a statistic meets a bound, e.g. output_token_throughput.avg>=800 (repeatable)",
+        help_heading = "Regression Gate"
+    )]
+    pub assertions: Vec<Assertion>,
+
     // Tokenization
     #[arg(
         long,
@@ -158,9 +713,28 @@ pub struct Args {
         help_heading = "Output"
     )]
     pub quiet: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "FORMAT",
+        help = "Also write per-request records as flat files next to individual_responses.jsonl",
+        help_heading = "Output"
+    )]
+    pub export: Vec<ExportFormat>,
+
+    #[cfg(feature = "prometheus")]
+    #[arg(
+        long,
+        value_name = "ADDR",
+        help = "Serve live Prometheus metrics at http://ADDR/metrics, e.g. 127.0.0.1:9464",
+        help_heading = "Output"
+    )]
+    pub metrics_listen: Option<SocketAddr>,
 }
 
-impl Args {
+impl BenchmarkArgs {
     pub fn require_benchmark_args(&self) -> Result<(&str, &str), clap::Error> {
         let url = self
             .url
@@ -173,22 +747,216 @@ impl Args {
         Ok((url, model))
     }
 
-    fn missing_required_arg(arg: &str) -> clap::Error {
-        Self::command().error(
-            ErrorKind::MissingRequiredArgument,
-            format!("the following required argument was not provided: {arg}"),
-        )
-    }
+    fn missing_required_arg(arg: &str) -> clap::Error {
+        Args::command().error(
+            ErrorKind::MissingRequiredArgument,
+            format!("the following required argument was not provided: {arg}"),
+        )
+    }
+
+    pub fn effective_output_format(&self) -> OutputFormat {
+        if self.quiet {
+            OutputFormat::None
+        } else if self.json {
+            OutputFormat::Json
+        } else {
+            self.output_format
+        }
+    }
+}
+
+fn parse_positive_f64(value: &str) -> Result<f64, String> {
+    let parsed: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid number: {value}"))?;
+    if parsed.is_finite() && parsed > 0.0 {
+        Ok(parsed)
+    } else {
+        Err(format!("must be a positive number: {value}"))
+    }
+}
+
+fn parse_significance_level(value: &str) -> Result<f64, String> {
+    let alpha = value
+        .parse::<f64>()
+        .map_err(|_| format!("invalid number: {value}"))?;
+    if alpha > 0.0 && alpha < 1.0 {
+        Ok(alpha)
+    } else {
+        Err(format!("must be between 0 and 1: {value}"))
+    }
+}
+
+fn parse_percentile(value: &str) -> Result<f64, String> {
+    let pct = value
+        .parse::<f64>()
+        .map_err(|_| format!("invalid number: {value}"))?;
+    if (0.0..=100.0).contains(&pct) {
+        Ok(pct)
+    } else {
+        Err(format!("must be between 0 and 100: {value}"))
+    }
+}
+
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    let fraction = value
+        .parse::<f64>()
+        .map_err(|_| format!("invalid number: {value}"))?;
+    if (0.0..=1.0).contains(&fraction) {
+        Ok(fraction)
+    } else {
+        Err(format!("must be between 0 and 1: {value}"))
+    }
+}
+
+fn parse_non_negative_f64(value: &str) -> Result<f64, String> {
+    let parsed: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid number: {value}"))?;
+    if parsed.is_finite() && parsed >= 0.0 {
+        Ok(parsed)
+    } else {
+        Err(format!("must be a non-negative number: {value}"))
+    }
+}
+
+/// Parses durations like `500ms`, `1.5s`, `2m`, `1h` or `30d`; a bare number is seconds.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    match parse_delay(value)? {
+        Duration::ZERO => Err(format!("must be a positive duration: {value}")),
+        duration => Ok(duration),
+    }
+}
+
+/// Like [`parse_duration`], but zero is allowed.
+fn parse_delay(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split_at = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split_at);
+
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid duration: {value}"))?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        "d" => number * 86400.0,
+        _ => {
+            return Err(format!(
+                "invalid duration unit '{unit}' (use ms, s, m, h or d)"
+            ));
+        }
+    };
+
+    if seconds.is_finite() && seconds >= 0.0 {
+        Ok(Duration::from_secs_f64(seconds))
+    } else {
+        Err(format!("must not be a negative duration: {value}"))
+    }
+}
+
+fn parse_header(value: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, header_value) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected NAME:VALUE, got '{value}'"))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|_| format!("invalid header name: '{}'", name.trim()))?;
+    let header_value = HeaderValue::from_str(header_value.trim())
+        .map_err(|_| format!("invalid value for header '{name}'"))?;
+    Ok((name, header_value))
+}
+
+/// Parses a JSON object given inline or, with a leading `@`, read from a file.
+fn parse_extra_body(value: &str) -> Result<Map<String, Value>, String> {
+    let contents = match value.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read '{path}': {err}"))?,
+        None => value.to_string(),
+    };
+    match serde_json::from_str(&contents) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        Ok(_) => Err("must be a JSON object".to_string()),
+        Err(err) => Err(format!("invalid JSON: {err}")),
+    }
+}
+
+fn parse_slo(value: &str) -> Result<Slo, String> {
+    let (metric, threshold) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected METRIC=LIMIT: {value}"))?;
+    let metric = SloMetric::parse(metric.trim())
+        .ok_or_else(|| format!("unknown SLO metric '{metric}' (use ttft, itl or e2e)"))?;
+
+    Ok(Slo {
+        metric,
+        threshold: parse_duration(threshold)?,
+    })
+}
+
+fn parse_concurrency_levels(value: &str) -> Result<SweepLevels, String> {
+    let levels = if let Some((start, end)) = value.split_once("..") {
+        let start: u32 = start
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid range start: {start}"))?;
+        let end: u32 = end
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid range end: {end}"))?;
+        if start == 0 || end < start {
+            return Err(format!("invalid concurrency range: {value}"));
+        }
+
+        let mut levels = Vec::new();
+        let mut level = start;
+        while level < end {
+            levels.push(level as f64);
+            level = level.saturating_mul(2);
+        }
+        levels.push(end as f64);
+        levels
+    } else {
+        value
+            .split(',')
+            .map(|level| match level.trim().parse::<u32>() {
+                Ok(level) if level > 0 => Ok(level as f64),
+                _ => Err(format!("invalid concurrency level: {level}")),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
 
-    pub fn effective_output_format(&self) -> OutputFormat {
-        if self.quiet {
-            OutputFormat::None
-        } else if self.json {
-            OutputFormat::Json
-        } else {
-            self.output_format
+    Ok(SweepLevels(levels))
+}
+
+fn parse_request_rates(value: &str) -> Result<SweepLevels, String> {
+    let rates = if let Some((start, rest)) = value.split_once("..") {
+        let (end, step) = rest
+            .split_once(':')
+            .ok_or_else(|| format!("request rate range needs a step (start..end:step): {value}"))?;
+        let start = parse_positive_f64(start.trim())?;
+        let end = parse_positive_f64(end.trim())?;
+        let step = parse_positive_f64(step.trim())?;
+        if end < start {
+            return Err(format!("invalid request rate range: {value}"));
         }
-    }
+
+        let num_steps = ((end - start) / step + 1e-9).floor() as usize;
+        (0..=num_steps)
+            .map(|index| start + step * index as f64)
+            .collect()
+    } else {
+        value
+            .split(',')
+            .map(|rate| parse_positive_f64(rate.trim()))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    Ok(SweepLevels(rates))
 }
 
 #[cfg(test)]
@@ -209,7 +977,7 @@ mod tests {
         ])
         .expect("parse args");
 
-        assert!(matches!(args.api, ApiType::Chat));
+        assert!(matches!(args.benchmark.api, ApiType::Chat));
     }
 
     #[test]
@@ -227,28 +995,60 @@ mod tests {
         ])
         .expect("parse args");
 
-        assert!(matches!(args.api, ApiType::Responses));
+        assert!(matches!(args.benchmark.api, ApiType::Responses));
+    }
+
+    #[test]
+    fn test_parse_completions_api_type() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--api",
+            "completions",
+            "--model",
+            "test-model",
+            "--url",
+            "http://localhost:8000/v1",
+        ])
+        .expect("parse args");
+
+        assert!(matches!(args.benchmark.api, ApiType::Completions));
+    }
+
+    #[test]
+    fn test_parse_anthropic_messages_api_type() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--api",
+            "anthropic-messages",
+            "--model",
+            "claude-sonnet-4-5",
+            "--url",
+            "https://api.anthropic.com/v1",
+        ])
+        .expect("parse args");
+
+        assert!(matches!(args.benchmark.api, ApiType::AnthropicMessages));
     }
 
     #[test]
     fn test_missing_url_is_error() {
         let args = Args::try_parse_from(["llmnop", "--model", "test-model", "--api-key", "key"])
             .expect("parse args");
-        assert!(args.require_benchmark_args().is_err());
+        assert!(args.benchmark.require_benchmark_args().is_err());
     }
 
     #[test]
     fn test_missing_model_is_error() {
         let args = Args::try_parse_from(["llmnop", "--url", "http://localhost:8000/v1"])
             .expect("parse args");
-        assert!(args.require_benchmark_args().is_err());
+        assert!(args.benchmark.require_benchmark_args().is_err());
     }
 
     #[test]
     fn test_missing_api_key_is_allowed() {
         let args = Args::try_parse_from(["llmnop", "--model", "test-model", "--url", "http://x"])
             .expect("parse args");
-        assert!(args.api_key.is_none());
+        assert!(args.benchmark.api_key.is_none());
     }
 
     #[test]
@@ -264,9 +1064,9 @@ mod tests {
         ])
         .expect("parse args");
 
-        assert!(!args.quiet);
-        assert!(!args.json);
-        assert!(matches!(args.output_format, OutputFormat::Table));
+        assert!(!args.benchmark.quiet);
+        assert!(!args.benchmark.json);
+        assert!(matches!(args.benchmark.output_format, OutputFormat::Table));
     }
 
     #[test]
@@ -283,8 +1083,11 @@ mod tests {
         ])
         .expect("parse args");
 
-        assert!(args.quiet);
-        assert!(matches!(args.effective_output_format(), OutputFormat::None));
+        assert!(args.benchmark.quiet);
+        assert!(matches!(
+            args.benchmark.effective_output_format(),
+            OutputFormat::None
+        ));
     }
 
     #[test]
@@ -301,8 +1104,11 @@ mod tests {
         ])
         .expect("parse args");
 
-        assert!(args.quiet);
-        assert!(matches!(args.effective_output_format(), OutputFormat::None));
+        assert!(args.benchmark.quiet);
+        assert!(matches!(
+            args.benchmark.effective_output_format(),
+            OutputFormat::None
+        ));
     }
 
     #[test]
@@ -320,8 +1126,11 @@ mod tests {
         ])
         .expect("parse args");
 
-        assert!(matches!(args.output_format, OutputFormat::Json));
-        assert!(matches!(args.effective_output_format(), OutputFormat::Json));
+        assert!(matches!(args.benchmark.output_format, OutputFormat::Json));
+        assert!(matches!(
+            args.benchmark.effective_output_format(),
+            OutputFormat::Json
+        ));
     }
 
     #[test]
@@ -338,8 +1147,11 @@ mod tests {
         ])
         .expect("parse args");
 
-        assert!(args.json);
-        assert!(matches!(args.effective_output_format(), OutputFormat::Json));
+        assert!(args.benchmark.json);
+        assert!(matches!(
+            args.benchmark.effective_output_format(),
+            OutputFormat::Json
+        ));
     }
 
     #[test]
@@ -358,8 +1170,11 @@ mod tests {
         ])
         .expect("parse args");
 
-        assert!(matches!(args.output_format, OutputFormat::Json));
-        assert!(matches!(args.effective_output_format(), OutputFormat::None));
+        assert!(matches!(args.benchmark.output_format, OutputFormat::Json));
+        assert!(matches!(
+            args.benchmark.effective_output_format(),
+            OutputFormat::None
+        ));
     }
 
     #[test]
@@ -377,9 +1192,544 @@ mod tests {
         ])
         .expect("parse args");
 
-        assert!(args.json);
-        assert!(args.quiet);
-        assert!(matches!(args.effective_output_format(), OutputFormat::None));
+        assert!(args.benchmark.json);
+        assert!(args.benchmark.quiet);
+        assert!(matches!(
+            args.benchmark.effective_output_format(),
+            OutputFormat::None
+        ));
+    }
+
+    #[test]
+    fn test_parse_warmup_requests() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--warmup-requests",
+            "4",
+        ])
+        .expect("parse args");
+
+        assert_eq!(args.benchmark.warmup_requests, 4);
+    }
+
+    #[test]
+    fn test_default_is_closed_loop() {
+        let args = Args::try_parse_from(["llmnop", "--model", "test-model", "--url", "http://x"])
+            .expect("parse args");
+
+        assert!(args.benchmark.request_rate.is_none());
+        assert_eq!(
+            args.benchmark.arrival_distribution,
+            ArrivalDistribution::Poisson
+        );
+    }
+
+    #[test]
+    fn test_parse_request_rate_with_distribution() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--request-rate",
+            "5",
+            "--arrival-distribution",
+            "gamma",
+            "--burstiness",
+            "0.5",
+        ])
+        .expect("parse args");
+
+        assert_eq!(args.benchmark.request_rate, Some(5.0));
+        assert_eq!(
+            args.benchmark.arrival_distribution,
+            ArrivalDistribution::Gamma
+        );
+        assert_eq!(args.benchmark.burstiness, 0.5);
+    }
+
+    #[test]
+    fn test_non_positive_request_rate_is_error() {
+        for rate in ["0", "-1", "inf", "abc"] {
+            let result = Args::try_parse_from([
+                "llmnop",
+                "--model",
+                "test-model",
+                "--url",
+                "http://x",
+                "--request-rate",
+                rate,
+            ]);
+            assert!(result.is_err(), "rate {rate} should be rejected");
+        }
+    }
+
+    #[test]
+    fn test_parse_dataset_with_token_range() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--dataset",
+            "prompts.jsonl",
+            "--dataset-format",
+            "sharegpt",
+            "--min-input-tokens",
+            "100",
+            "--max-input-tokens",
+            "2000",
+        ])
+        .expect("parse args");
+
+        assert_eq!(
+            args.benchmark.dataset.as_deref(),
+            Some(std::path::Path::new("prompts.jsonl"))
+        );
+        assert_eq!(args.benchmark.dataset_format, DatasetFormat::Sharegpt);
+        assert_eq!(args.benchmark.min_input_tokens, Some(100));
+        assert_eq!(args.benchmark.max_input_tokens, Some(2000));
+    }
+
+    #[test]
+    fn test_input_token_range_requires_dataset() {
+        let result = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--max-input-tokens",
+            "2000",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_num_turns() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--num-turns",
+            "5",
+        ])
+        .expect("parse args");
+        assert_eq!(args.benchmark.num_turns, 5);
+
+        let result = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--num-turns",
+            "0",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_shared_prefix_options() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--prefix-tokens",
+            "2000",
+            "--num-prefixes",
+            "8",
+            "--prefix-skew",
+            "1.2",
+        ])
+        .expect("parse args");
+
+        assert_eq!(args.benchmark.prefix_tokens, Some(2000));
+        assert_eq!(args.benchmark.num_prefixes, 8);
+        assert_eq!(args.benchmark.prefix_skew, 1.2);
+    }
+
+    #[test]
+    fn test_prefix_options_require_prefix_tokens() {
+        let result = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--num-prefixes",
+            "8",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_repeated_slos() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--slo",
+            "ttft=500ms",
+            "--slo",
+            "itl=50ms",
+            "--slo",
+            "e2e=10s",
+        ])
+        .expect("parse args");
+
+        assert_eq!(
+            args.benchmark.slos,
+            vec![
+                Slo {
+                    metric: SloMetric::TimeToFirstToken,
+                    threshold: Duration::from_millis(500),
+                },
+                Slo {
+                    metric: SloMetric::InterTokenLatency,
+                    threshold: Duration::from_millis(50),
+                },
+                Slo {
+                    metric: SloMetric::EndToEndLatency,
+                    threshold: Duration::from_secs(10),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_slo_rejects_bad_input() {
+        assert!(parse_slo("ttft").is_err());
+        assert!(parse_slo("latency=1s").is_err());
+        assert!(parse_slo("ttft=0ms").is_err());
+        assert!(parse_slo("ttft=5 parsecs").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("30d"), Ok(Duration::from_secs(30 * 86400)));
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("0s").is_err());
+        assert_eq!(parse_delay("0s"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_parse_sweep_concurrency_list() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "sweep",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--concurrency",
+            "1,2,8",
+        ])
+        .expect("parse args");
+
+        let Some(Command::Sweep(sweep)) = args.command else {
+            panic!("expected sweep command");
+        };
+        assert_eq!(sweep.concurrency, Some(SweepLevels(vec![1.0, 2.0, 8.0])));
+        assert_eq!(sweep.benchmark.model.as_deref(), Some("test-model"));
+    }
+
+    #[test]
+    fn test_parse_concurrency_range_doubles() {
+        assert_eq!(
+            parse_concurrency_levels("1..64").unwrap(),
+            SweepLevels(vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0])
+        );
+        assert_eq!(
+            parse_concurrency_levels("3..20").unwrap(),
+            SweepLevels(vec![3.0, 6.0, 12.0, 20.0])
+        );
+        assert!(parse_concurrency_levels("0..8").is_err());
+        assert!(parse_concurrency_levels("1,0").is_err());
+    }
+
+    #[test]
+    fn test_parse_request_rate_range_with_step() {
+        assert_eq!(
+            parse_request_rates("1..3:0.5").unwrap(),
+            SweepLevels(vec![1.0, 1.5, 2.0, 2.5, 3.0])
+        );
+        assert_eq!(
+            parse_request_rates("2,4.5").unwrap(),
+            SweepLevels(vec![2.0, 4.5])
+        );
+        assert!(parse_request_rates("1..3").is_err());
+    }
+
+    #[test]
+    fn test_parse_regression_gate() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--baseline",
+            "1700000000_123456789",
+            "--max-regression",
+            "ttft.p99=10%",
+            "--assert",
+            "output_token_throughput.avg>=800",
+            "--assert",
+            "error_rate<=0.01",
+        ])
+        .expect("parse args");
+
+        assert_eq!(
+            args.benchmark.baseline.as_deref(),
+            Some("1700000000_123456789")
+        );
+        assert_eq!(args.benchmark.max_regressions.len(), 1);
+        assert_eq!(args.benchmark.assertions.len(), 2);
+
+        let result = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--max-regression",
+            "ttft.p99=10%",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_runs_subcommands() {
+        let args = Args::try_parse_from(["llmnop", "runs", "show", "1700000000_123456789"])
+            .expect("parse args");
+        let Some(Command::Runs(RunsArgs {
+            command: RunsCommand::Show(show),
+        })) = args.command
+        else {
+            panic!("expected runs show command");
+        };
+        assert_eq!(show.run, "1700000000_123456789");
+
+        let args = Args::try_parse_from([
+            "llmnop",
+            "runs",
+            "prune",
+            "--older-than",
+            "30d",
+            "--keep",
+            "5",
+        ])
+        .expect("parse args");
+        let Some(Command::Runs(RunsArgs {
+            command: RunsCommand::Prune(prune),
+        })) = args.command
+        else {
+            panic!("expected runs prune command");
+        };
+        assert_eq!(prune.older_than, Some(Duration::from_secs(30 * 86400)));
+        assert_eq!(prune.keep, Some(5));
+        assert!(!prune.dry_run);
+
+        assert!(Args::try_parse_from(["llmnop", "runs", "prune"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_compare_runs_and_stats() {
+        let args =
+            Args::try_parse_from(["llmnop", "compare", "run-a", "run-b", "--stats", "p90,max"])
+                .expect("parse args");
+
+        let Some(Command::Compare(compare)) = args.command else {
+            panic!("expected compare command");
+        };
+        assert_eq!(compare.runs, vec!["run-a", "run-b"]);
+        assert_eq!(compare.stats, vec![CompareStat::P90, CompareStat::Max]);
+        assert_eq!(compare.alpha, 0.05);
+
+        assert!(Args::try_parse_from(["llmnop", "compare", "run-a"]).is_err());
+        assert!(parse_significance_level("1").is_err());
+        assert_eq!(parse_significance_level("0.01"), Ok(0.01));
+    }
+
+    #[test]
+    fn test_parse_report_filters_and_percentiles() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "report",
+            "1700000000_123456789",
+            "--skip-first",
+            "30s",
+            "--percentiles",
+            "50,99.9",
+        ])
+        .expect("parse args");
+
+        let Some(Command::Report(report)) = args.command else {
+            panic!("expected report command");
+        };
+        assert_eq!(report.run, "1700000000_123456789");
+        assert_eq!(report.skip_first, Some(Duration::from_secs(30)));
+        assert_eq!(report.skip_last, None);
+        assert_eq!(report.percentiles, vec![50.0, 99.9]);
+        assert_eq!(report.output, None);
+        assert!(!report.html);
+
+        assert!(parse_percentile("101").is_err());
+        assert_eq!(parse_percentile("0"), Ok(0.0));
+    }
+
+    #[test]
+    fn test_parse_export_formats() {
+        let args =
+            Args::try_parse_from(["llmnop", "export", "1700000000_123456789"]).expect("parse args");
+        let Some(Command::Export(export)) = args.command else {
+            panic!("expected export command");
+        };
+        assert_eq!(export.format, ExportFormat::Csv);
+        assert_eq!(export.output, None);
+
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--export",
+            "csv",
+        ])
+        .expect("parse args");
+        assert_eq!(args.benchmark.export, vec![ExportFormat::Csv]);
+        assert!(Args::try_parse_from(["llmnop", "export", "run", "--format", "xlsx"]).is_err());
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_parse_metrics_listen() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--metrics-listen",
+            "127.0.0.1:9464",
+        ])
+        .expect("parse args");
+        assert_eq!(
+            args.benchmark.metrics_listen,
+            Some("127.0.0.1:9464".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_duration_run() {
+        let base = ["llmnop", "--model", "test-model", "--url", "http://x"];
+        let args = Args::try_parse_from(base.iter().chain(&["--duration", "10m"])).unwrap();
+        assert_eq!(args.benchmark.duration, Some(Duration::from_secs(600)));
+        assert_eq!(args.benchmark.grace_period, Duration::from_secs(30));
+
+        assert!(Args::try_parse_from(base.iter().chain(&["--grace-period", "5s"])).is_err());
+        assert!(
+            Args::try_parse_from(base.iter().chain(&[
+                "--duration",
+                "1m",
+                "--max-num-completed-requests",
+                "100"
+            ]))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_mock_server_args() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "mock-server",
+            "--ttft",
+            "0",
+            "--itl",
+            "5ms",
+            "--error-rate",
+            "0.25",
+        ])
+        .expect("parse args");
+        let Some(Command::MockServer(mock)) = args.command else {
+            panic!("expected mock-server command");
+        };
+        assert_eq!(mock.listen, "127.0.0.1:8000".parse().unwrap());
+        assert_eq!(mock.ttft, Duration::ZERO);
+        assert_eq!(mock.itl, Duration::from_millis(5));
+        assert_eq!(mock.error_rate, 0.25);
+        assert_eq!(mock.error_status, 500);
+
+        assert!(Args::try_parse_from(["llmnop", "mock-server", "--jitter", "1.5"]).is_err());
+        assert!(Args::try_parse_from(["llmnop", "mock-server", "--error-status", "200"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_headers_and_extra_body() {
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+            "--header",
+            "X-Tenant: team-a",
+            "--header",
+            "OpenAI-Organization:org-1",
+            "--extra-body",
+            r#"{"temperature":0,"chat_template_kwargs":{"enable_thinking":false}}"#,
+        ])
+        .expect("parse args");
+
+        let headers: Vec<(&str, &str)> = args
+            .benchmark
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.to_str().unwrap()))
+            .collect();
+        assert_eq!(
+            headers,
+            vec![("x-tenant", "team-a"), ("openai-organization", "org-1")]
+        );
+        let extra_body = args.benchmark.extra_body.unwrap();
+        assert_eq!(extra_body["temperature"], 0);
+        assert_eq!(extra_body["chat_template_kwargs"]["enable_thinking"], false);
+
+        assert!(parse_header("no-colon").is_err());
+        assert!(parse_header("bad name:x").is_err());
+        assert!(parse_extra_body("[1, 2]").is_err());
+        assert!(parse_extra_body("@/nonexistent/extra.json").is_err());
+    }
+
+    #[test]
+    fn test_sweep_requires_levels() {
+        let result = Args::try_parse_from([
+            "llmnop",
+            "sweep",
+            "--model",
+            "test-model",
+            "--url",
+            "http://x",
+        ]);
+        assert!(result.is_err());
     }
 
     #[cfg(feature = "self-update")]
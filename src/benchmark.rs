@@ -1,13 +1,19 @@
-use crate::args::ApiType;
+use crate::args::{ApiType, BenchmarkArgs};
 use crate::client::{
-    ResponsesStreamEvent, ResponsesUsage, create_chat_completion_stream, create_responses_stream,
+    AnthropicClient, AnthropicContentDelta, AnthropicStreamEvent, AnthropicUsage, ApiClients,
+    ChatMessage, OpenAiClient, ResponsesStreamEvent, ResponsesUsage,
+    create_anthropic_messages_stream, create_chat_completion_stream, create_completion_stream,
+    create_responses_stream,
 };
+use crate::error::{ErrorKind, RequestFailure};
+use crate::prompt::PrefixUse;
 use crate::tokens;
 use anyhow::{Result, anyhow};
 use async_openai::types::chat::CompletionUsage;
-use async_openai::{Client, config::OpenAIConfig};
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use serde::Serialize;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Serialize)]
@@ -21,18 +27,183 @@ pub struct BenchmarkResult {
     pub reasoning_tokens: u32,
     pub inter_token_latency_s: f64,
     pub inter_event_latency_s: f64,
+    /// Every gap between consecutive stream events, so stalls are not averaged away.
+    pub inter_event_gaps_s: Vec<f64>,
     pub total_tokens: u32,
+    /// When the first attempt was sent; `ttft` and `total_latency` describe the last one.
     pub request_start_unix_ns: u64,
     pub request_end_unix_ns: u64,
+    pub scheduled_start_unix_ns: Option<u64>,
+    pub conversation: Option<ConversationTurn>,
+    pub prefix: Option<PrefixUse>,
+    /// Output token limit sent with the request, if any.
+    pub requested_output_tokens: Option<u32>,
+    /// Why the server stopped generating, in OpenAI's terms (`stop`, `length`, ...).
+    pub finish_reason: Option<String>,
+    /// Attempts made, including the first; above 1 only with `--max-retries`.
+    pub attempts: u32,
+    /// Streamed reply text, kept so multi-turn sessions can append it to their history.
+    #[serde(skip)]
+    pub generated_text: String,
+}
+
+impl BenchmarkResult {
+    /// Longest pause between two stream events, typically a decode stall behind a prefill.
+    pub fn max_stall_s(&self) -> Option<f64> {
+        self.inter_event_gaps_s.iter().copied().reduce(f64::max)
+    }
+
+    /// When the first token arrived, counted back from the end so retries before it are skipped.
+    pub fn first_token_unix_ns(&self) -> u64 {
+        let streaming = self.total_latency.saturating_sub(self.ttft);
+        self.request_end_unix_ns
+            .saturating_sub(streaming.as_nanos() as u64)
+            .max(self.request_start_unix_ns)
+    }
+
+    /// Generated tokens, reasoning included, minus the requested output length.
+    pub fn output_length_deviation(&self) -> Option<i64> {
+        self.requested_output_tokens.map(|requested| {
+            i64::from(self.output_tokens + self.reasoning_tokens) - i64::from(requested)
+        })
+    }
+}
+
+/// Position of a request within a multi-turn session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ConversationTurn {
+    pub session_index: u32,
+    pub turn_index: u32,
 }
 
 #[derive(Debug, Clone)]
 pub struct BenchmarkRequest {
     pub model: String,
-    pub prompt: String,
+    pub messages: Vec<ChatMessage>,
     pub max_tokens: Option<u32>,
     pub tokenizer: String,
     pub use_server_token_count: bool,
+    /// Ask the server to keep generating until `max_tokens` is reached.
+    pub exact_output_tokens: bool,
+    pub timeouts: RequestTimeouts,
+    pub scheduled_start_unix_ns: Option<u64>,
+    pub conversation: Option<ConversationTurn>,
+    pub prefix: Option<PrefixUse>,
+}
+
+impl BenchmarkRequest {
+    /// Full conversation text used for client-side input token counting.
+    fn input_text(&self) -> String {
+        self.messages
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// `--extra-body` plus the sampling fields that pin the output length, where requested.
+    ///
+    /// `min_tokens` and `ignore_eos` are understood by vLLM and SGLang; explicit
+    /// `--extra-body` values win. Anthropic rejects unknown fields, so it is left alone.
+    fn body_overrides<'a>(
+        &self,
+        api: ApiType,
+        extra_body: &'a Map<String, Value>,
+    ) -> Cow<'a, Map<String, Value>> {
+        let pin_length = self.exact_output_tokens && !matches!(api, ApiType::AnthropicMessages);
+        let Some(max_tokens) = self.max_tokens.filter(|_| pin_length) else {
+            return Cow::Borrowed(extra_body);
+        };
+        let mut body = extra_body.clone();
+        body.entry("min_tokens")
+            .or_insert_with(|| Value::from(max_tokens));
+        body.entry("ignore_eos").or_insert(Value::Bool(true));
+        Cow::Owned(body)
+    }
+}
+
+/// Per-request limits; a request that hits one is cancelled and recorded as a `timeout` error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestTimeouts {
+    /// Longest wait for the first content or reasoning token.
+    pub ttft: Option<Duration>,
+    /// Longest time from sending the request to the end of its stream.
+    pub total: Option<Duration>,
+    /// Longest gap between stream events once the stream has started.
+    pub idle: Option<Duration>,
+}
+
+impl RequestTimeouts {
+    pub fn from_args(args: &BenchmarkArgs) -> Self {
+        Self {
+            ttft: args.ttft_timeout,
+            total: args.request_timeout,
+            idle: args.idle_timeout,
+        }
+    }
+}
+
+/// Applies [`RequestTimeouts`] while a request is sent and its stream is read.
+struct StreamTimer {
+    timeouts: RequestTimeouts,
+    start: Instant,
+    last_event: Option<Instant>,
+}
+
+impl StreamTimer {
+    fn new(timeouts: RequestTimeouts, start: Instant) -> Self {
+        Self {
+            timeouts,
+            start,
+            last_event: None,
+        }
+    }
+
+    /// The soonest deadline still in force, with the limit that set it.
+    fn deadline(&self, first_token_received: bool) -> Option<(Instant, &'static str, Duration)> {
+        let ttft = self
+            .timeouts
+            .ttft
+            .filter(|_| !first_token_received)
+            .map(|limit| (self.start + limit, "no first token within", limit));
+        let total = self
+            .timeouts
+            .total
+            .map(|limit| (self.start + limit, "request did not finish within", limit));
+        let idle = self
+            .timeouts
+            .idle
+            .zip(self.last_event)
+            .map(|(limit, last_event)| (last_event + limit, "no stream event within", limit));
+        [ttft, total, idle]
+            .into_iter()
+            .flatten()
+            .min_by_key(|(deadline, _, _)| *deadline)
+    }
+
+    /// Runs `future` to completion, or drops it once a deadline passes.
+    async fn limit<F: Future>(
+        &self,
+        first_token_received: bool,
+        future: F,
+    ) -> Result<F::Output, RequestFailure> {
+        let Some((deadline, limit_name, limit)) = self.deadline(first_token_received) else {
+            return Ok(future.await);
+        };
+        tokio::time::timeout_at(deadline.into(), future)
+            .await
+            .map_err(|_| RequestFailure::new(ErrorKind::Timeout, format!("{limit_name} {limit:?}")))
+    }
+
+    /// Next stream event; dropping the stream on a timeout closes the connection.
+    async fn next<S, T>(&mut self, stream: &mut S, first_token_received: bool) -> Result<Option<T>>
+    where
+        S: Stream<Item = Result<T>> + Unpin,
+    {
+        let event = self.limit(first_token_received, stream.next()).await?;
+        self.last_event = Some(Instant::now());
+        event.transpose()
+    }
 }
 
 struct TokenCounts {
@@ -43,18 +214,31 @@ struct TokenCounts {
 }
 
 pub async fn run_benchmark(
-    client: &Client<OpenAIConfig>,
+    clients: &ApiClients,
     api: ApiType,
-    request: BenchmarkRequest,
-) -> Result<BenchmarkResult> {
-    match api {
-        ApiType::Chat => run_chat_benchmark(client, &request).await,
-        ApiType::Responses => run_responses_benchmark(client, &request).await,
+    request: &BenchmarkRequest,
+) -> Result<BenchmarkResult, RequestFailure> {
+    let client = &clients.openai;
+    let extra_body = &*request.body_overrides(api, &clients.extra_body);
+    let mut result = match api {
+        ApiType::Chat => run_chat_benchmark(client, extra_body, request).await,
+        ApiType::Responses => run_responses_benchmark(client, extra_body, request).await,
+        ApiType::Completions => run_completions_benchmark(client, extra_body, request).await,
+        ApiType::AnthropicMessages => {
+            run_anthropic_benchmark(&clients.anthropic, extra_body, request).await
+        }
     }
+    .map_err(RequestFailure::from_anyhow)?;
+    result.scheduled_start_unix_ns = request.scheduled_start_unix_ns;
+    result.conversation = request.conversation;
+    result.prefix = request.prefix;
+    result.requested_output_tokens = request.max_tokens;
+    Ok(result)
 }
 
 async fn run_chat_benchmark(
-    client: &Client<OpenAIConfig>,
+    client: &OpenAiClient,
+    extra_body: &Map<String, Value>,
     request: &BenchmarkRequest,
 ) -> Result<BenchmarkResult> {
     let request_start_unix_ns = unix_time_now_ns();
@@ -64,22 +248,37 @@ async fn run_chat_benchmark(
     let mut generated_text = String::new();
     let mut reasoning_text = String::new();
     let mut usage: Option<CompletionUsage> = None;
-
-    let mut stream = create_chat_completion_stream(
-        client,
-        &request.model,
-        &request.prompt,
-        request.max_tokens,
-        request.use_server_token_count,
-    )
-    .await?;
-    while let Some(response_result) = stream.next().await {
-        let response = response_result?;
+    let mut finish_reason = None;
+
+    let mut timer = StreamTimer::new(request.timeouts, start_time);
+    let mut stream = timer
+        .limit(
+            false,
+            create_chat_completion_stream(
+                client,
+                &request.model,
+                &request.messages,
+                request.max_tokens,
+                request.use_server_token_count,
+                extra_body,
+            ),
+        )
+        .await??;
+    while let Some(response) = timer
+        .next(
+            &mut stream,
+            !content_arrivals.is_empty() || !reasoning_arrivals.is_empty(),
+        )
+        .await?
+    {
         if let Some(chunk_usage) = response.usage {
             usage = Some(chunk_usage);
         }
         for choice in response.choices {
             let now = Instant::now();
+            if let Some(reason) = &choice.finish_reason {
+                finish_reason = Some(normalize_finish_reason(reason));
+            }
 
             let reasoning = choice
                 .delta
@@ -108,13 +307,13 @@ async fn run_chat_benchmark(
     let token_counts = resolve_token_counts(
         request.use_server_token_count,
         usage_counts,
-        &request.prompt,
+        &request.input_text(),
         &generated_text,
         &reasoning_text,
         &request.tokenizer,
     )?;
 
-    Ok(process_benchmark_data_with_timestamps(
+    let mut result = process_benchmark_data_with_timestamps(
         start_time,
         end_time,
         &content_arrivals,
@@ -122,11 +321,15 @@ async fn run_chat_benchmark(
         &token_counts,
         request_start_unix_ns,
         request_end_unix_ns,
-    ))
+    );
+    result.generated_text = generated_text;
+    result.finish_reason = finish_reason;
+    Ok(result)
 }
 
 async fn run_responses_benchmark(
-    client: &Client<OpenAIConfig>,
+    client: &OpenAiClient,
+    extra_body: &Map<String, Value>,
     request: &BenchmarkRequest,
 ) -> Result<BenchmarkResult> {
     let request_start_unix_ns = unix_time_now_ns();
@@ -136,38 +339,75 @@ async fn run_responses_benchmark(
     let mut generated_text = String::new();
     let mut reasoning_text = String::new();
     let mut usage: Option<ResponsesUsage> = None;
-
-    let mut stream =
-        create_responses_stream(client, &request.model, &request.prompt, request.max_tokens)
-            .await?;
-    while let Some(event_result) = stream.next().await {
-        let event = event_result?;
+    let mut finish_reason = None;
+
+    let mut timer = StreamTimer::new(request.timeouts, start_time);
+    let mut stream = timer
+        .limit(
+            false,
+            create_responses_stream(
+                client,
+                &request.model,
+                &request.messages,
+                request.max_tokens,
+                extra_body,
+            ),
+        )
+        .await??;
+    while let Some(event) = timer
+        .next(
+            &mut stream,
+            !content_arrivals.is_empty() || !reasoning_arrivals.is_empty(),
+        )
+        .await?
+    {
         let now = Instant::now();
 
         match event {
-            ResponsesStreamEvent::OutputTextDelta { delta: Some(text) } => {
-                if !text.is_empty() {
-                    content_arrivals.push((now, text.clone()));
-                    generated_text.push_str(&text);
-                }
+            ResponsesStreamEvent::OutputTextDelta { delta: Some(text) } if !text.is_empty() => {
+                content_arrivals.push((now, text.clone()));
+                generated_text.push_str(&text);
             }
             ResponsesStreamEvent::ReasoningTextDelta { delta: Some(text) }
             | ResponsesStreamEvent::ReasoningSummaryTextDelta { delta: Some(text) }
-            | ResponsesStreamEvent::ReasoningDelta { delta: Some(text) } => {
-                if !text.is_empty() {
-                    reasoning_arrivals.push((now, text.clone()));
-                    reasoning_text.push_str(&text);
-                }
+            | ResponsesStreamEvent::ReasoningDelta { delta: Some(text) }
+                if !text.is_empty() =>
+            {
+                reasoning_arrivals.push((now, text.clone()));
+                reasoning_text.push_str(&text);
             }
             ResponsesStreamEvent::ResponseCompleted { response } => {
                 usage = response.and_then(|response| response.usage);
+                finish_reason = Some("stop".to_string());
+            }
+            ResponsesStreamEvent::ResponseIncomplete { response } => {
+                let response = response.unwrap_or_default();
+                finish_reason = Some(
+                    response
+                        .incomplete_details
+                        .and_then(|details| details.reason)
+                        .map_or_else(
+                            || "incomplete".to_string(),
+                            |reason| normalize_finish_reason(&reason),
+                        ),
+                );
+                usage = response.usage;
+            }
+            ResponsesStreamEvent::ResponseFailed { response } => {
+                let error = response.and_then(|response| response.error);
+                let message = error
+                    .as_ref()
+                    .and_then(|error| error.get("message"))
+                    .and_then(|value| value.as_str())
+                    .unwrap_or("response failed");
+                return Err(RequestFailure::new(ErrorKind::Stream, message).into());
             }
             ResponsesStreamEvent::Error { error } => {
                 let message = error
                     .get("message")
                     .and_then(|value| value.as_str())
                     .unwrap_or("unknown Responses API error");
-                return Err(anyhow::anyhow!("Responses API error: {}", message));
+                return Err(RequestFailure::new(ErrorKind::Stream, message).into());
             }
             _ => {}
         }
@@ -180,13 +420,202 @@ async fn run_responses_benchmark(
     let token_counts = resolve_token_counts(
         request.use_server_token_count,
         usage_counts,
-        &request.prompt,
+        &request.input_text(),
+        &generated_text,
+        &reasoning_text,
+        &request.tokenizer,
+    )?;
+
+    let mut result = process_benchmark_data_with_timestamps(
+        start_time,
+        end_time,
+        &content_arrivals,
+        &reasoning_arrivals,
+        &token_counts,
+        request_start_unix_ns,
+        request_end_unix_ns,
+    );
+    result.generated_text = generated_text;
+    result.finish_reason = finish_reason;
+    Ok(result)
+}
+
+async fn run_completions_benchmark(
+    client: &OpenAiClient,
+    extra_body: &Map<String, Value>,
+    request: &BenchmarkRequest,
+) -> Result<BenchmarkResult> {
+    let request_start_unix_ns = unix_time_now_ns();
+    let start_time = Instant::now();
+    let mut content_arrivals: Vec<(Instant, String)> = Vec::new();
+    let mut generated_text = String::new();
+    let mut usage: Option<CompletionUsage> = None;
+    let mut finish_reason = None;
+
+    // Raw completions have no roles, so multi-turn history is sent as a flat transcript.
+    let prompt = request.input_text();
+    let mut timer = StreamTimer::new(request.timeouts, start_time);
+    let mut stream = timer
+        .limit(
+            false,
+            create_completion_stream(
+                client,
+                &request.model,
+                &prompt,
+                request.max_tokens,
+                request.use_server_token_count,
+                extra_body,
+            ),
+        )
+        .await??;
+    while let Some(response) = timer
+        .next(&mut stream, !content_arrivals.is_empty())
+        .await?
+    {
+        if let Some(chunk_usage) = response.usage {
+            usage = Some(chunk_usage);
+        }
+        for choice in response.choices {
+            let now = Instant::now();
+            if let Some(reason) = &choice.finish_reason {
+                finish_reason = Some(normalize_finish_reason(reason));
+            }
+
+            let content = choice.text.as_deref().unwrap_or("");
+            if !content.is_empty() {
+                content_arrivals.push((now, content.to_string()));
+                generated_text.push_str(content);
+            }
+        }
+    }
+
+    let end_time = Instant::now();
+    let request_end_unix_ns = unix_time_now_ns();
+
+    let usage_counts = usage.as_ref().map(token_counts_from_chat_usage);
+    let token_counts = resolve_token_counts(
+        request.use_server_token_count,
+        usage_counts,
+        &prompt,
+        &generated_text,
+        "",
+        &request.tokenizer,
+    )?;
+
+    let mut result = process_benchmark_data_with_timestamps(
+        start_time,
+        end_time,
+        &content_arrivals,
+        &[],
+        &token_counts,
+        request_start_unix_ns,
+        request_end_unix_ns,
+    );
+    result.generated_text = generated_text;
+    result.finish_reason = finish_reason;
+    Ok(result)
+}
+
+async fn run_anthropic_benchmark(
+    client: &AnthropicClient,
+    extra_body: &Map<String, Value>,
+    request: &BenchmarkRequest,
+) -> Result<BenchmarkResult> {
+    let request_start_unix_ns = unix_time_now_ns();
+    let start_time = Instant::now();
+    let mut content_arrivals: Vec<(Instant, String)> = Vec::new();
+    let mut reasoning_arrivals: Vec<(Instant, String)> = Vec::new();
+    let mut generated_text = String::new();
+    let mut reasoning_text = String::new();
+    let mut usage = AnthropicUsage::default();
+    let mut finish_reason = None;
+
+    let mut timer = StreamTimer::new(request.timeouts, start_time);
+    let mut stream = timer
+        .limit(
+            false,
+            create_anthropic_messages_stream(
+                client,
+                &request.model,
+                &request.messages,
+                request.max_tokens,
+                extra_body,
+            ),
+        )
+        .await??;
+    while let Some(event) = timer
+        .next(
+            &mut stream,
+            !content_arrivals.is_empty() || !reasoning_arrivals.is_empty(),
+        )
+        .await?
+    {
+        let now = Instant::now();
+
+        match event {
+            AnthropicStreamEvent::MessageStart { message } => {
+                if let Some(start_usage) = message.usage {
+                    usage = start_usage;
+                }
+            }
+            AnthropicStreamEvent::ContentBlockDelta {
+                delta: AnthropicContentDelta::TextDelta { text },
+            } if !text.is_empty() => {
+                content_arrivals.push((now, text.clone()));
+                generated_text.push_str(&text);
+            }
+            AnthropicStreamEvent::ContentBlockDelta {
+                delta: AnthropicContentDelta::ThinkingDelta { thinking },
+            } if !thinking.is_empty() => {
+                reasoning_arrivals.push((now, thinking.clone()));
+                reasoning_text.push_str(&thinking);
+            }
+            // message_delta usage is cumulative and may repeat or omit the input counts.
+            AnthropicStreamEvent::MessageDelta {
+                delta,
+                usage: delta_usage,
+            } => {
+                if let Some(stop_reason) = delta.and_then(|delta| delta.stop_reason) {
+                    finish_reason = Some(normalize_finish_reason(&stop_reason));
+                }
+                let Some(delta_usage) = delta_usage else {
+                    continue;
+                };
+                usage = AnthropicUsage {
+                    input_tokens: delta_usage.input_tokens.or(usage.input_tokens),
+                    output_tokens: delta_usage.output_tokens.or(usage.output_tokens),
+                    cache_creation_input_tokens: delta_usage
+                        .cache_creation_input_tokens
+                        .or(usage.cache_creation_input_tokens),
+                    cache_read_input_tokens: delta_usage
+                        .cache_read_input_tokens
+                        .or(usage.cache_read_input_tokens),
+                };
+            }
+            AnthropicStreamEvent::Error { error } => {
+                let message = error
+                    .get("message")
+                    .and_then(|value| value.as_str())
+                    .unwrap_or("unknown Anthropic API error");
+                return Err(RequestFailure::new(ErrorKind::Stream, message).into());
+            }
+            _ => {}
+        }
+    }
+
+    let end_time = Instant::now();
+    let request_end_unix_ns = unix_time_now_ns();
+
+    let token_counts = resolve_token_counts(
+        request.use_server_token_count,
+        token_counts_from_anthropic_usage(&usage),
+        &request.input_text(),
         &generated_text,
         &reasoning_text,
         &request.tokenizer,
     )?;
 
-    Ok(process_benchmark_data_with_timestamps(
+    let mut result = process_benchmark_data_with_timestamps(
         start_time,
         end_time,
         &content_arrivals,
@@ -194,7 +623,23 @@ async fn run_responses_benchmark(
         &token_counts,
         request_start_unix_ns,
         request_end_unix_ns,
-    ))
+    );
+    result.generated_text = generated_text;
+    result.finish_reason = finish_reason;
+    Ok(result)
+}
+
+/// Maps Anthropic and Responses stop reasons onto OpenAI's `stop`, `length`,
+/// `content_filter` and `tool_calls`. Unknown reasons are kept as reported.
+fn normalize_finish_reason(reason: &str) -> String {
+    match reason {
+        "end_turn" | "stop_sequence" => "stop",
+        "max_tokens" | "max_output_tokens" => "length",
+        "tool_use" | "function_call" => "tool_calls",
+        "refusal" => "content_filter",
+        other => other,
+    }
+    .to_string()
 }
 
 fn token_counts_from_chat_usage(usage: &CompletionUsage) -> TokenCounts {
@@ -232,6 +677,22 @@ fn token_counts_from_responses_usage(usage: &ResponsesUsage) -> Option<TokenCoun
     })
 }
 
+fn token_counts_from_anthropic_usage(usage: &AnthropicUsage) -> Option<TokenCounts> {
+    // Cached prompt tokens are reported separately from input_tokens but still count as input.
+    let input = usage.input_tokens?
+        + usage.cache_creation_input_tokens.unwrap_or(0)
+        + usage.cache_read_input_tokens.unwrap_or(0);
+    let output = usage.output_tokens?;
+
+    // Thinking tokens are billed as output and not broken out in usage.
+    Some(TokenCounts {
+        input,
+        output,
+        reasoning: 0,
+        total: input + output,
+    })
+}
+
 fn resolve_token_counts(
     use_server_token_count: bool,
     usage_counts: Option<TokenCounts>,
@@ -244,6 +705,7 @@ fn resolve_token_counts(
         usage_counts.ok_or_else(|| anyhow!("server did not return token usage"))
     } else {
         compute_token_counts(prompt, generated_text, reasoning_text, tokenizer)
+            .map_err(|err| RequestFailure::new(ErrorKind::Tokenizer, format!("{err:#}")).into())
     }
 }
 
@@ -371,9 +833,20 @@ fn process_benchmark_data_with_timestamps(
         reasoning_tokens: tokens.reasoning,
         inter_token_latency_s,
         inter_event_latency_s,
+        inter_event_gaps_s: time_to_next_event
+            .iter()
+            .map(Duration::as_secs_f64)
+            .collect(),
         total_tokens: tokens.total,
         request_start_unix_ns,
         request_end_unix_ns,
+        scheduled_start_unix_ns: None,
+        conversation: None,
+        prefix: None,
+        requested_output_tokens: None,
+        finish_reason: None,
+        attempts: 1,
+        generated_text: String::new(),
     }
 }
 
@@ -432,6 +905,51 @@ mod tests {
         assert_eq!(result.inter_event_latency_s, 0.064);
     }
 
+    #[test]
+    fn test_inter_event_gaps_keep_stalls() {
+        let now = Instant::now();
+        let content_arrivals = vec![
+            (now + Duration::from_millis(10), "a".to_string()),
+            (now + Duration::from_millis(20), "b".to_string()),
+            (now + Duration::from_millis(520), "c".to_string()),
+            (now + Duration::from_millis(530), "d".to_string()),
+        ];
+        let tokens = TokenCounts {
+            input: 10,
+            output: 4,
+            reasoning: 0,
+            total: 14,
+        };
+
+        let result = process_benchmark_data(
+            now,
+            now + Duration::from_millis(530),
+            &content_arrivals,
+            &[],
+            &tokens,
+        );
+
+        assert_eq!(result.inter_event_gaps_s.len(), 3);
+        assert!((result.inter_event_gaps_s[1] - 0.5).abs() < 1e-9);
+        assert!((result.max_stall_s().unwrap() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_max_stall_is_none_without_gaps() {
+        let now = Instant::now();
+        let tokens = TokenCounts {
+            input: 10,
+            output: 1,
+            reasoning: 0,
+            total: 11,
+        };
+        let content_arrivals = vec![(now + Duration::from_millis(10), "a".to_string())];
+
+        let result = process_benchmark_data(now, now, &content_arrivals, &[], &tokens);
+        assert!(result.inter_event_gaps_s.is_empty());
+        assert_eq!(result.max_stall_s(), None);
+    }
+
     #[test]
     fn test_throughput_generation_window_example() {
         // chunks arrive at T=[1.0s, 1.2s, 1.5s], output_tokens=30
@@ -816,4 +1334,113 @@ mod tests {
         assert_eq!(counts.reasoning, 1);
         assert_eq!(counts.total, 13);
     }
+
+    #[test]
+    fn test_token_counts_from_anthropic_usage_includes_cached_input() {
+        let usage = AnthropicUsage {
+            input_tokens: Some(12),
+            output_tokens: Some(40),
+            cache_creation_input_tokens: Some(100),
+            cache_read_input_tokens: Some(2000),
+        };
+
+        let counts = token_counts_from_anthropic_usage(&usage).expect("counts");
+        assert_eq!(counts.input, 2112);
+        assert_eq!(counts.output, 40);
+        assert_eq!(counts.reasoning, 0);
+        assert_eq!(counts.total, 2152);
+    }
+
+    #[test]
+    fn test_token_counts_from_anthropic_usage_requires_output() {
+        let usage = AnthropicUsage {
+            input_tokens: Some(12),
+            ..AnthropicUsage::default()
+        };
+
+        assert!(token_counts_from_anthropic_usage(&usage).is_none());
+    }
+
+    #[test]
+    fn test_exact_output_tokens_pins_length_without_overriding_extra_body() {
+        let mut request = BenchmarkRequest {
+            model: "m".to_string(),
+            messages: vec![ChatMessage::user("hi")],
+            max_tokens: Some(64),
+            tokenizer: "t".to_string(),
+            use_server_token_count: true,
+            exact_output_tokens: true,
+            timeouts: RequestTimeouts::default(),
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: None,
+        };
+        let mut extra_body = Map::new();
+        extra_body.insert("ignore_eos".to_string(), Value::Bool(false));
+
+        let body = request.body_overrides(ApiType::Chat, &extra_body);
+        assert_eq!(body["min_tokens"], 64);
+        assert_eq!(body["ignore_eos"], false);
+
+        assert!(matches!(
+            request.body_overrides(ApiType::AnthropicMessages, &extra_body),
+            Cow::Borrowed(_)
+        ));
+        request.max_tokens = None;
+        assert!(matches!(
+            request.body_overrides(ApiType::Completions, &extra_body),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_normalize_finish_reason_across_apis() {
+        for (reason, expected) in [
+            ("stop", "stop"),
+            ("end_turn", "stop"),
+            ("length", "length"),
+            ("max_tokens", "length"),
+            ("max_output_tokens", "length"),
+            ("tool_use", "tool_calls"),
+            ("refusal", "content_filter"),
+            ("content_filter", "content_filter"),
+            ("pause_turn", "pause_turn"),
+        ] {
+            assert_eq!(normalize_finish_reason(reason), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_timer_applies_the_soonest_deadline() {
+        let start = Instant::now();
+        let mut timer = StreamTimer::new(
+            RequestTimeouts {
+                ttft: Some(Duration::from_millis(20)),
+                total: Some(Duration::from_secs(60)),
+                idle: Some(Duration::from_secs(1)),
+            },
+            start,
+        );
+        assert_eq!(
+            timer.deadline(false).unwrap().0,
+            start + Duration::from_millis(20)
+        );
+        // Once a token arrives the TTFT limit no longer applies, and idle only counts between events.
+        assert_eq!(
+            timer.deadline(true).unwrap().0,
+            start + Duration::from_secs(60)
+        );
+
+        let mut stream = futures::stream::pending::<Result<()>>();
+        let err = timer.next(&mut stream, false).await.unwrap_err();
+        let failure = RequestFailure::from_anyhow(err);
+        assert_eq!(failure.kind, ErrorKind::Timeout);
+        assert_eq!(failure.message, "no first token within 20ms");
+
+        timer.last_event = Some(start);
+        assert_eq!(
+            timer.deadline(true).unwrap().0,
+            start + Duration::from_secs(1)
+        );
+    }
 }
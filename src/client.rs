@@ -1,14 +1,112 @@
-use anyhow::{Context, Result, anyhow};
+use crate::error::{ErrorKind, RequestFailure, error_message, retry_after};
+use anyhow::{Context, Result};
 use async_openai::types::chat::{
+    ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage,
     ChatCompletionRequestUserMessageArgs, ChatCompletionStreamOptions, CompletionUsage,
     CreateChatCompletionRequestArgs,
 };
-use async_openai::{Client, config::OpenAIConfig};
-use futures::{Stream, StreamExt};
+use eventsource_stream::{Event, EventStreamError, Eventsource};
+use futures::{Stream, StreamExt, future};
 use serde::Deserialize;
-use serde_json::Value;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
 use std::pin::Pin;
 
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+// The Messages API requires max_tokens, so runs without --mean-output-tokens need a ceiling.
+const ANTHROPIC_DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// HTTP clients for every supported API, built once per target so requests share connections.
+pub struct ApiClients {
+    pub openai: OpenAiClient,
+    pub anthropic: AnthropicClient,
+    /// `--extra-body` fields merged into every request body.
+    pub extra_body: Map<String, Value>,
+}
+
+/// Client for OpenAI-compatible endpoints. Streams are read directly rather than through
+/// async-openai so failures keep their HTTP status and `Retry-After` header.
+pub struct OpenAiClient {
+    http: reqwest::Client,
+    api_base: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiClient {
+    pub fn new(http: reqwest::Client, api_base: &str, api_key: Option<String>) -> Self {
+        Self {
+            http,
+            api_base: api_base.trim_end_matches('/').to_string(),
+            api_key: api_key.or_else(|| std::env::var("OPENAI_API_KEY").ok()),
+        }
+    }
+
+    fn post(&self, path: &str, request: &Value) -> reqwest::RequestBuilder {
+        let builder = self
+            .http
+            .post(format!("{}/{path}", self.api_base))
+            .json(request);
+        match &self.api_key {
+            Some(api_key) => builder.bearer_auth(api_key),
+            None => builder,
+        }
+    }
+}
+
+/// Minimal client for Anthropic's Messages API, which async-openai does not cover.
+pub struct AnthropicClient {
+    http: reqwest::Client,
+    api_base: String,
+    api_key: Option<String>,
+}
+
+impl AnthropicClient {
+    pub fn new(http: reqwest::Client, api_base: &str, api_key: Option<String>) -> Self {
+        Self {
+            http,
+            api_base: api_base.trim_end_matches('/').to_string(),
+            api_key: api_key.or_else(|| std::env::var("ANTHROPIC_API_KEY").ok()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatRole {
+    User,
+    Assistant,
+}
+
+impl ChatRole {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::User,
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct StreamDelta {
     pub content: Option<String>,
@@ -19,6 +117,8 @@ pub struct StreamDelta {
 #[derive(Debug, Deserialize)]
 pub struct StreamChoice {
     pub delta: StreamDelta,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -27,6 +127,20 @@ pub struct StreamChunk {
     pub usage: Option<CompletionUsage>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CompletionStreamChoice {
+    pub text: Option<String>,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompletionStreamChunk {
+    #[serde(default)]
+    pub choices: Vec<CompletionStreamChoice>,
+    pub usage: Option<CompletionUsage>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
 pub enum ResponsesStreamEvent {
@@ -52,6 +166,33 @@ pub enum ResponsesStreamEvent {
     },
     #[serde(rename = "response.completed")]
     ResponseCompleted { response: Option<ResponseCompleted> },
+    #[serde(rename = "response.incomplete")]
+    ResponseIncomplete { response: Option<ResponseCompleted> },
+    #[serde(rename = "response.failed")]
+    ResponseFailed { response: Option<ResponseCompleted> },
+    #[serde(rename = "error")]
+    Error {
+        #[serde(default)]
+        error: Value,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum AnthropicStreamEvent {
+    #[serde(rename = "message_start")]
+    MessageStart { message: AnthropicMessageStart },
+    #[serde(rename = "content_block_delta")]
+    ContentBlockDelta { delta: AnthropicContentDelta },
+    #[serde(rename = "message_delta")]
+    MessageDelta {
+        #[serde(default)]
+        delta: Option<AnthropicMessageDelta>,
+        #[serde(default)]
+        usage: Option<AnthropicUsage>,
+    },
     #[serde(rename = "error")]
     Error {
         #[serde(default)]
@@ -62,8 +203,48 @@ pub enum ResponsesStreamEvent {
 }
 
 #[derive(Debug, Deserialize)]
+pub struct AnthropicMessageStart {
+    #[serde(default)]
+    pub usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnthropicMessageDelta {
+    pub stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum AnthropicContentDelta {
+    #[serde(rename = "text_delta")]
+    TextDelta { text: String },
+    #[serde(rename = "thinking_delta")]
+    ThinkingDelta { thinking: String },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct AnthropicUsage {
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub cache_creation_input_tokens: Option<u32>,
+    pub cache_read_input_tokens: Option<u32>,
+}
+
+/// Final `response` object of a completed, incomplete or failed Responses stream.
+#[derive(Debug, Default, Deserialize)]
 pub struct ResponseCompleted {
     pub usage: Option<ResponsesUsage>,
+    #[serde(default)]
+    pub incomplete_details: Option<ResponseIncompleteDetails>,
+    #[serde(default)]
+    pub error: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResponseIncompleteDetails {
+    pub reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -80,22 +261,37 @@ pub struct ResponsesOutputTokensDetails {
     pub reasoning_tokens: Option<u32>,
 }
 
+type EventStream<T> = Pin<Box<dyn Stream<Item = Result<T, anyhow::Error>> + Send>>;
+
 pub async fn create_chat_completion_stream(
-    client: &Client<OpenAIConfig>,
+    client: &OpenAiClient,
     model: &str,
-    prompt: &str,
+    messages: &[ChatMessage],
     max_tokens: Option<u32>,
     include_usage: bool,
-) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, anyhow::Error>> + Send>>> {
+    extra_body: &Map<String, Value>,
+) -> Result<EventStream<StreamChunk>> {
+    let messages = messages
+        .iter()
+        .map(|message| -> Result<ChatCompletionRequestMessage> {
+            let message = match message.role {
+                ChatRole::User => ChatCompletionRequestUserMessageArgs::default()
+                    .content(message.content.as_str())
+                    .build()
+                    .context("Failed to build message")?
+                    .into(),
+                ChatRole::Assistant => ChatCompletionRequestAssistantMessageArgs::default()
+                    .content(message.content.as_str())
+                    .build()
+                    .context("Failed to build message")?
+                    .into(),
+            };
+            Ok(message)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
     let mut builder = CreateChatCompletionRequestArgs::default();
-    builder
-        .model(model)
-        .stream(true)
-        .messages([ChatCompletionRequestUserMessageArgs::default()
-            .content(prompt)
-            .build()
-            .context("Failed to build message")?
-            .into()]);
+    builder.model(model).stream(true).messages(messages);
 
     if let Some(tokens) = max_tokens {
         builder.max_completion_tokens(tokens);
@@ -108,52 +304,223 @@ pub async fn create_chat_completion_stream(
         });
     }
 
-    let request = builder.build().context("Failed to build request")?;
+    let mut request = serde_json::to_value(builder.build().context("Failed to build request")?)?;
+    merge_extra_body(&mut request, extra_body);
 
-    let stream = client
-        .chat()
-        .create_stream_byot::<_, StreamChunk>(request)
-        .await
-        .map_err(|err| anyhow!("OpenAI error: {:?}", err))?;
+    event_stream(client.post("chat/completions", &request)).await
+}
 
-    let mapped_stream =
-        stream.map(|chunk_result| chunk_result.map_err(|err| anyhow!("OpenAI error: {:?}", err)));
+pub async fn create_completion_stream(
+    client: &OpenAiClient,
+    model: &str,
+    prompt: &str,
+    max_tokens: Option<u32>,
+    include_usage: bool,
+    extra_body: &Map<String, Value>,
+) -> Result<EventStream<CompletionStreamChunk>> {
+    let mut request = serde_json::json!({
+        "model": model,
+        "prompt": prompt,
+        "stream": true,
+    });
+
+    if let Some(tokens) = max_tokens {
+        request["max_tokens"] = Value::from(tokens);
+    }
+
+    if include_usage {
+        request["stream_options"] = serde_json::json!({ "include_usage": true });
+    }
+    merge_extra_body(&mut request, extra_body);
 
-    Ok(Box::pin(mapped_stream))
+    event_stream(client.post("completions", &request)).await
 }
 
 pub async fn create_responses_stream(
-    client: &Client<OpenAIConfig>,
+    client: &OpenAiClient,
     model: &str,
-    prompt: &str,
+    messages: &[ChatMessage],
     max_tokens: Option<u32>,
-) -> Result<Pin<Box<dyn Stream<Item = Result<ResponsesStreamEvent, anyhow::Error>> + Send>>> {
+    extra_body: &Map<String, Value>,
+) -> Result<EventStream<ResponsesStreamEvent>> {
     let mut request = serde_json::json!({
         "model": model,
-        "input": prompt,
+        "input": responses_input(messages),
         "stream": true,
     });
 
     if let Some(tokens) = max_tokens {
         request["max_output_tokens"] = Value::from(tokens);
     }
+    merge_extra_body(&mut request, extra_body);
+
+    event_stream(client.post("responses", &request)).await
+}
 
-    let stream = client
-        .responses()
-        .create_stream_byot::<_, ResponsesStreamEvent>(request)
+pub async fn create_anthropic_messages_stream(
+    client: &AnthropicClient,
+    model: &str,
+    messages: &[ChatMessage],
+    max_tokens: Option<u32>,
+    extra_body: &Map<String, Value>,
+) -> Result<EventStream<AnthropicStreamEvent>> {
+    let messages: Vec<Value> = messages
+        .iter()
+        .map(|message| {
+            serde_json::json!({
+                "role": message.role.as_str(),
+                "content": message.content,
+            })
+        })
+        .collect();
+    let mut request = serde_json::json!({
+        "model": model,
+        "messages": messages,
+        "max_tokens": max_tokens.unwrap_or(ANTHROPIC_DEFAULT_MAX_TOKENS),
+        "stream": true,
+    });
+    merge_extra_body(&mut request, extra_body);
+
+    let mut builder = client
+        .http
+        .post(format!("{}/messages", client.api_base))
+        .header("anthropic-version", ANTHROPIC_VERSION)
+        .json(&request);
+    if let Some(api_key) = &client.api_key {
+        builder = builder.header("x-api-key", api_key);
+    }
+
+    event_stream(builder).await
+}
+
+/// Sends the request and parses each server-sent event as `T`, ending at `[DONE]`.
+///
+/// Every failure is a [`RequestFailure`], so callers can tell a 429 from a dropped stream.
+async fn event_stream<T: DeserializeOwned + Send + 'static>(
+    request: reqwest::RequestBuilder,
+) -> Result<EventStream<T>> {
+    let response = request
+        .send()
         .await
-        .map_err(|err| anyhow!("OpenAI error: {:?}", err))?;
+        .map_err(|err| RequestFailure::from_reqwest(&err, false))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let retry_after = retry_after(response.headers());
+        let body = response.text().await.unwrap_or_default();
+        return Err(RequestFailure::from_status(status, retry_after, &body).into());
+    }
+
+    let events = response
+        .bytes_stream()
+        .eventsource()
+        .take_while(|event| future::ready(!matches!(event, Ok(event) if event.data == "[DONE]")))
+        .map(|event_result| {
+            let event = event_result.map_err(|err| match err {
+                EventStreamError::Transport(err) => RequestFailure::from_reqwest(&err, true),
+                err => RequestFailure::new(ErrorKind::Deserialization, err.to_string()),
+            })?;
+            parse_event(&event)
+        });
+
+    Ok(Box::pin(events))
+}
 
-    let mapped_stream =
-        stream.map(|chunk_result| chunk_result.map_err(|err| anyhow!("OpenAI error: {:?}", err)));
+fn parse_event<T: DeserializeOwned>(event: &Event) -> Result<T> {
+    serde_json::from_str(&event.data).map_err(|err| {
+        // Some servers report failures mid-stream as a bare `{"error": ...}` event.
+        let failure = match serde_json::from_str::<Value>(&event.data) {
+            Ok(value) if value.get("error").is_some() => {
+                RequestFailure::new(ErrorKind::Stream, error_message(&event.data))
+            }
+            _ => RequestFailure::new(
+                ErrorKind::Deserialization,
+                format!("Failed to parse '{}' event: {err}", event.event),
+            ),
+        };
+        failure.into()
+    })
+}
 
-    Ok(Box::pin(mapped_stream))
+/// Top-level `--extra-body` fields replace the ones llmnop sets, except `stream`.
+fn merge_extra_body(request: &mut Value, extra_body: &Map<String, Value>) {
+    let Some(fields) = request.as_object_mut() else {
+        return;
+    };
+    for (key, value) in extra_body {
+        if key != "stream" {
+            fields.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// A lone user message is sent as plain text; conversations become a list of role/content items.
+fn responses_input(messages: &[ChatMessage]) -> Value {
+    match messages {
+        [message] if message.role == ChatRole::User => Value::from(message.content.as_str()),
+        _ => Value::Array(
+            messages
+                .iter()
+                .map(|message| {
+                    serde_json::json!({
+                        "role": message.role.as_str(),
+                        "content": message.content,
+                    })
+                })
+                .collect(),
+        ),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_merge_extra_body_overrides_fields_but_keeps_streaming() {
+        let mut request = serde_json::json!({"model": "m", "stream": true, "max_tokens": 10});
+        let extra_body = serde_json::json!({"max_tokens": 20, "ignore_eos": true, "stream": false});
+        merge_extra_body(&mut request, extra_body.as_object().unwrap());
+        assert_eq!(
+            request,
+            serde_json::json!({"model": "m", "stream": true, "max_tokens": 20, "ignore_eos": true})
+        );
+    }
+
+    #[test]
+    fn test_responses_input_single_prompt_is_text() {
+        let input = responses_input(&[ChatMessage::user("hello")]);
+        assert_eq!(input, Value::from("hello"));
+    }
+
+    #[test]
+    fn test_responses_input_conversation_is_message_list() {
+        let input = responses_input(&[
+            ChatMessage::user("hello"),
+            ChatMessage::assistant("hi"),
+            ChatMessage::user("how are you?"),
+        ]);
+        assert_eq!(
+            input,
+            serde_json::json!([
+                {"role": "user", "content": "hello"},
+                {"role": "assistant", "content": "hi"},
+                {"role": "user", "content": "how are you?"},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_stream_chunk_finish_reason_deserialize() {
+        let chunk: StreamChunk = serde_json::from_str(
+            r#"{"choices":[{"index":0,"delta":{},"finish_reason":"length"}]}"#,
+        )
+        .expect("deserialize chunk");
+
+        assert_eq!(chunk.choices[0].finish_reason.as_deref(), Some("length"));
+        assert!(chunk.usage.is_none());
+    }
+
     #[test]
     fn test_stream_chunk_usage_deserialize() {
         let chunk: StreamChunk = serde_json::from_str(
@@ -167,6 +534,108 @@ mod tests {
         assert_eq!(usage.total_tokens, 10);
     }
 
+    #[test]
+    fn test_completion_stream_chunk_deserialize() {
+        let chunk: CompletionStreamChunk = serde_json::from_str(
+            r#"{"id":"cmpl-1","object":"text_completion","choices":[{"index":0,"text":" world","finish_reason":null}]}"#,
+        )
+        .expect("deserialize chunk");
+
+        assert_eq!(chunk.choices.len(), 1);
+        assert_eq!(chunk.choices[0].text.as_deref(), Some(" world"));
+        assert!(chunk.usage.is_none());
+    }
+
+    #[test]
+    fn test_completion_stream_usage_chunk_deserialize() {
+        let chunk: CompletionStreamChunk = serde_json::from_str(
+            r#"{"choices":[],"usage":{"prompt_tokens":5,"completion_tokens":7,"total_tokens":12}}"#,
+        )
+        .expect("deserialize chunk");
+
+        let usage = chunk.usage.expect("usage");
+        assert_eq!(usage.prompt_tokens, 5);
+        assert_eq!(usage.completion_tokens, 7);
+    }
+
+    #[test]
+    fn test_anthropic_message_start_deserialize() {
+        let event: AnthropicStreamEvent = serde_json::from_str(
+            r#"{"type":"message_start","message":{"id":"msg_1","role":"assistant","content":[],"usage":{"input_tokens":25,"output_tokens":1,"cache_read_input_tokens":10}}}"#,
+        )
+        .expect("deserialize event");
+
+        match event {
+            AnthropicStreamEvent::MessageStart { message } => {
+                let usage = message.usage.expect("usage");
+                assert_eq!(usage.input_tokens, Some(25));
+                assert_eq!(usage.cache_read_input_tokens, Some(10));
+            }
+            _ => panic!("unexpected event variant"),
+        }
+    }
+
+    #[test]
+    fn test_anthropic_content_block_deltas_deserialize() {
+        let text: AnthropicStreamEvent = serde_json::from_str(
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"Hello"}}"#,
+        )
+        .expect("deserialize event");
+        let thinking: AnthropicStreamEvent = serde_json::from_str(
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Let me"}}"#,
+        )
+        .expect("deserialize event");
+        let signature: AnthropicStreamEvent = serde_json::from_str(
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"abc"}}"#,
+        )
+        .expect("deserialize event");
+
+        assert!(matches!(
+            text,
+            AnthropicStreamEvent::ContentBlockDelta {
+                delta: AnthropicContentDelta::TextDelta { ref text }
+            } if text == "Hello"
+        ));
+        assert!(matches!(
+            thinking,
+            AnthropicStreamEvent::ContentBlockDelta {
+                delta: AnthropicContentDelta::ThinkingDelta { ref thinking }
+            } if thinking == "Let me"
+        ));
+        assert!(matches!(
+            signature,
+            AnthropicStreamEvent::ContentBlockDelta {
+                delta: AnthropicContentDelta::Other
+            }
+        ));
+    }
+
+    #[test]
+    fn test_anthropic_message_delta_usage_deserialize() {
+        let event: AnthropicStreamEvent = serde_json::from_str(
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn","stop_sequence":null},"usage":{"output_tokens":15}}"#,
+        )
+        .expect("deserialize event");
+
+        match event {
+            AnthropicStreamEvent::MessageDelta { delta, usage } => {
+                assert_eq!(
+                    delta.and_then(|delta| delta.stop_reason).as_deref(),
+                    Some("end_turn")
+                );
+                assert_eq!(usage.and_then(|usage| usage.output_tokens), Some(15));
+            }
+            _ => panic!("unexpected event variant"),
+        }
+    }
+
+    #[test]
+    fn test_anthropic_ping_is_other() {
+        let event: AnthropicStreamEvent =
+            serde_json::from_str(r#"{"type":"ping"}"#).expect("deserialize event");
+        assert!(matches!(event, AnthropicStreamEvent::Other));
+    }
+
     #[test]
     fn test_output_text_delta_deserialize() {
         let event: ResponsesStreamEvent =
@@ -276,4 +745,30 @@ mod tests {
             _ => panic!("unexpected event variant"),
         }
     }
+
+    #[test]
+    fn test_response_incomplete_deserialize() {
+        let event: ResponsesStreamEvent = serde_json::from_str(
+            r#"{"type":"response.incomplete","response":{"status":"incomplete","incomplete_details":{"reason":"max_output_tokens"},"usage":{"input_tokens":3,"output_tokens":16}}}"#,
+        )
+        .expect("deserialize event");
+
+        match event {
+            ResponsesStreamEvent::ResponseIncomplete {
+                response: Some(response),
+            } => {
+                assert_eq!(
+                    response
+                        .incomplete_details
+                        .and_then(|details| details.reason),
+                    Some("max_output_tokens".to_string())
+                );
+                assert_eq!(
+                    response.usage.and_then(|usage| usage.output_tokens),
+                    Some(16)
+                );
+            }
+            _ => panic!("unexpected event variant"),
+        }
+    }
 }
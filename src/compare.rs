@@ -0,0 +1,662 @@
+use crate::args::{CompareArgs, CompareStat};
+use crate::output::{
+    BenchmarkSummary, MetricStats, RequestRecord, bootstrap_ci, default_results_dir, mann_whitney_u,
+};
+use anyhow::{Context, Result, bail};
+use comfy_table::{
+    Attribute, Cell, CellAlignment, Color, ContentArrangement, Table, presets::UTF8_FULL_CONDENSED,
+};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A saved run loaded back from disk.
+pub struct SavedRun {
+    pub dir: PathBuf,
+    pub summary: BenchmarkSummary,
+    /// Per-request metric values from successful profiling requests, keyed by metric name.
+    pub samples: BTreeMap<String, Vec<f64>>,
+}
+
+impl CompareStat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompareStat::Avg => "avg",
+            CompareStat::Min => "min",
+            CompareStat::P50 => "p50",
+            CompareStat::P90 => "p90",
+            CompareStat::P95 => "p95",
+            CompareStat::P99 => "p99",
+            CompareStat::Max => "max",
+        }
+    }
+
+    pub fn value(&self, stats: &MetricStats) -> Option<f64> {
+        match self {
+            CompareStat::Avg => stats.avg,
+            CompareStat::Min => stats.min,
+            CompareStat::P50 => stats.p50,
+            CompareStat::P90 => stats.p90,
+            CompareStat::P95 => stats.p95,
+            CompareStat::P99 => stats.p99,
+            CompareStat::Max => stats.max,
+        }
+    }
+
+    fn quantile(&self) -> Option<f64> {
+        match self {
+            CompareStat::P50 => Some(0.50),
+            CompareStat::P99 => Some(0.99),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    LowerIsBetter,
+    HigherIsBetter,
+    Neutral,
+}
+
+pub fn direction(metric: &str) -> Direction {
+    match metric {
+        "request_latency"
+        | "time_to_first_token"
+        | "time_to_first_output_token"
+        | "inter_token_latency"
+        | "inter_event_latency"
+        | "inter_event_gap"
+        | "max_stall"
+        | "scheduler_lag"
+        | "time_to_first_token_prefix_hit"
+        | "time_to_first_token_prefix_miss"
+        | "error_request_count"
+        | "error_rate" => Direction::LowerIsBetter,
+        "successful_request_count"
+        | "request_throughput"
+        | "slo_attainment"
+        | "goodput_request_throughput"
+        | "goodput_token_throughput"
+        | "output_token_throughput_per_request"
+        | "output_token_throughput"
+        | "total_token_throughput" => Direction::HigherIsBetter,
+        _ => Direction::Neutral,
+    }
+}
+
+/// One metric statistic lined up across every compared run.
+#[derive(Debug, Clone, PartialEq)]
+struct ComparisonRow {
+    metric: &'static str,
+    stat: CompareStat,
+    unit: String,
+    values: Vec<Option<f64>>,
+    /// Bootstrap confidence interval of each value, for p50 and p99 rows.
+    intervals: Vec<Option<[f64; 2]>>,
+    /// Mann-Whitney U p-value of each run's samples against the baseline's.
+    p_values: Vec<Option<f64>>,
+}
+
+impl ComparisonRow {
+    /// Change of run `index` relative to the first run, as absolute and percentage deltas.
+    fn delta(&self, index: usize) -> Option<(f64, Option<f64>)> {
+        let baseline = self.values.first().copied().flatten()?;
+        let value = self.values.get(index).copied().flatten()?;
+        let absolute = value - baseline;
+        let percent = (baseline != 0.0).then(|| absolute / baseline.abs() * 100.0);
+        Some((absolute, percent))
+    }
+
+    fn is_regression(&self, absolute: f64) -> Option<bool> {
+        match direction(self.metric) {
+            _ if absolute == 0.0 => None,
+            Direction::LowerIsBetter => Some(absolute > 0.0),
+            Direction::HigherIsBetter => Some(absolute < 0.0),
+            Direction::Neutral => None,
+        }
+    }
+
+    /// Whether run `index` changed from the baseline, or `None` when there are no samples to test.
+    fn is_significant(&self, index: usize, alpha: f64) -> Option<bool> {
+        self.p_values
+            .get(index)
+            .copied()
+            .flatten()
+            .map(|p| p < alpha)
+    }
+
+    /// Regressions are red and improvements green; changes that are not significant stay plain.
+    fn change_color(&self, index: usize, alpha: f64) -> Color {
+        if self.is_significant(index, alpha) == Some(false) {
+            return Color::Reset;
+        }
+        match self
+            .delta(index)
+            .and_then(|(absolute, _)| self.is_regression(absolute))
+        {
+            Some(true) => Color::Red,
+            Some(false) => Color::Green,
+            None => Color::Reset,
+        }
+    }
+}
+
+pub fn run_compare(args: &CompareArgs) -> Result<()> {
+    let results_dir = default_results_dir()?;
+    let runs = args
+        .runs
+        .iter()
+        .map(|run| load_run(&resolve_summary_path(run, &results_dir)?))
+        .collect::<Result<Vec<_>>>()?;
+
+    let rows = comparison_rows(&runs, &args.stats);
+    print_comparison_table(&runs, &rows, args.alpha);
+    Ok(())
+}
+
+/// Accepts any summary file, a run directory, or a bare run id under the results directory.
+/// A file is used as given, whatever its name; otherwise this is the run's `summary.json`.
+pub fn resolve_summary_path(run: &str, results_dir: &Path) -> Result<PathBuf> {
+    let path = Path::new(run);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    Ok(resolve_run_dir(run, results_dir)?.join("summary.json"))
+}
+
+/// Accepts a `summary.json` path, a run directory, or a bare run id under the results directory.
+pub fn resolve_run_dir(run: &str, results_dir: &Path) -> Result<PathBuf> {
+    let path = Path::new(run);
+    if path.is_file() {
+        // Other files would silently stand in for the summary.json next to them.
+        if path.file_name() != Some("summary.json".as_ref()) {
+            bail!(
+                "'{}' is not a run directory or a summary.json file",
+                path.display()
+            );
+        }
+        return Ok(path.parent().unwrap_or(Path::new(".")).to_path_buf());
+    }
+    if path.join("summary.json").is_file() {
+        return Ok(path.to_path_buf());
+    }
+
+    let mut matches = Vec::new();
+    if results_dir.is_dir() {
+        for entry in fs::read_dir(results_dir)? {
+            let candidate = entry?.path().join(run);
+            if candidate.join("summary.json").is_file() {
+                matches.push(candidate);
+            }
+        }
+    }
+
+    match matches.len() {
+        0 => bail!(
+            "No run found for '{}' (looked for a path or a run id under {})",
+            run,
+            results_dir.display()
+        ),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            matches.sort();
+            let candidates: Vec<String> = matches.iter().map(|m| m.display().to_string()).collect();
+            bail!(
+                "Run id '{}' is ambiguous; pass one of these paths instead:\n  {}",
+                run,
+                candidates.join("\n  ")
+            )
+        }
+    }
+}
+
+pub fn load_summary(dir: &Path) -> Result<BenchmarkSummary> {
+    load_summary_file(&dir.join("summary.json"))
+}
+
+pub fn load_summary_file(path: &Path) -> Result<BenchmarkSummary> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read '{}'", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("Failed to parse '{}'", path.display()))
+}
+
+/// Loads a summary file and, when present, the per-request records in the same directory.
+pub fn load_run(summary_path: &Path) -> Result<SavedRun> {
+    let summary = load_summary_file(summary_path)?;
+    let dir = summary_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    // Runs copied around without their per-request records can still be compared, just untested.
+    let samples = if dir.join("individual_responses.jsonl").is_file() {
+        // A trimmed report summary is tested on the requests its statistics describe.
+        let bounds = summary
+            .window
+            .map(|_| (summary.start_time_unix_ns, summary.end_time_unix_ns));
+        load_samples(&load_records(dir)?, bounds)
+    } else {
+        BTreeMap::new()
+    };
+
+    Ok(SavedRun {
+        dir: dir.to_path_buf(),
+        summary,
+        samples,
+    })
+}
+
+pub fn load_records(dir: &Path) -> Result<Vec<RequestRecord>> {
+    let path = dir.join("individual_responses.jsonl");
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read '{}'", path.display()))?;
+    parse_records(&contents).with_context(|| format!("Failed to parse '{}'", path.display()))
+}
+
+pub fn parse_records(contents: &str) -> Result<Vec<RequestRecord>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_index, line)| {
+            serde_json::from_str(line).with_context(|| format!("line {}", line_index + 1))
+        })
+        .collect()
+}
+
+/// Per-metric values of successful profiling requests, limited to `bounds` (start and end
+/// in unix ns) when given, with the same rules as the `llmnop report` skip filters.
+fn load_samples(
+    records: &[RequestRecord],
+    bounds: Option<(u64, u64)>,
+) -> BTreeMap<String, Vec<f64>> {
+    let mut samples: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for record in records {
+        if record.error.is_some() || record.metadata.benchmark_phase != "profiling" {
+            continue;
+        }
+        let metadata = &record.metadata;
+        let outside = bounds.is_some_and(|(start, end)| {
+            metadata.request_start_ns.is_some_and(|ns| ns < start)
+                || metadata.request_end_ns.is_some_and(|ns| ns > end)
+        });
+        if outside {
+            continue;
+        }
+        for (name, metric) in &record.metrics {
+            if let Some(value) = metric.value.as_f64() {
+                samples.entry(name.clone()).or_default().push(value);
+            }
+        }
+    }
+    samples
+}
+
+fn comparison_rows(runs: &[SavedRun], stats: &[CompareStat]) -> Vec<ComparisonRow> {
+    let metrics: Vec<_> = runs.iter().map(|run| run.summary.metrics()).collect();
+    let Some(first) = metrics.first() else {
+        return Vec::new();
+    };
+
+    let mut rows = Vec::new();
+    for (metric_index, (metric, _)) in first.iter().enumerate() {
+        let per_run: Vec<Option<&MetricStats>> =
+            metrics.iter().map(|m| m[metric_index].1).collect();
+        let Some(unit) = per_run.iter().flatten().map(|m| m.unit.clone()).next() else {
+            continue;
+        };
+        let samples: Vec<Option<&Vec<f64>>> =
+            runs.iter().map(|run| run.samples.get(*metric)).collect();
+        let p_values: Vec<Option<f64>> = samples
+            .iter()
+            .enumerate()
+            .map(|(index, candidate)| {
+                let baseline = samples[0]?;
+                let candidate = (*candidate)?;
+                (index > 0)
+                    .then(|| mann_whitney_u(baseline, candidate))
+                    .flatten()
+                    .map(|test| test.p_value)
+            })
+            .collect();
+
+        for &stat in stats {
+            let values: Vec<Option<f64>> = per_run
+                .iter()
+                .map(|m| m.and_then(|m| stat.value(m)))
+                .collect();
+            if values.iter().all(Option::is_none) {
+                continue;
+            }
+            let intervals = samples
+                .iter()
+                .map(|samples| bootstrap_ci(samples.as_ref()?, stat.quantile()?))
+                .collect();
+            rows.push(ComparisonRow {
+                metric,
+                stat,
+                unit: unit.clone(),
+                values,
+                intervals,
+                p_values: p_values.clone(),
+            });
+        }
+    }
+    rows
+}
+
+fn print_comparison_table(runs: &[SavedRun], rows: &[ComparisonRow], alpha: f64) {
+    println!();
+    for (index, run) in runs.iter().enumerate() {
+        let role = if index == 0 { "baseline" } else { "candidate" };
+        println!(
+            "{} ({}): {} [{}]",
+            run.summary.benchmark_id,
+            role,
+            run.summary.input_config.model,
+            run.dir.display()
+        );
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    let mut header = vec![
+        Cell::new("Metric").add_attribute(Attribute::Bold),
+        Cell::new("Stat").add_attribute(Attribute::Bold),
+    ];
+    for (index, run) in runs.iter().enumerate() {
+        header.push(Cell::new(&run.summary.benchmark_id).add_attribute(Attribute::Bold));
+        if index > 0 {
+            header.push(Cell::new("Δ").add_attribute(Attribute::Bold));
+            header.push(Cell::new("Δ%").add_attribute(Attribute::Bold));
+            header.push(Cell::new("p").add_attribute(Attribute::Bold));
+        }
+    }
+    table.set_header(header);
+
+    fn fmt_opt(value: Option<f64>) -> String {
+        value
+            .map(|v| format!("{:.2}", v))
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    for row in rows {
+        let mut cells = vec![
+            Cell::new(format!("{} ({})", row.metric, row.unit)).fg(Color::Cyan),
+            Cell::new(row.stat.as_str()),
+        ];
+        for (index, value) in row.values.iter().enumerate() {
+            let value = match (value, row.intervals[index]) {
+                (Some(value), Some([low, high])) => {
+                    format!("{:.2} [{:.2}, {:.2}]", value, low, high)
+                }
+                (value, _) => fmt_opt(*value),
+            };
+            cells.push(
+                Cell::new(value)
+                    .set_alignment(CellAlignment::Right)
+                    .fg(Color::Green),
+            );
+            if index == 0 {
+                continue;
+            }
+
+            let delta = row.delta(index);
+            let color = row.change_color(index, alpha);
+            let (absolute, percent) = match delta {
+                Some((absolute, percent)) => (
+                    format!("{:+.2}", absolute),
+                    percent
+                        .map(|p| format!("{:+.1}%", p))
+                        .unwrap_or_else(|| "-".to_string()),
+                ),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            cells.push(
+                Cell::new(absolute)
+                    .set_alignment(CellAlignment::Right)
+                    .fg(color),
+            );
+            cells.push(
+                Cell::new(percent)
+                    .set_alignment(CellAlignment::Right)
+                    .fg(color),
+            );
+            cells.push(
+                Cell::new(
+                    row.p_values[index]
+                        .map(|p| {
+                            if p < 0.001 {
+                                "<0.001".to_string()
+                            } else {
+                                format!("{:.3}", p)
+                            }
+                        })
+                        .unwrap_or_else(|| "-".to_string()),
+                )
+                .set_alignment(CellAlignment::Right)
+                .fg(color),
+            );
+        }
+        table.add_row(cells);
+    }
+
+    println!();
+    println!("{table}");
+    println!(
+        "Intervals are 95% bootstrap CIs. p is a Mann-Whitney U test against the baseline; per-request metrics are only colored when p < {}.",
+        alpha
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summary(benchmark_id: &str, ttft_p50: f64, throughput: f64) -> BenchmarkSummary {
+        let avg = |unit: &str, avg: f64| json!({"unit": unit, "avg": avg});
+        let latency =
+            json!({"unit": "ms", "avg": ttft_p50, "p50": ttft_p50, "p99": ttft_p50 * 2.0});
+        serde_json::from_value(json!({
+            "version": "2025-10-05",
+            "schema_version": "2.0",
+            "llmnop_version": "0.9.0",
+            "benchmark_id": benchmark_id,
+            "benchmark_slug": "model_550_150",
+            "start_time_unix_ns": 0,
+            "end_time_unix_ns": 0,
+            "input_config": {
+                "model": "model",
+                "tokenizer": "model",
+                "mean_input_tokens": 550,
+                "stddev_input_tokens": 0,
+                "num_concurrent_requests": 1
+            },
+            "benchmark_duration": avg("s", 10.0),
+            "request_count": avg("requests", 10.0),
+            "successful_request_count": avg("requests", 10.0),
+            "error_request_count": avg("requests", 0.0),
+            "error_rate": avg("ratio", 0.0),
+            "request_throughput": avg("requests/sec", throughput),
+            "request_latency": latency,
+            "time_to_first_token": latency,
+            "inter_token_latency": latency,
+            "inter_event_latency": latency,
+            "output_token_throughput_per_request": avg("tokens/sec/request", 50.0),
+            "output_token_throughput": avg("tokens/sec", 500.0),
+            "total_token_throughput": avg("tokens/sec", 600.0),
+            "input_sequence_length": latency,
+            "output_token_count": latency,
+            "reasoning_token_count": latency,
+            "output_sequence_length": latency,
+            "total_input_tokens": avg("tokens", 5500.0),
+            "total_output_tokens": avg("tokens", 1500.0),
+            "total_reasoning_tokens": avg("tokens", 0.0),
+            "total_output_sequence_tokens": avg("tokens", 1500.0)
+        }))
+        .unwrap()
+    }
+
+    fn run(summary: BenchmarkSummary, ttft_samples: Vec<f64>) -> SavedRun {
+        SavedRun {
+            dir: PathBuf::from(&summary.benchmark_id),
+            summary,
+            samples: BTreeMap::from([("time_to_first_token".to_string(), ttft_samples)]),
+        }
+    }
+
+    fn row<'a>(rows: &'a [ComparisonRow], metric: &str, stat: CompareStat) -> &'a ComparisonRow {
+        rows.iter()
+            .find(|r| r.metric == metric && r.stat == stat)
+            .unwrap_or_else(|| panic!("missing {metric} {stat:?}"))
+    }
+
+    #[test]
+    fn aligns_metrics_across_runs() {
+        let runs = [
+            run(summary("a", 100.0, 2.0), Vec::new()),
+            run(summary("b", 150.0, 2.5), Vec::new()),
+        ];
+        let rows = comparison_rows(&runs, &[CompareStat::Avg, CompareStat::P99]);
+
+        let ttft = row(&rows, "time_to_first_token", CompareStat::P99);
+        assert_eq!(ttft.values, vec![Some(200.0), Some(300.0)]);
+        assert_eq!(ttft.unit, "ms");
+        assert_eq!(ttft.delta(1), Some((100.0, Some(50.0))));
+        assert_eq!(ttft.is_regression(100.0), Some(true));
+
+        let throughput = row(&rows, "request_throughput", CompareStat::Avg);
+        assert_eq!(throughput.delta(1), Some((0.5, Some(25.0))));
+        assert_eq!(throughput.is_regression(0.5), Some(false));
+
+        // Avg-only metrics have no p99 row, and absent optional metrics are skipped.
+        assert!(
+            !rows
+                .iter()
+                .any(|r| r.metric == "request_throughput" && r.stat == CompareStat::P99)
+        );
+        assert!(!rows.iter().any(|r| r.metric == "slo_attainment"));
+    }
+
+    #[test]
+    fn delta_handles_zero_baseline_and_missing_values() {
+        let row = ComparisonRow {
+            metric: "error_rate",
+            stat: CompareStat::Avg,
+            unit: "ratio".to_string(),
+            values: vec![Some(0.0), Some(0.1), None],
+            intervals: vec![None; 3],
+            p_values: vec![None; 3],
+        };
+        assert_eq!(row.delta(1), Some((0.1, None)));
+        assert_eq!(row.delta(2), None);
+        assert_eq!(row.is_regression(0.0), None);
+    }
+
+    #[test]
+    fn colors_only_significant_changes() {
+        let noisy: Vec<f64> = (0..40).map(|i| 100.0 + f64::from(i % 10)).collect();
+        let shifted: Vec<f64> = noisy.iter().map(|v| v + 50.0).collect();
+        let mut jittered = noisy.clone();
+        jittered.rotate_left(3);
+        jittered[0] += 1.0;
+
+        let runs = [
+            run(summary("a", 104.0, 2.0), noisy),
+            run(summary("b", 105.0, 2.0), jittered),
+            run(summary("c", 154.0, 1.0), shifted),
+        ];
+        let rows = comparison_rows(&runs, &[CompareStat::Avg, CompareStat::P50]);
+
+        let ttft = row(&rows, "time_to_first_token", CompareStat::P50);
+        assert_eq!(ttft.p_values[0], None);
+        assert_eq!(ttft.is_significant(1, 0.05), Some(false));
+        assert_eq!(ttft.change_color(1, 0.05), Color::Reset);
+        assert_eq!(ttft.is_significant(2, 0.05), Some(true));
+        assert_eq!(ttft.change_color(2, 0.05), Color::Red);
+        assert!(ttft.intervals.iter().all(Option::is_some));
+
+        // Run-level metrics have no samples to test and keep their direction colors.
+        let throughput = row(&rows, "request_throughput", CompareStat::Avg);
+        assert_eq!(throughput.is_significant(2, 0.05), None);
+        assert_eq!(throughput.change_color(2, 0.05), Color::Red);
+    }
+
+    #[test]
+    fn load_samples_keeps_successful_profiling_requests() {
+        let contents = r#"{"metadata":{"request_index":0,"benchmark_phase":"warmup"},"metrics":{"time_to_first_token":{"value":900.0,"unit":"ms"}}}
+{"metadata":{"request_index":0,"benchmark_phase":"profiling"},"metrics":{"time_to_first_token":{"value":100.0,"unit":"ms"},"output_token_count":{"value":12,"unit":"tokens"}}}
+{"metadata":{"request_index":1,"benchmark_phase":"profiling"},"metrics":{},"error":{"code":-1,"type":"request_error","message":"boom"}}
+"#;
+        let samples = load_samples(&parse_records(contents).unwrap(), None);
+        assert_eq!(samples["time_to_first_token"], vec![100.0]);
+        assert_eq!(samples["output_token_count"], vec![12.0]);
+    }
+
+    #[test]
+    fn load_samples_keeps_requests_inside_a_report_window() {
+        let contents = r#"{"metadata":{"request_index":0,"benchmark_phase":"profiling","request_start_ns":1000,"request_end_ns":2000},"metrics":{"time_to_first_token":{"value":900.0,"unit":"ms"}}}
+{"metadata":{"request_index":1,"benchmark_phase":"profiling","request_start_ns":3000,"request_end_ns":4000},"metrics":{"time_to_first_token":{"value":100.0,"unit":"ms"}}}
+{"metadata":{"request_index":2,"benchmark_phase":"profiling","request_start_ns":5000,"request_end_ns":9000},"metrics":{"time_to_first_token":{"value":300.0,"unit":"ms"}}}
+"#;
+        let records = parse_records(contents).unwrap();
+        assert_eq!(
+            load_samples(&records, Some((2500, 8000)))["time_to_first_token"],
+            vec![100.0]
+        );
+        assert_eq!(load_samples(&records, None)["time_to_first_token"].len(), 3);
+    }
+
+    #[test]
+    fn resolves_run_by_id_path_or_summary_file() {
+        let results_dir =
+            std::env::temp_dir().join(format!("llmnop-compare-{}", std::process::id()));
+        let run_dir = results_dir.join("model_550_150").join("1700000000_1");
+        fs::create_dir_all(&run_dir).unwrap();
+        fs::write(run_dir.join("summary.json"), "{}").unwrap();
+
+        assert_eq!(
+            resolve_run_dir("1700000000_1", &results_dir).unwrap(),
+            run_dir
+        );
+        assert_eq!(
+            resolve_run_dir(run_dir.to_str().unwrap(), &results_dir).unwrap(),
+            run_dir
+        );
+        assert_eq!(
+            resolve_run_dir(run_dir.join("summary.json").to_str().unwrap(), &results_dir).unwrap(),
+            run_dir
+        );
+        assert!(resolve_run_dir("missing", &results_dir).is_err());
+
+        fs::remove_dir_all(&results_dir).unwrap();
+    }
+
+    #[test]
+    fn resolves_a_summary_file_with_any_name_as_given() {
+        let results_dir =
+            std::env::temp_dir().join(format!("llmnop-compare-file-{}", std::process::id()));
+        let run_dir = results_dir.join("model_550_150").join("1700000000_1");
+        fs::create_dir_all(&run_dir).unwrap();
+        fs::write(run_dir.join("summary.json"), "{}").unwrap();
+        let baseline = results_dir.join("baseline.json");
+        fs::write(&baseline, "{}").unwrap();
+
+        assert_eq!(
+            resolve_summary_path(baseline.to_str().unwrap(), &results_dir).unwrap(),
+            baseline
+        );
+        assert_eq!(
+            resolve_summary_path("1700000000_1", &results_dir).unwrap(),
+            run_dir.join("summary.json")
+        );
+        // A file that is not a summary.json must not resolve to the directory it sits in.
+        assert!(resolve_run_dir(baseline.to_str().unwrap(), &results_dir).is_err());
+
+        fs::remove_dir_all(&results_dir).unwrap();
+    }
+}
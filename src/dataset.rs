@@ -0,0 +1,349 @@
+use crate::args::DatasetFormat;
+use crate::tokens;
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// A real prompt loaded from a dataset file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatasetEntry {
+    pub prompt: String,
+    /// Target output length for this row, used in place of `--mean-output-tokens`.
+    pub output_tokens: Option<u32>,
+}
+
+/// A parsed row before token counting; ShareGPT rows carry the reference reply instead of a length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DatasetRecord {
+    prompt: String,
+    output_tokens: Option<u32>,
+    completion: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputTokenRange {
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+}
+
+impl InputTokenRange {
+    fn contains(&self, num_tokens: u32) -> bool {
+        self.min.is_none_or(|min| num_tokens >= min) && self.max.is_none_or(|max| num_tokens <= max)
+    }
+}
+
+pub fn load_dataset(
+    path: &Path,
+    format: DatasetFormat,
+    input_tokens: InputTokenRange,
+    tokenizer: &str,
+) -> Result<Vec<DatasetEntry>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read dataset '{}'", path.display()))?;
+
+    let is_csv = match format {
+        DatasetFormat::Csv => true,
+        DatasetFormat::Auto => path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv")),
+        DatasetFormat::Jsonl | DatasetFormat::Sharegpt => false,
+    };
+
+    let records = if is_csv {
+        parse_csv(&contents)
+    } else {
+        parse_json_records(&contents, format)
+    }
+    .with_context(|| format!("Failed to parse dataset '{}'", path.display()))?;
+
+    if records.is_empty() {
+        bail!("Dataset '{}' contains no prompts", path.display());
+    }
+
+    let entries = count_and_filter(records, input_tokens, tokenizer)?;
+    if entries.is_empty() {
+        bail!(
+            "No prompts in dataset '{}' fall within the input token range",
+            path.display()
+        );
+    }
+
+    Ok(entries)
+}
+
+fn count_and_filter(
+    records: Vec<DatasetRecord>,
+    input_tokens: InputTokenRange,
+    tokenizer: &str,
+) -> Result<Vec<DatasetEntry>> {
+    let prompts: Vec<String> = records.iter().map(|r| r.prompt.clone()).collect();
+    let prompt_lengths = tokens::encode_batch(&prompts, tokenizer)?;
+
+    let completions: Vec<String> = records
+        .iter()
+        .filter(|r| r.output_tokens.is_none())
+        .filter_map(|r| r.completion.clone())
+        .collect();
+    let mut completion_lengths = tokens::encode_batch(&completions, tokenizer)?.into_iter();
+
+    let mut entries = Vec::with_capacity(records.len());
+    for (record, prompt_ids) in records.into_iter().zip(prompt_lengths) {
+        let output_tokens = match (record.output_tokens, &record.completion) {
+            (Some(output_tokens), _) => Some(output_tokens),
+            (None, Some(_)) => completion_lengths
+                .next()
+                .map(|ids| ids.len() as u32)
+                .filter(|&n| n > 0),
+            (None, None) => None,
+        };
+
+        if input_tokens.contains(prompt_ids.len() as u32) {
+            entries.push(DatasetEntry {
+                prompt: record.prompt,
+                output_tokens,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+fn parse_json_records(contents: &str, format: DatasetFormat) -> Result<Vec<DatasetRecord>> {
+    // ShareGPT dumps are usually a single JSON array; everything else is one record per line.
+    let values: Vec<(usize, Value)> = if contents.trim_start().starts_with('[') {
+        let values: Vec<Value> = serde_json::from_str(contents)?;
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (i + 1, v))
+            .collect()
+    } else {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map(|value| (i + 1, value))
+                    .map_err(|e| anyhow!("line {}: {}", i + 1, e))
+            })
+            .collect::<Result<_>>()?
+    };
+
+    let mut records = Vec::with_capacity(values.len());
+    for (record_number, value) in values {
+        let is_sharegpt = match format {
+            DatasetFormat::Sharegpt => true,
+            DatasetFormat::Jsonl => false,
+            _ => value.get("conversations").is_some() || value.get("messages").is_some(),
+        };
+
+        let record = if is_sharegpt {
+            // Conversations without a user turn carry nothing to replay.
+            parse_sharegpt_record(&value)
+        } else {
+            Some(
+                parse_prompt_record(&value)
+                    .map_err(|e| anyhow!("record {}: {}", record_number, e))?,
+            )
+        };
+        records.extend(record);
+    }
+
+    Ok(records)
+}
+
+fn parse_prompt_record(value: &Value) -> Result<DatasetRecord> {
+    let prompt = value
+        .get("prompt")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("missing string field 'prompt'"))?;
+
+    let output_tokens = ["output_tokens", "max_tokens", "output_len"]
+        .iter()
+        .find_map(|key| value.get(*key))
+        .map(|v| {
+            v.as_u64()
+                .and_then(|n| u32::try_from(n).ok())
+                .filter(|&n| n > 0)
+                .ok_or_else(|| anyhow!("output length must be a positive integer, got {}", v))
+        })
+        .transpose()?;
+
+    Ok(DatasetRecord {
+        prompt: prompt.to_string(),
+        output_tokens,
+        completion: None,
+    })
+}
+
+fn parse_sharegpt_record(value: &Value) -> Option<DatasetRecord> {
+    let turns = value
+        .get("conversations")
+        .or_else(|| value.get("messages"))?
+        .as_array()?;
+
+    let turn_text = |turn: &Value, speakers: &[&str]| -> Option<String> {
+        let speaker = turn.get("from").or_else(|| turn.get("role"))?.as_str()?;
+        if !speakers.contains(&speaker) {
+            return None;
+        }
+        let text = turn
+            .get("value")
+            .or_else(|| turn.get("content"))?
+            .as_str()?;
+        Some(text.to_string())
+    };
+
+    let prompt_index = turns
+        .iter()
+        .position(|turn| turn_text(turn, &["human", "user"]).is_some())?;
+    let prompt = turn_text(&turns[prompt_index], &["human", "user"])?;
+    if prompt.trim().is_empty() {
+        return None;
+    }
+
+    let completion = turns
+        .get(prompt_index + 1)
+        .and_then(|turn| turn_text(turn, &["gpt", "assistant"]));
+
+    Some(DatasetRecord {
+        prompt,
+        output_tokens: None,
+        completion,
+    })
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<DatasetRecord>> {
+    let mut reader = csv::Reader::from_reader(contents.as_bytes());
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+
+    let prompt_column = column("prompt").ok_or_else(|| anyhow!("missing 'prompt' column"))?;
+    let output_column = column("output_tokens")
+        .or_else(|| column("max_tokens"))
+        .or_else(|| column("output_len"));
+
+    let mut records = Vec::new();
+    for (row_index, row) in reader.records().enumerate() {
+        let row = row?;
+        let row_number = row_index + 2;
+
+        let prompt = row.get(prompt_column).unwrap_or_default();
+        if prompt.is_empty() {
+            continue;
+        }
+
+        let output_tokens =
+            match output_column.and_then(|c| row.get(c)).map(str::trim) {
+                None | Some("") => None,
+                Some(value) => Some(value.parse::<u32>().ok().filter(|&n| n > 0).ok_or_else(
+                    || {
+                        anyhow!(
+                            "row {}: output length must be a positive integer, got '{}'",
+                            row_number,
+                            value
+                        )
+                    },
+                )?),
+            };
+
+        records.push(DatasetRecord {
+            prompt: prompt.to_string(),
+            output_tokens,
+            completion: None,
+        });
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(prompt: &str, output_tokens: Option<u32>, completion: Option<&str>) -> DatasetRecord {
+        DatasetRecord {
+            prompt: prompt.to_string(),
+            output_tokens,
+            completion: completion.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parses_prompt_jsonl_with_output_length_aliases() {
+        let contents = r#"{"prompt": "first", "output_tokens": 64}
+
+{"prompt": "second", "max_tokens": 32}
+{"prompt": "third"}
+"#;
+        let records = parse_json_records(contents, DatasetFormat::Auto).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                record("first", Some(64), None),
+                record("second", Some(32), None),
+                record("third", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_prompt_record_without_prompt() {
+        let err = parse_json_records(r#"{"text": "hi"}"#, DatasetFormat::Jsonl).unwrap_err();
+        assert!(err.to_string().contains("record 1"), "{err}");
+    }
+
+    #[test]
+    fn parses_sharegpt_array() {
+        let contents = r#"[
+            {"id": "a", "conversations": [
+                {"from": "system", "value": "be brief"},
+                {"from": "human", "value": "hello"},
+                {"from": "gpt", "value": "hi there"}
+            ]},
+            {"id": "b", "conversations": [{"from": "gpt", "value": "orphan"}]},
+            {"messages": [{"role": "user", "content": "question"}]}
+        ]"#;
+        let records = parse_json_records(contents, DatasetFormat::Auto).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                record("hello", None, Some("hi there")),
+                record("question", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_csv_with_optional_output_length() {
+        let contents = "id,prompt,output_tokens\n1,\"hello, world\",16\n2,plain,\n3,,8\n";
+        let records = parse_csv(contents).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                record("hello, world", Some(16), None),
+                record("plain", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_csv_without_prompt_column() {
+        assert!(parse_csv("text\nhello\n").is_err());
+    }
+
+    #[test]
+    fn input_token_range_bounds_are_inclusive() {
+        let range = InputTokenRange {
+            min: Some(10),
+            max: Some(20),
+        };
+        assert!(!range.contains(9));
+        assert!(range.contains(10));
+        assert!(range.contains(20));
+        assert!(!range.contains(21));
+        assert!(InputTokenRange::default().contains(0));
+    }
+}
@@ -0,0 +1,280 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Broad cause of a failed request, recorded as the error `type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// HTTP 429 from the server.
+    RateLimit,
+    /// Any other non-2xx HTTP status.
+    HttpStatus,
+    Timeout,
+    /// Could not connect, or the connection was reset or closed early.
+    Connection,
+    /// The stream opened but then reported an error.
+    Stream,
+    /// A response body or stream event did not parse.
+    Deserialization,
+    Tokenizer,
+    Other,
+}
+
+impl ErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::RateLimit => "rate_limit",
+            ErrorKind::HttpStatus => "http_status",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Connection => "connection",
+            ErrorKind::Stream => "stream",
+            ErrorKind::Deserialization => "deserialization",
+            ErrorKind::Tokenizer => "tokenizer",
+            ErrorKind::Other => "other",
+        }
+    }
+
+    /// Reads a saved error `type`; records from older versions fall back to `other`.
+    pub fn parse(value: &str) -> Self {
+        [
+            ErrorKind::RateLimit,
+            ErrorKind::HttpStatus,
+            ErrorKind::Timeout,
+            ErrorKind::Connection,
+            ErrorKind::Stream,
+            ErrorKind::Deserialization,
+            ErrorKind::Tokenizer,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == value)
+        .unwrap_or(ErrorKind::Other)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A failed request, classified so rate limits, server errors and disconnects can be told apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestFailure {
+    pub kind: ErrorKind,
+    pub status: Option<u16>,
+    /// Delay the server asked for in a `Retry-After` header.
+    pub retry_after: Option<Duration>,
+    pub message: String,
+    /// Attempts made, including the first; above 1 only with `--max-retries`.
+    pub attempts: u32,
+    /// When the first attempt was sent and when the request gave up, stamped by the runner so
+    /// `llmnop report` can window failures like successful requests.
+    pub request_start_unix_ns: Option<u64>,
+    pub request_end_unix_ns: Option<u64>,
+}
+
+impl RequestFailure {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            status: None,
+            retry_after: None,
+            message: message.into(),
+            attempts: 1,
+            request_start_unix_ns: None,
+            request_end_unix_ns: None,
+        }
+    }
+
+    pub fn from_status(status: StatusCode, retry_after: Option<Duration>, body: &str) -> Self {
+        let kind = if status == StatusCode::TOO_MANY_REQUESTS {
+            ErrorKind::RateLimit
+        } else {
+            ErrorKind::HttpStatus
+        };
+        Self {
+            status: Some(status.as_u16()),
+            retry_after,
+            ..Self::new(kind, format!("HTTP {status}: {}", error_message(body)))
+        }
+    }
+
+    /// Classifies a transport error; `mid_stream` is true once the response headers arrived.
+    pub fn from_reqwest(err: &reqwest::Error, mid_stream: bool) -> Self {
+        let kind = if err.is_timeout() {
+            ErrorKind::Timeout
+        } else if err.is_connect() || is_connection_reset(err) {
+            ErrorKind::Connection
+        } else if mid_stream {
+            ErrorKind::Stream
+        } else if err.is_request() {
+            ErrorKind::Connection
+        } else {
+            ErrorKind::Other
+        };
+        Self::new(kind, error_chain(err))
+    }
+
+    /// HTTP status of the failure, or 0 when it has none.
+    pub fn code(&self) -> i32 {
+        self.status.map_or(0, i32::from)
+    }
+
+    /// Failures that a later attempt may not hit: rate limits, 5xx, timeouts and dropped connections.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind {
+            ErrorKind::RateLimit | ErrorKind::Timeout | ErrorKind::Connection => true,
+            ErrorKind::HttpStatus => self.status.is_some_and(|status| status >= 500),
+            _ => false,
+        }
+    }
+
+    /// Recovers the classification from an error chain, treating unclassified errors as `other`.
+    pub fn from_anyhow(err: anyhow::Error) -> Self {
+        match err.downcast::<RequestFailure>() {
+            Ok(failure) => failure,
+            Err(err) => Self::new(ErrorKind::Other, format!("{err:#}")),
+        }
+    }
+}
+
+impl fmt::Display for RequestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} error: {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for RequestFailure {}
+
+/// Parses a `Retry-After` header given in seconds; HTTP dates are ignored.
+pub fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+}
+
+/// The `error.message` of an OpenAI- or Anthropic-style error body, else the body itself.
+pub fn error_message(body: &str) -> String {
+    let message = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| {
+            let error = value.get("error").unwrap_or(&value);
+            error
+                .get("message")
+                .and_then(|message| message.as_str())
+                .map(str::to_string)
+        });
+    message.unwrap_or_else(|| body.trim().to_string())
+}
+
+fn is_connection_reset(err: &reqwest::Error) -> bool {
+    use std::io::ErrorKind as IoKind;
+
+    let mut source = std::error::Error::source(err);
+    while let Some(err) = source {
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io_err.kind(),
+                IoKind::ConnectionReset
+                    | IoKind::ConnectionAborted
+                    | IoKind::BrokenPipe
+                    | IoKind::UnexpectedEof
+            );
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// reqwest's own message is terse ("error sending request"), so the causes are appended.
+fn error_chain(err: &reqwest::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = std::error::Error::source(err);
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+    #[test]
+    fn status_failures_split_rate_limits_from_server_errors() {
+        let rate_limited = RequestFailure::from_status(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(2)),
+            r#"{"error":{"message":"slow down","type":"rate_limit_error"}}"#,
+        );
+        assert_eq!(rate_limited.kind, ErrorKind::RateLimit);
+        assert_eq!(rate_limited.code(), 429);
+        assert_eq!(
+            rate_limited.message,
+            "HTTP 429 Too Many Requests: slow down"
+        );
+        assert!(rate_limited.is_retryable());
+
+        let unavailable =
+            RequestFailure::from_status(StatusCode::SERVICE_UNAVAILABLE, None, "busy");
+        assert_eq!(unavailable.kind, ErrorKind::HttpStatus);
+        assert!(unavailable.is_retryable());
+
+        let bad_request = RequestFailure::from_status(
+            StatusCode::BAD_REQUEST,
+            None,
+            r#"{"object":"error","message":"bad model","code":400}"#,
+        );
+        assert_eq!(bad_request.message, "HTTP 400 Bad Request: bad model");
+        assert!(!bad_request.is_retryable());
+    }
+
+    #[test]
+    fn kinds_round_trip_and_unknown_types_are_other() {
+        for kind in [
+            ErrorKind::RateLimit,
+            ErrorKind::Stream,
+            ErrorKind::Tokenizer,
+        ] {
+            assert_eq!(ErrorKind::parse(kind.as_str()), kind);
+        }
+        assert_eq!(ErrorKind::parse("RequestError"), ErrorKind::Other);
+    }
+
+    #[test]
+    fn classification_survives_anyhow_context() {
+        let err = anyhow::Error::new(RequestFailure::new(ErrorKind::Stream, "overloaded"))
+            .context("sending request");
+        let failure = RequestFailure::from_anyhow(err);
+        assert_eq!(failure.kind, ErrorKind::Stream);
+        assert_eq!(failure.message, "overloaded");
+
+        let err = anyhow::anyhow!("server did not return token usage");
+        let failure = RequestFailure::from_anyhow(err);
+        assert_eq!(failure.kind, ErrorKind::Other);
+        assert_eq!(failure.code(), 0);
+    }
+
+    #[test]
+    fn parses_retry_after_seconds() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(3)));
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), None);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::args::{ExportArgs, ExportFormat};
+use crate::compare::{load_records, resolve_run_dir};
+use crate::output::{RequestMetadata, RequestRecord, default_results_dir};
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// One typed column of the flattened records; `None` is an empty cell.
+#[derive(Debug, Clone, PartialEq)]
+enum ColumnValues {
+    UInt64(Vec<Option<u64>>),
+    Int64(Vec<Option<i64>>),
+    Float64(Vec<Option<f64>>),
+    Bool(Vec<Option<bool>>),
+    Utf8(Vec<Option<String>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Column {
+    name: String,
+    values: ColumnValues,
+}
+
+impl Column {
+    fn new(name: &str, values: ColumnValues) -> Self {
+        Self {
+            name: name.to_string(),
+            values,
+        }
+    }
+
+    fn cell(&self, row: usize) -> String {
+        match &self.values {
+            ColumnValues::UInt64(values) => values[row].map(|v| v.to_string()),
+            ColumnValues::Int64(values) => values[row].map(|v| v.to_string()),
+            ColumnValues::Float64(values) => values[row].map(|v| v.to_string()),
+            ColumnValues::Bool(values) => values[row].map(|v| v.to_string()),
+            ColumnValues::Utf8(values) => values[row].clone(),
+        }
+        .unwrap_or_default()
+    }
+}
+
+pub fn run_export(args: &ExportArgs) -> Result<()> {
+    let dir = resolve_run_dir(&args.run, &default_results_dir()?)?;
+    let path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| export_path(&dir, args.format));
+    export_records(&load_records(&dir)?, args.format, &path)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+/// Writes each format next to a run's `individual_responses.jsonl`.
+pub fn export_run(dir: &Path, formats: &[ExportFormat]) -> Result<()> {
+    if formats.is_empty() {
+        return Ok(());
+    }
+    let records = load_records(dir)?;
+    for &format in formats {
+        export_records(&records, format, &export_path(dir, format))?;
+    }
+    Ok(())
+}
+
+fn export_path(dir: &Path, format: ExportFormat) -> PathBuf {
+    dir.join(format!("individual_responses.{}", format.extension()))
+}
+
+fn export_records(records: &[RequestRecord], format: ExportFormat, path: &Path) -> Result<()> {
+    let columns = flatten_records(records);
+    match format {
+        ExportFormat::Csv => write_csv(&columns, records.len(), path),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => write_parquet(&columns, path),
+    }
+    .with_context(|| format!("Failed to write '{}'", path.display()))
+}
+
+/// One row per request: metadata, then every scalar metric by name, then the error.
+///
+/// The per-event `inter_event_gaps_ms` lists do not fit a flat row and are left out.
+fn flatten_records(records: &[RequestRecord]) -> Vec<Column> {
+    let uint = |name: &str, field: fn(&RequestMetadata) -> Option<u64>| {
+        let values = records.iter().map(|r| field(&r.metadata)).collect();
+        Column::new(name, ColumnValues::UInt64(values))
+    };
+    let boolean = |name: &str, field: fn(&RequestMetadata) -> Option<bool>| {
+        let values = records.iter().map(|r| field(&r.metadata)).collect();
+        Column::new(name, ColumnValues::Bool(values))
+    };
+    let text = |name: &str, field: fn(&RequestRecord) -> Option<String>| {
+        Column::new(
+            name,
+            ColumnValues::Utf8(records.iter().map(field).collect()),
+        )
+    };
+
+    let mut columns = vec![
+        uint("request_index", |m| Some(m.request_index as u64)),
+        text("benchmark_phase", |r| {
+            Some(r.metadata.benchmark_phase.clone())
+        }),
+        uint("request_start_ns", |m| m.request_start_ns),
+        uint("request_end_ns", |m| m.request_end_ns),
+        uint("request_scheduled_ns", |m| m.request_scheduled_ns),
+        uint("session_index", |m| m.session_index.map(u64::from)),
+        uint("turn_index", |m| m.turn_index.map(u64::from)),
+        uint("context_length", |m| m.context_length.map(u64::from)),
+        uint("prefix_index", |m| m.prefix_index.map(u64::from)),
+        boolean("prefix_cache_hit_likely", |m| m.prefix_cache_hit_likely),
+        boolean("slo_met", |m| m.slo_met),
+        text("finish_reason", |r| r.metadata.finish_reason.clone()),
+        uint("attempts", |m| m.attempts.map(u64::from)),
+    ];
+
+    let metric_names: BTreeSet<&str> = records
+        .iter()
+        .flat_map(|record| &record.metrics)
+        .filter(|(_, metric)| metric.value.is_number())
+        .map(|(name, _)| name.as_str())
+        .collect();
+    for name in metric_names {
+        let values = records
+            .iter()
+            .map(|record| {
+                record
+                    .metrics
+                    .get(name)
+                    .and_then(|metric| metric.value.as_f64())
+            })
+            .collect();
+        columns.push(Column::new(name, ColumnValues::Float64(values)));
+    }
+
+    columns.extend([
+        Column::new(
+            "error_code",
+            ColumnValues::Int64(
+                records
+                    .iter()
+                    .map(|r| r.error.as_ref().map(|e| i64::from(e.code)))
+                    .collect(),
+            ),
+        ),
+        text("error_type", |r| {
+            r.error.as_ref().map(|e| e.error_type.clone())
+        }),
+        text("error_message", |r| {
+            r.error.as_ref().map(|e| e.message.clone())
+        }),
+    ]);
+    columns
+}
+
+fn write_csv(columns: &[Column], num_rows: usize, path: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(columns.iter().map(|column| column.name.as_str()))?;
+    for row in 0..num_rows {
+        writer.write_record(columns.iter().map(|column| column.cell(row)))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet(columns: &[Column], path: &Path) -> Result<()> {
+    use arrow_array::{
+        ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray, UInt64Array,
+    };
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+    use std::sync::Arc;
+
+    let mut fields = Vec::with_capacity(columns.len());
+    let mut arrays: Vec<ArrayRef> = Vec::with_capacity(columns.len());
+    for column in columns {
+        let (data_type, array): (DataType, ArrayRef) = match &column.values {
+            ColumnValues::UInt64(values) => (
+                DataType::UInt64,
+                Arc::new(UInt64Array::from(values.clone())),
+            ),
+            ColumnValues::Int64(values) => {
+                (DataType::Int64, Arc::new(Int64Array::from(values.clone())))
+            }
+            ColumnValues::Float64(values) => (
+                DataType::Float64,
+                Arc::new(Float64Array::from(values.clone())),
+            ),
+            ColumnValues::Bool(values) => (
+                DataType::Boolean,
+                Arc::new(BooleanArray::from(values.clone())),
+            ),
+            ColumnValues::Utf8(values) => {
+                (DataType::Utf8, Arc::new(StringArray::from(values.clone())))
+            }
+        };
+        fields.push(Field::new(&column.name, data_type, true));
+        arrays.push(array);
+    }
+
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(std::fs::File::create(path)?, schema, Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare::parse_records;
+
+    const RECORDS: &str = r#"{"metadata":{"request_index":0,"benchmark_phase":"warmup","request_start_ns":1,"request_end_ns":2},"metrics":{"time_to_first_token":{"value":90.5,"unit":"ms"}}}
+{"metadata":{"request_index":0,"benchmark_phase":"profiling","request_start_ns":10,"request_end_ns":20,"slo_met":true},"metrics":{"time_to_first_token":{"value":100.0,"unit":"ms"},"output_token_count":{"value":12,"unit":"tokens"}},"inter_event_gaps_ms":[1.0,2.0]}
+{"metadata":{"request_index":1,"benchmark_phase":"profiling","slo_met":false},"metrics":{},"error":{"code":1,"type":"RequestError","message":"boom, \"quoted\""}}
+"#;
+
+    fn column<'a>(columns: &'a [Column], name: &str) -> &'a ColumnValues {
+        &columns
+            .iter()
+            .find(|column| column.name == name)
+            .unwrap_or_else(|| panic!("missing column {name}"))
+            .values
+    }
+
+    #[test]
+    fn flattens_metadata_metrics_and_errors() {
+        let columns = flatten_records(&parse_records(RECORDS).unwrap());
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names[0], "request_index");
+        assert!(names.contains(&"output_token_count"));
+        assert!(!names.contains(&"inter_event_gaps_ms"));
+        assert_eq!(names.last(), Some(&"error_message"));
+
+        assert_eq!(
+            column(&columns, "time_to_first_token"),
+            &ColumnValues::Float64(vec![Some(90.5), Some(100.0), None])
+        );
+        assert_eq!(
+            column(&columns, "slo_met"),
+            &ColumnValues::Bool(vec![None, Some(true), Some(false)])
+        );
+        assert_eq!(
+            column(&columns, "error_code"),
+            &ColumnValues::Int64(vec![None, None, Some(1)])
+        );
+    }
+
+    #[test]
+    fn writes_csv_with_one_row_per_request() {
+        let records = parse_records(RECORDS).unwrap();
+        let path = std::env::temp_dir().join(format!("llmnop-export-{}.csv", std::process::id()));
+        export_records(&records, ExportFormat::Csv, &path).unwrap();
+
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(rows.len(), 3);
+        let cell = |row: usize, name: &str| {
+            let index = headers.iter().position(|h| h == name).unwrap();
+            rows[row][index].to_string()
+        };
+        assert_eq!(cell(0, "benchmark_phase"), "warmup");
+        assert_eq!(cell(1, "output_token_count"), "12");
+        assert_eq!(cell(2, "output_token_count"), "");
+        assert_eq!(cell(2, "error_message"), "boom, \"quoted\"");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn writes_typed_parquet_columns() {
+        use arrow_schema::DataType;
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let records = parse_records(RECORDS).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("llmnop-export-{}.parquet", std::process::id()));
+        export_records(&records, ExportFormat::Parquet, &path).unwrap();
+
+        let file = std::fs::File::open(&path).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        let schema = reader.schema().clone();
+        let rows: usize = reader
+            .build()
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .sum();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(rows, 3);
+        let data_type = |name: &str| schema.field_with_name(name).unwrap().data_type().clone();
+        assert_eq!(data_type("request_start_ns"), DataType::UInt64);
+        assert_eq!(data_type("time_to_first_token"), DataType::Float64);
+        assert_eq!(data_type("slo_met"), DataType::Boolean);
+        assert_eq!(data_type("error_code"), DataType::Int64);
+    }
+}
@@ -0,0 +1,451 @@
+use crate::args::{BenchmarkArgs, CompareStat};
+use crate::compare::{Direction, direction, load_summary_file, resolve_summary_path};
+use crate::output::{BenchmarkSummary, SUMMARY_METRICS, default_results_dir};
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// A summary statistic named as `METRIC.STAT`, e.g. `ttft.p99`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricRef {
+    pub metric: &'static str,
+    pub stat: CompareStat,
+}
+
+impl MetricRef {
+    /// Accepts summary field names or the short SLO names; the stat defaults to `avg`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (metric, stat) = match value.rsplit_once('.') {
+            Some((metric, stat)) => (metric.trim(), stat.trim()),
+            None => (value.trim(), "avg"),
+        };
+
+        let canonical = match metric {
+            "ttft" => "time_to_first_token",
+            "ttfo" => "time_to_first_output_token",
+            "itl" | "tpot" => "inter_token_latency",
+            "e2e" => "request_latency",
+            other => other,
+        };
+        let metric = SUMMARY_METRICS
+            .into_iter()
+            .find(|name| *name == canonical)
+            .ok_or_else(|| format!("unknown metric '{metric}'"))?;
+        let stat = CompareStat::from_str(stat, true).map_err(|_| {
+            format!("unknown statistic '{stat}' (use avg, min, p50, p90, p95, p99 or max)")
+        })?;
+
+        Ok(Self { metric, stat })
+    }
+
+    fn value(&self, summary: &BenchmarkSummary) -> Option<f64> {
+        summary
+            .metric(self.metric)
+            .and_then(|stats| self.stat.value(stats))
+    }
+}
+
+impl std::fmt::Display for MetricRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.metric, self.stat.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    AtLeast,
+    AtMost,
+    Above,
+    Below,
+}
+
+impl Comparison {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::AtLeast => ">=",
+            Comparison::AtMost => "<=",
+            Comparison::Above => ">",
+            Comparison::Below => "<",
+        }
+    }
+
+    fn holds(&self, actual: f64, expected: f64) -> bool {
+        match self {
+            Comparison::AtLeast => actual >= expected,
+            Comparison::AtMost => actual <= expected,
+            Comparison::Above => actual > expected,
+            Comparison::Below => actual < expected,
+        }
+    }
+}
+
+/// An absolute bound on a summary statistic, from `--assert`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assertion {
+    pub metric: MetricRef,
+    pub comparison: Comparison,
+    pub value: f64,
+}
+
+impl Assertion {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let op_start = value
+            .find(['<', '>'])
+            .ok_or_else(|| format!("expected METRIC.STAT<OP>VALUE with >=, <=, > or <: {value}"))?;
+        let (metric, rest) = value.split_at(op_start);
+        let (comparison, expected) = if let Some(expected) = rest.strip_prefix(">=") {
+            (Comparison::AtLeast, expected)
+        } else if let Some(expected) = rest.strip_prefix("<=") {
+            (Comparison::AtMost, expected)
+        } else if let Some(expected) = rest.strip_prefix('>') {
+            (Comparison::Above, expected)
+        } else {
+            (Comparison::Below, &rest[1..])
+        };
+
+        let expected = expected.trim();
+        Ok(Self {
+            metric: MetricRef::parse(metric)?,
+            comparison,
+            value: expected
+                .parse()
+                .map_err(|_| format!("invalid number: {expected}"))?,
+        })
+    }
+}
+
+/// A cap on how far a statistic may move in its worse direction, from `--max-regression`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaxRegression {
+    pub metric: MetricRef,
+    pub max_percent: f64,
+}
+
+impl MaxRegression {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (metric, percent) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected METRIC.STAT=PERCENT: {value}"))?;
+        let percent = percent.trim();
+        let max_percent: f64 = percent
+            .strip_suffix('%')
+            .unwrap_or(percent)
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid number: {percent}"))?;
+        if !max_percent.is_finite() || max_percent < 0.0 {
+            return Err(format!("must be a non-negative number: {percent}"));
+        }
+
+        Ok(Self {
+            metric: MetricRef::parse(metric)?,
+            max_percent,
+        })
+    }
+}
+
+/// Pass/fail checks evaluated against a finished run's summary.
+pub struct Gate {
+    baseline: Option<BenchmarkSummary>,
+    max_regressions: Vec<MaxRegression>,
+    assertions: Vec<Assertion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Verdict {
+    pub passed: bool,
+    /// Benchmark id of the baseline run used for regression checks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<String>,
+    pub checks: Vec<CheckResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub check: String,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<f64>,
+    /// How far the statistic moved in its worse direction, relative to the baseline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regression_percent: Option<f64>,
+    pub message: String,
+}
+
+impl Gate {
+    /// Returns `None` when no checks were requested.
+    pub fn from_args(args: &BenchmarkArgs) -> Result<Option<Self>> {
+        if args.max_regressions.is_empty() && args.assertions.is_empty() {
+            return Ok(None);
+        }
+
+        let baseline = match &args.baseline {
+            Some(run) => Some(load_summary_file(&resolve_summary_path(
+                run,
+                &default_results_dir()?,
+            )?)?),
+            None => None,
+        };
+
+        Ok(Some(Self {
+            baseline,
+            max_regressions: args.max_regressions.clone(),
+            assertions: args.assertions.clone(),
+        }))
+    }
+
+    pub fn evaluate(&self, summary: &BenchmarkSummary) -> Verdict {
+        let mut checks = Vec::new();
+
+        if let Some(baseline) = &self.baseline {
+            for regression in &self.max_regressions {
+                checks.push(check_regression(regression, baseline, summary));
+            }
+        }
+        for assertion in &self.assertions {
+            checks.push(check_assertion(assertion, summary));
+        }
+
+        Verdict {
+            passed: checks.iter().all(|check| check.passed),
+            baseline: self
+                .baseline
+                .as_ref()
+                .map(|baseline| baseline.benchmark_id.clone()),
+            checks,
+        }
+    }
+}
+
+fn check_regression(
+    regression: &MaxRegression,
+    baseline: &BenchmarkSummary,
+    summary: &BenchmarkSummary,
+) -> CheckResult {
+    let metric = &regression.metric;
+    let check = format!("{}={}%", metric, regression.max_percent);
+    let actual = metric.value(summary);
+    let baseline_value = metric.value(baseline);
+
+    let (Some(actual), Some(baseline_value)) = (actual, baseline_value) else {
+        return CheckResult {
+            check,
+            passed: false,
+            actual,
+            baseline: baseline_value,
+            regression_percent: None,
+            message: format!("{} is missing from the run or the baseline", metric),
+        };
+    };
+
+    let change = actual - baseline_value;
+    let worse_by = match direction(metric.metric) {
+        Direction::LowerIsBetter => change,
+        Direction::HigherIsBetter => -change,
+        Direction::Neutral => change.abs(),
+    };
+    // A zero baseline has no meaningful percentage; any worsening fails the check.
+    let regression_percent =
+        (baseline_value != 0.0).then(|| worse_by / baseline_value.abs() * 100.0);
+    let passed = match regression_percent {
+        Some(percent) => percent <= regression.max_percent,
+        None => worse_by <= 0.0,
+    };
+
+    CheckResult {
+        check,
+        passed,
+        actual: Some(actual),
+        baseline: Some(baseline_value),
+        regression_percent,
+        message: format!(
+            "{} is {:.2} vs baseline {:.2} ({}, limit {}%)",
+            metric,
+            actual,
+            baseline_value,
+            regression_percent
+                .map(|p| format!("{:+.1}% worse", p))
+                .unwrap_or_else(|| "baseline is zero".to_string()),
+            regression.max_percent
+        ),
+    }
+}
+
+fn check_assertion(assertion: &Assertion, summary: &BenchmarkSummary) -> CheckResult {
+    let metric = &assertion.metric;
+    let check = format!(
+        "{}{}{}",
+        metric,
+        assertion.comparison.as_str(),
+        assertion.value
+    );
+    let actual = metric.value(summary);
+    let passed = actual.is_some_and(|actual| assertion.comparison.holds(actual, assertion.value));
+    let message = match actual {
+        Some(actual) => format!(
+            "{} is {:.2}, expected {} {}",
+            metric,
+            actual,
+            assertion.comparison.as_str(),
+            assertion.value
+        ),
+        None => format!("{} is missing from the run", metric),
+    };
+
+    CheckResult {
+        check,
+        passed,
+        actual,
+        baseline: None,
+        regression_percent: None,
+        message,
+    }
+}
+
+pub fn print_verdict(verdict: &Verdict) {
+    let failed = verdict.checks.iter().filter(|check| !check.passed).count();
+    if verdict.passed {
+        println!("Regression gate: PASSED ({} checks)", verdict.checks.len());
+    } else {
+        println!(
+            "Regression gate: FAILED ({} of {} checks)",
+            failed,
+            verdict.checks.len()
+        );
+    }
+    for check in &verdict.checks {
+        let status = if check.passed { "pass" } else { "FAIL" };
+        println!("  {}  {}", status, check.message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::MetricStats;
+
+    fn stats(avg: f64, p99: f64) -> MetricStats {
+        serde_json::from_value(serde_json::json!({"unit": "ms", "avg": avg, "p99": p99})).unwrap()
+    }
+
+    fn summary(ttft_p99: f64, throughput: f64) -> BenchmarkSummary {
+        let mut summary: BenchmarkSummary = serde_json::from_value(serde_json::json!({
+            "version": "2025-10-05",
+            "schema_version": "2.0",
+            "llmnop_version": "0.9.0",
+            "benchmark_id": "baseline-run",
+            "benchmark_slug": "model_550_150",
+            "start_time_unix_ns": 0,
+            "end_time_unix_ns": 0,
+            "input_config": {
+                "model": "model",
+                "tokenizer": "model",
+                "mean_input_tokens": 550,
+                "stddev_input_tokens": 0,
+                "num_concurrent_requests": 1
+            },
+            "benchmark_duration": {"unit": "s"},
+            "request_count": {"unit": "requests"},
+            "successful_request_count": {"unit": "requests"},
+            "error_request_count": {"unit": "requests"},
+            "error_rate": {"unit": "ratio"},
+            "request_throughput": {"unit": "requests/sec"},
+            "request_latency": {"unit": "ms"},
+            "time_to_first_token": {"unit": "ms"},
+            "inter_token_latency": {"unit": "ms"},
+            "inter_event_latency": {"unit": "ms"},
+            "output_token_throughput_per_request": {"unit": "tokens/sec/request"},
+            "output_token_throughput": {"unit": "tokens/sec"},
+            "total_token_throughput": {"unit": "tokens/sec"},
+            "input_sequence_length": {"unit": "tokens"},
+            "output_token_count": {"unit": "tokens"},
+            "reasoning_token_count": {"unit": "tokens"},
+            "output_sequence_length": {"unit": "tokens"},
+            "total_input_tokens": {"unit": "tokens"},
+            "total_output_tokens": {"unit": "tokens"},
+            "total_reasoning_tokens": {"unit": "tokens"},
+            "total_output_sequence_tokens": {"unit": "tokens"}
+        }))
+        .unwrap();
+        summary.time_to_first_token = stats(ttft_p99 / 2.0, ttft_p99);
+        summary.output_token_throughput = stats(throughput, throughput);
+        summary
+    }
+
+    fn gate(max_regressions: &[&str], assertions: &[&str]) -> Gate {
+        Gate {
+            baseline: Some(summary(200.0, 1000.0)),
+            max_regressions: max_regressions
+                .iter()
+                .map(|spec| MaxRegression::parse(spec).unwrap())
+                .collect(),
+            assertions: assertions
+                .iter()
+                .map(|spec| Assertion::parse(spec).unwrap())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn parses_metric_refs_with_aliases() {
+        let metric = MetricRef::parse("ttft.p99").unwrap();
+        assert_eq!(metric.metric, "time_to_first_token");
+        assert_eq!(metric.stat, CompareStat::P99);
+        assert_eq!(metric.to_string(), "time_to_first_token.p99");
+
+        assert_eq!(
+            MetricRef::parse("output_token_throughput").unwrap().stat,
+            CompareStat::Avg
+        );
+        assert!(MetricRef::parse("latency.p99").is_err());
+        assert!(MetricRef::parse("ttft.p42").is_err());
+    }
+
+    #[test]
+    fn parses_assertions_and_regression_limits() {
+        let assertion = Assertion::parse("output_token_throughput.avg>=800").unwrap();
+        assert_eq!(assertion.comparison, Comparison::AtLeast);
+        assert_eq!(assertion.value, 800.0);
+        assert_eq!(
+            Assertion::parse("error_rate<0.01").unwrap().comparison,
+            Comparison::Below
+        );
+        assert!(Assertion::parse("error_rate=0").is_err());
+
+        assert_eq!(
+            MaxRegression::parse("ttft.p99=10%").unwrap().max_percent,
+            10.0
+        );
+        assert_eq!(MaxRegression::parse("itl.p50=5").unwrap().max_percent, 5.0);
+        assert!(MaxRegression::parse("ttft.p99=-5%").is_err());
+    }
+
+    #[test]
+    fn regression_respects_metric_direction() {
+        let gate = gate(&["ttft.p99=10%", "output_token_throughput.avg=5%"], &[]);
+
+        let verdict = gate.evaluate(&summary(215.0, 980.0));
+        assert!(verdict.passed, "{verdict:?}");
+        assert_eq!(verdict.baseline.as_deref(), Some("baseline-run"));
+        assert_eq!(verdict.checks[0].regression_percent, Some(7.5));
+
+        let verdict = gate.evaluate(&summary(150.0, 900.0));
+        assert!(!verdict.passed);
+        assert!(verdict.checks[0].passed);
+        assert!(!verdict.checks[1].passed);
+        assert_eq!(verdict.checks[1].regression_percent, Some(10.0));
+    }
+
+    #[test]
+    fn assertions_fail_on_missing_metrics() {
+        let gate = gate(&[], &["output_token_throughput.avg>=800", "ttfo.p99<=100"]);
+        let verdict = gate.evaluate(&summary(200.0, 850.0));
+
+        assert!(!verdict.passed);
+        assert!(verdict.checks[0].passed);
+        assert!(!verdict.checks[1].passed);
+        assert_eq!(verdict.checks[1].actual, None);
+    }
+}
@@ -0,0 +1,594 @@
+use crate::benchmark::BenchmarkResult;
+use crate::error::RequestFailure;
+use crate::output::BenchmarkSummary;
+use crate::runs::format_unix_time;
+use serde_json::Value;
+use std::fmt::Write;
+
+const WIDTH: f64 = 480.0;
+const HEIGHT: f64 = 260.0;
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_RIGHT: f64 = 16.0;
+const MARGIN_TOP: f64 = 12.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+/// Enough points to draw a smooth CDF without bloating the file on large runs.
+const MAX_CDF_POINTS: usize = 500;
+
+const STYLE: &str = "
+body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; margin: 2rem auto; max-width: 1040px; color: #1f2328; padding: 0 1rem; }
+h1 { margin-bottom: 0.25rem; }
+.subtitle { color: #59636e; margin-top: 0; }
+table { border-collapse: collapse; margin: 0.5rem 0 1.5rem; font-size: 0.9rem; }
+th, td { border-bottom: 1px solid #d1d9e0; padding: 0.3rem 0.75rem; text-align: left; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+.charts { display: grid; grid-template-columns: repeat(auto-fill, minmax(480px, 1fr)); gap: 1rem; }
+figure { margin: 0; }
+figcaption { font-weight: 600; margin-bottom: 0.25rem; }
+svg { width: 100%; height: auto; font-size: 11px; }
+svg .axis { stroke: #59636e; }
+svg .grid { stroke: #e6eaef; }
+svg text { fill: #59636e; }
+svg .bar { fill: #0969da; }
+svg .wait { fill: #afb8c1; }
+svg .line { fill: none; stroke: #0969da; stroke-width: 1.5; }
+";
+
+/// Renders a saved run as one HTML page with inline SVG charts and no external assets.
+pub fn render_html(
+    summary: &BenchmarkSummary,
+    results: &[Result<BenchmarkResult, RequestFailure>],
+) -> String {
+    let successful: Vec<&BenchmarkResult> = results.iter().flatten().collect();
+    let ttft_ms: Vec<f64> = successful
+        .iter()
+        .map(|br| br.ttft.as_secs_f64() * 1000.0)
+        .collect();
+    let itl_ms: Vec<f64> = successful
+        .iter()
+        .map(|br| br.inter_token_latency_s * 1000.0)
+        .collect();
+    let e2e_ms: Vec<f64> = successful
+        .iter()
+        .map(|br| br.total_latency.as_secs_f64() * 1000.0)
+        .collect();
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>llmnop report: {id}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>{model}</h1>\n<p class=\"subtitle\">Run {id}, started {started} UTC, llmnop {version}</p>\n",
+        id = escape(&summary.benchmark_id),
+        model = escape(&summary.input_config.model),
+        started = format_unix_time(summary.start_time_unix_ns / 1_000_000_000),
+        version = escape(&summary.llmnop_version),
+    );
+
+    html.push_str("<h2>Configuration</h2>\n");
+    html.push_str(&config_table(summary));
+    html.push_str("<h2>Metrics</h2>\n");
+    html.push_str(&metrics_table(summary));
+    if !summary.error_summary.is_empty() {
+        html.push_str(
+            "<h2>Errors</h2>\n<table>\n<tr><th>Count</th><th>Type</th><th>Code</th><th>Message</th></tr>\n",
+        );
+        for entry in &summary.error_summary {
+            let _ = writeln!(
+                html,
+                "<tr><td class=\"num\">{}</td><td>{}</td><td class=\"num\">{}</td><td>{}</td></tr>",
+                entry.count,
+                escape(&entry.error_type),
+                entry.code,
+                escape(&entry.message)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h2>Latency</h2>\n<div class=\"charts\">\n");
+    for (name, values) in [
+        ("Time to first token", &ttft_ms),
+        ("Inter-token latency", &itl_ms),
+        ("End-to-end latency", &e2e_ms),
+    ] {
+        html.push_str(&figure(
+            &format!("{name} histogram"),
+            histogram_svg(values, "ms"),
+        ));
+        html.push_str(&figure(&format!("{name} CDF"), cdf_svg(values, "ms")));
+    }
+    html.push_str("</div>\n");
+
+    let run_start_ns = summary.start_time_unix_ns;
+    let run_end_ns = summary.end_time_unix_ns.max(
+        successful
+            .iter()
+            .map(|br| br.request_end_unix_ns)
+            .max()
+            .unwrap_or_default(),
+    );
+    html.push_str("<h2>Timeline</h2>\n<div class=\"charts\">\n");
+    html.push_str(&figure(
+        "Requests (grey: waiting for first token)",
+        timeline_svg(&successful, run_start_ns, run_end_ns),
+    ));
+    html.push_str(&figure(
+        "Output throughput",
+        throughput_svg(&successful, run_start_ns, run_end_ns),
+    ));
+    html.push_str("</div>\n</body>\n</html>\n");
+    html
+}
+
+fn config_table(summary: &BenchmarkSummary) -> String {
+    let mut html = String::from("<table>\n");
+    if let Ok(Value::Object(fields)) = serde_json::to_value(&summary.input_config) {
+        for (name, value) in fields {
+            let value = match value {
+                Value::String(text) => text,
+                other => other.to_string(),
+            };
+            let _ = writeln!(
+                html,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape(&name),
+                escape(&value)
+            );
+        }
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn metrics_table(summary: &BenchmarkSummary) -> String {
+    let mut html = String::from(
+        "<table>\n<tr><th>Metric</th><th>Unit</th><th>avg</th><th>min</th><th>p50</th>\
+         <th>p90</th><th>p99</th><th>max</th></tr>\n",
+    );
+    for (name, stats) in summary.metrics() {
+        let Some(stats) = stats.filter(|stats| stats.avg.is_some()) else {
+            continue;
+        };
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td>{}</td>",
+            name,
+            escape(&stats.unit)
+        );
+        for value in [
+            stats.avg, stats.min, stats.p50, stats.p90, stats.p99, stats.max,
+        ] {
+            let value = value
+                .map(|v| format!("{:.2}", v))
+                .unwrap_or_else(|| "-".to_string());
+            let _ = write!(html, "<td class=\"num\">{value}</td>");
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn figure(caption: &str, svg: String) -> String {
+    format!(
+        "<figure>\n<figcaption>{}</figcaption>\n{}</figure>\n",
+        escape(caption),
+        svg
+    )
+}
+
+/// Data-to-pixel mapping for one chart, with axes drawn by `render`.
+struct Plot {
+    x_min: f64,
+    x_max: f64,
+    y_min: f64,
+    y_max: f64,
+    x_label: String,
+    y_label: String,
+}
+
+impl Plot {
+    fn new(x: (f64, f64), y: (f64, f64), x_label: &str, y_label: &str) -> Self {
+        // A flat range would divide by zero; widen it so the data sits on the left or bottom edge.
+        let widen = |(min, max): (f64, f64)| {
+            if max > min {
+                (min, max)
+            } else {
+                (min, min + 1.0)
+            }
+        };
+        let (x_min, x_max) = widen(x);
+        let (y_min, y_max) = widen(y);
+        Self {
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+            x_label: x_label.to_string(),
+            y_label: y_label.to_string(),
+        }
+    }
+
+    fn px(&self, x: f64) -> f64 {
+        MARGIN_LEFT
+            + (x - self.x_min) / (self.x_max - self.x_min) * (WIDTH - MARGIN_LEFT - MARGIN_RIGHT)
+    }
+
+    fn py(&self, y: f64) -> f64 {
+        HEIGHT
+            - MARGIN_BOTTOM
+            - (y - self.y_min) / (self.y_max - self.y_min) * (HEIGHT - MARGIN_TOP - MARGIN_BOTTOM)
+    }
+
+    fn render(&self, body: &str) -> String {
+        let mut svg =
+            format!("<svg viewBox=\"0 0 {WIDTH} {HEIGHT}\" role=\"img\">\n<g class=\"grid\">\n");
+        let x_ticks = nice_ticks(self.x_min, self.x_max);
+        let y_ticks = nice_ticks(self.y_min, self.y_max);
+        for &y in &y_ticks {
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{:.1}\" x2=\"{:.1}\" y1=\"{y:.1}\" y2=\"{y:.1}\"/>",
+                MARGIN_LEFT,
+                WIDTH - MARGIN_RIGHT,
+                y = self.py(y)
+            );
+        }
+        svg.push_str("</g>\n");
+        svg.push_str(body);
+
+        let (left, bottom) = (MARGIN_LEFT, HEIGHT - MARGIN_BOTTOM);
+        let _ = writeln!(
+            svg,
+            "<g class=\"axis\"><line x1=\"{left}\" x2=\"{left}\" y1=\"{MARGIN_TOP}\" y2=\"{bottom}\"/>\
+             <line x1=\"{left}\" x2=\"{right}\" y1=\"{bottom}\" y2=\"{bottom}\"/></g>",
+            right = WIDTH - MARGIN_RIGHT
+        );
+        for &x in &x_ticks {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+                self.px(x),
+                bottom + 14.0,
+                format_tick(x)
+            );
+        }
+        for &y in &y_ticks {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+                left - 6.0,
+                self.py(y) + 4.0,
+                format_tick(y)
+            );
+        }
+        let _ = write!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>\n\
+             <text transform=\"translate(14 {:.1}) rotate(-90)\" text-anchor=\"middle\">{}</text>\n</svg>\n",
+            (left + WIDTH - MARGIN_RIGHT) / 2.0,
+            HEIGHT - 6.0,
+            escape(&self.x_label),
+            (MARGIN_TOP + bottom) / 2.0,
+            escape(&self.y_label)
+        );
+        svg
+    }
+}
+
+fn histogram_svg(values: &[f64], unit: &str) -> String {
+    let (min, max) = range(values);
+    let counts = histogram(values, min, max);
+    let bin_width = (max - min) / counts.len() as f64;
+    let max_count = counts.iter().copied().max().unwrap_or_default();
+
+    let plot = Plot::new((min, max), (0.0, max_count as f64), unit, "requests");
+    let mut body = String::from("<g class=\"bar\">\n");
+    for (index, &count) in counts.iter().enumerate().filter(|(_, count)| **count > 0) {
+        let x0 = plot.px(min + index as f64 * bin_width);
+        let x1 = plot.px(min + (index + 1) as f64 * bin_width);
+        let y = plot.py(count as f64);
+        let _ = writeln!(
+            body,
+            "<rect x=\"{x0:.1}\" y=\"{y:.1}\" width=\"{:.1}\" height=\"{:.1}\"/>",
+            (x1 - x0 - 1.0).max(0.5),
+            plot.py(0.0) - y
+        );
+    }
+    body.push_str("</g>\n");
+    plot.render(&body)
+}
+
+/// Equal-width bin counts over `[min, max]`, about the square root of the sample count.
+fn histogram(values: &[f64], min: f64, max: f64) -> Vec<usize> {
+    let bins = ((values.len() as f64).sqrt().ceil() as usize).clamp(5, 40);
+    let mut counts = vec![0; bins];
+    let width = (max - min) / bins as f64;
+    for &value in values {
+        let index = if width > 0.0 {
+            ((value - min) / width) as usize
+        } else {
+            0
+        };
+        counts[index.min(bins - 1)] += 1;
+    }
+    counts
+}
+
+fn cdf_svg(values: &[f64], unit: &str) -> String {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let plot = Plot::new(range(&sorted), (0.0, 1.0), unit, "fraction of requests");
+
+    let step = sorted.len().div_ceil(MAX_CDF_POINTS).max(1);
+    let mut points = String::new();
+    for (index, value) in sorted.iter().enumerate() {
+        if index % step == 0 || index + 1 == sorted.len() {
+            let fraction = (index + 1) as f64 / sorted.len() as f64;
+            let _ = write!(points, "{:.1},{:.1} ", plot.px(*value), plot.py(fraction));
+        }
+    }
+    plot.render(&format!(
+        "<polyline class=\"line\" points=\"{}\"/>\n",
+        points.trim_end()
+    ))
+}
+
+/// One bar per request from start to end, ordered by start time.
+fn timeline_svg(results: &[&BenchmarkResult], run_start_ns: u64, run_end_ns: u64) -> String {
+    let mut requests = results.to_vec();
+    requests.sort_by_key(|br| br.request_start_unix_ns);
+    let seconds = |ns: u64| ns.saturating_sub(run_start_ns) as f64 / 1e9;
+
+    let plot = Plot::new(
+        (0.0, seconds(run_end_ns)),
+        (0.0, requests.len() as f64),
+        "seconds since start",
+        "request",
+    );
+    let row_height = (plot.py(0.0) - plot.py(1.0)).max(0.2);
+    let mut waiting = String::from("<g class=\"wait\">\n");
+    let mut streaming = String::from("<g class=\"bar\">\n");
+    for (index, br) in requests.iter().enumerate() {
+        let start = seconds(br.request_start_unix_ns);
+        let first_token = seconds(br.first_token_unix_ns());
+        let end = seconds(br.request_end_unix_ns).max(first_token);
+        let y = plot.py((index + 1) as f64);
+        for (group, from, to) in [
+            (&mut waiting, start, first_token),
+            (&mut streaming, first_token, end),
+        ] {
+            let _ = writeln!(
+                group,
+                "<rect x=\"{:.1}\" y=\"{y:.2}\" width=\"{:.2}\" height=\"{row_height:.2}\"/>",
+                plot.px(from),
+                (plot.px(to) - plot.px(from)).max(0.5)
+            );
+        }
+    }
+    waiting.push_str("</g>\n");
+    streaming.push_str("</g>\n");
+    plot.render(&(waiting + &streaming))
+}
+
+fn throughput_svg(results: &[&BenchmarkResult], run_start_ns: u64, run_end_ns: u64) -> String {
+    let duration_s = run_end_ns.saturating_sub(run_start_ns) as f64 / 1e9;
+    let rates = throughput_over_time(results, run_start_ns, duration_s);
+    let bin_width = duration_s / rates.len() as f64;
+    let max_rate = rates.iter().copied().fold(0.0, f64::max);
+
+    let plot = Plot::new(
+        (0.0, duration_s),
+        (0.0, max_rate),
+        "seconds since start",
+        "tokens/s",
+    );
+    let mut points = String::new();
+    for (index, rate) in rates.iter().enumerate() {
+        let y = plot.py(*rate);
+        let _ = write!(
+            points,
+            "{:.1},{y:.1} {:.1},{y:.1} ",
+            plot.px(index as f64 * bin_width),
+            plot.px((index + 1) as f64 * bin_width)
+        );
+    }
+    plot.render(&format!(
+        "<polyline class=\"line\" points=\"{}\"/>\n",
+        points.trim_end()
+    ))
+}
+
+/// Output tokens per second in equal time bins, spreading each request's tokens evenly
+/// between its first token and its end.
+fn throughput_over_time(
+    results: &[&BenchmarkResult],
+    run_start_ns: u64,
+    duration_s: f64,
+) -> Vec<f64> {
+    let bins = (duration_s.ceil() as usize).clamp(10, 120);
+    let bin_width = if duration_s > 0.0 {
+        duration_s / bins as f64
+    } else {
+        1.0
+    };
+    let mut tokens = vec![0.0; bins];
+
+    for br in results {
+        let count = (br.output_tokens + br.reasoning_tokens) as f64;
+        let start = br.first_token_unix_ns().saturating_sub(run_start_ns) as f64 / 1e9;
+        let end = (br.request_end_unix_ns.saturating_sub(run_start_ns) as f64 / 1e9).max(start);
+        if end - start <= 0.0 {
+            let index = ((end / bin_width) as usize).min(bins - 1);
+            tokens[index] += count;
+            continue;
+        }
+        for (index, bin) in tokens.iter_mut().enumerate() {
+            let bin_start = index as f64 * bin_width;
+            let overlap = end.min(bin_start + bin_width) - start.max(bin_start);
+            if overlap > 0.0 {
+                *bin += count * overlap / (end - start);
+            }
+        }
+    }
+
+    tokens.into_iter().map(|count| count / bin_width).collect()
+}
+
+fn range(values: &[f64]) -> (f64, f64) {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if min.is_finite() && max.is_finite() {
+        (min, max)
+    } else {
+        (0.0, 1.0)
+    }
+}
+
+/// About five round-numbered ticks (1, 2 or 5 times a power of ten) within `[min, max]`.
+fn nice_ticks(min: f64, max: f64) -> Vec<f64> {
+    let rough_step = (max - min) / 5.0;
+    if rough_step <= 0.0 || !rough_step.is_finite() {
+        return vec![min];
+    }
+    let magnitude = 10_f64.powf(rough_step.log10().floor());
+    let step = match rough_step / magnitude {
+        f if f <= 1.0 => 1.0,
+        f if f <= 2.0 => 2.0,
+        f if f <= 5.0 => 5.0,
+        _ => 10.0,
+    } * magnitude;
+
+    let mut ticks = Vec::new();
+    let mut tick = (min / step).ceil() * step;
+    while tick <= max + step * 1e-9 {
+        ticks.push(tick);
+        tick += step;
+    }
+    ticks
+}
+
+fn format_tick(value: f64) -> String {
+    if value.abs() >= 1000.0 || value.fract().abs() < 1e-9 {
+        format!("{:.0}", value)
+    } else {
+        let formatted = format!("{:.3}", value);
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::output::{BenchmarkConfig, summarize_results, test_config};
+    use std::time::Duration;
+
+    fn result(start_s: u64, ttft_ms: u64, end_s: u64, output_tokens: u32) -> BenchmarkResult {
+        BenchmarkResult {
+            ttft: Duration::from_millis(ttft_ms),
+            ttfo: None,
+            total_latency: Duration::from_secs(end_s - start_s),
+            throughput: 50.0,
+            input_tokens: 100,
+            output_tokens,
+            reasoning_tokens: 0,
+            inter_token_latency_s: 0.01,
+            inter_event_latency_s: 0.01,
+            inter_event_gaps_s: Vec::new(),
+            total_tokens: 100 + output_tokens,
+            request_start_unix_ns: start_s * 1_000_000_000,
+            request_end_unix_ns: end_s * 1_000_000_000,
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: None,
+            requested_output_tokens: None,
+            finish_reason: None,
+            attempts: 1,
+            generated_text: String::new(),
+        }
+    }
+
+    #[test]
+    fn nice_ticks_use_round_steps() {
+        assert_eq!(nice_ticks(0.0, 10.0), vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+        assert_eq!(nice_ticks(13.0, 38.0), vec![15.0, 20.0, 25.0, 30.0, 35.0]);
+        assert_eq!(nice_ticks(5.0, 5.0), vec![5.0]);
+        assert_eq!(format_tick(0.25), "0.25");
+        assert_eq!(format_tick(1500.0), "1500");
+    }
+
+    #[test]
+    fn histogram_counts_every_value() {
+        let values: Vec<f64> = (0..100).map(f64::from).collect();
+        let counts = histogram(&values, 0.0, 99.0);
+        assert_eq!(counts.len(), 10);
+        assert_eq!(counts.iter().sum::<usize>(), 100);
+        assert_eq!(histogram(&[3.0, 3.0], 3.0, 3.0)[0], 2);
+    }
+
+    #[test]
+    fn throughput_spreads_tokens_over_streaming_time() {
+        // 100 tokens streamed from 1s to 3s, plus 10 tokens that arrive all at once at 6s.
+        let streamed = result(0, 1000, 3, 100);
+        let instant = result(5, 1000, 6, 10);
+        let rates = throughput_over_time(&[&streamed, &instant], 0, 10.0);
+
+        assert_eq!(rates.len(), 10);
+        assert_eq!(rates[0], 0.0);
+        assert_eq!(rates[1], 50.0);
+        assert_eq!(rates[2], 50.0);
+        assert_eq!(rates[6], 10.0);
+        assert_eq!(rates.iter().sum::<f64>(), 110.0);
+    }
+
+    #[test]
+    fn renders_a_self_contained_page() {
+        let config = BenchmarkConfig {
+            model: "org/<model>",
+            tokenizer: "org/model",
+            mean_input_tokens: 100,
+            num_concurrent_requests: 2,
+            ..test_config()
+        };
+        let results = vec![
+            Ok(result(0, 100, 2, 20)),
+            Ok(result(1, 200, 4, 30)),
+            Err(RequestFailure::new(ErrorKind::Timeout, "timed out")),
+        ];
+        let summary = summarize_results(
+            "1700000000_123456789",
+            &config,
+            &results,
+            Duration::from_secs(4),
+            0,
+            4_000_000_000,
+        );
+
+        let html = render_html(&summary, &results);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>org/&lt;model&gt;</h1>"));
+        assert!(html.contains("<td>timeout</td><td class=\"num\">0</td><td>timed out</td>"));
+        assert_eq!(html.matches("<svg").count(), 8);
+        assert!(!html.contains("http://") && !html.contains("https://"));
+        assert!(!html.contains("<script"));
+    }
+}
@@ -1,279 +1,122 @@
 mod args;
 mod benchmark;
 mod client;
+mod compare;
+mod dataset;
+mod error;
+mod export;
+mod gate;
+mod html;
+#[cfg(feature = "prometheus")]
+mod metrics;
+mod mock;
 mod output;
 mod prompt;
+mod report;
+mod runner;
+mod runs;
+mod schedule;
 #[cfg(feature = "self-update")]
 mod self_update;
+mod slo;
+mod sweep;
 mod tokens;
 
 use anyhow::Result;
-#[cfg(feature = "self-update")]
-use args::Command;
-use args::{ApiType, Args, OutputFormat};
-use async_openai::{Client, config::OpenAIConfig};
-use benchmark::{BenchmarkRequest, BenchmarkResult, run_benchmark};
+use args::{Args, Command, OutputFormat};
 use clap::Parser;
-use futures::{StreamExt, stream::FuturesUnordered};
-use indicatif::{ProgressBar, ProgressStyle};
-use prompt::{PromptConfig, generate_prompt};
-use rand::prelude::*;
-use rand_distr::Normal;
-use std::io::{self, IsTerminal};
-use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::time;
-
-use output::{BenchmarkConfig, print_summary_to_stdout, write_results_json};
-
-fn unix_time_now_ns() -> u64 {
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .ok()
-        .and_then(|d| u64::try_from(d.as_nanos()).ok())
-        .unwrap_or_default()
-}
-
-fn sample_max_tokens(mean: u32, stddev: u32) -> u32 {
-    if stddev == 0 {
-        return mean.max(1);
-    }
-
-    let dist = Normal::new(mean as f64, stddev as f64).unwrap();
-    let mut rng = rand::rng();
+use export::export_run;
+use gate::{Gate, print_verdict};
+use runner::{BenchmarkTarget, LoadProfile, benchmark_config, prompt_source, run_load};
+use std::process::ExitCode;
 
-    loop {
-        let sample = dist.sample(&mut rng);
-        if sample >= 1.0 {
-            return sample.ceil() as u32;
-        }
-    }
-}
-
-async fn run_benchmark_task(
-    client: Arc<Client<OpenAIConfig>>,
-    api_type: ApiType,
-    request: BenchmarkRequest,
-) -> Result<BenchmarkResult> {
-    run_benchmark(&client, api_type, request).await
-}
+use output::{default_results_dir, write_results_json};
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     let args = Args::parse();
 
-    #[cfg(feature = "self-update")]
-    if let Some(Command::Update) = args.command {
-        return self_update::run_update().await;
-    }
-
-    let (url, model) = match args.require_benchmark_args() {
-        Ok(values) => values,
-        Err(err) => err.exit(),
-    };
-    let model = model.to_string();
-
-    let mut openai_config = OpenAIConfig::new().with_api_base(url);
-    if let Some(api_key) = args.api_key.clone() {
-        openai_config = openai_config.with_api_key(api_key);
-    }
-    let client = Arc::new(Client::with_config(openai_config));
-
-    let tokenizer = args.tokenizer.clone().unwrap_or_else(|| model.clone());
-    let api = args.api;
-    let use_server_token_count = args.use_server_token_count;
-
-    let overall_start = Instant::now();
-    let overall_start_unix_ns = unix_time_now_ns();
-
-    let prompt_config = PromptConfig {
-        mean_input_tokens: args.mean_input_tokens,
-        stddev_input_tokens: args.stddev_input_tokens,
-    };
-
-    let mut prompts = Vec::with_capacity(args.max_num_completed_requests as usize);
-    for _ in 0..args.max_num_completed_requests {
-        let prompt = generate_prompt(&prompt_config, &tokenizer)?;
-        prompts.push(prompt);
-    }
-
-    let mut all_results = Vec::with_capacity(args.max_num_completed_requests as usize);
-
-    let mut in_flight = FuturesUnordered::new();
-    let mut next_request_index = 0;
-
-    let output_format = args.effective_output_format();
-    let disable_progress =
-        matches!(output_format, OutputFormat::None) || !io::stderr().is_terminal();
-
-    let pb = if disable_progress {
-        ProgressBar::hidden()
-    } else {
-        let pb = ProgressBar::new(args.max_num_completed_requests as u64);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template(
-                    "{spinner:.green} [{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} ({eta})",
-                )
-                .unwrap()
-                .progress_chars("##-"),
-        );
-        pb.tick();
-        pb
-    };
-
-    let timeout_duration = Duration::from_secs(args.timeout);
-    let timeout_future = time::sleep(timeout_duration);
-    let mut timeout_occurred = false;
-
-    tokio::pin!(timeout_future);
-
-    while next_request_index < args.max_num_completed_requests
-        && in_flight.len() < args.num_concurrent_requests as usize
-    {
-        let prompt = &prompts[next_request_index as usize];
-        let model_name = model.clone();
-        let prompt_clone = prompt.clone();
-        let client_clone = client.clone();
-        let tokenizer_clone = tokenizer.clone();
-        let max_tokens = args
-            .mean_output_tokens
-            .map(|mean| sample_max_tokens(mean, args.stddev_output_tokens));
-
-        let request = BenchmarkRequest {
-            model: model_name,
-            prompt: prompt_clone,
-            max_tokens,
-            tokenizer: tokenizer_clone,
-            use_server_token_count,
-        };
-
-        in_flight.push(tokio::spawn(run_benchmark_task(client_clone, api, request)));
-        next_request_index += 1;
-    }
-
-    loop {
-        tokio::select! {
-            _ = &mut timeout_future, if !timeout_occurred => {
-                eprintln!(
-                    "\nTimeout reached after {} seconds. Collecting completed results...",
-                    args.timeout
-                );
-                timeout_occurred = true;
-            }
-
-            Some(done) = in_flight.next(), if !in_flight.is_empty() => {
-                match done {
-                    Ok(Ok(benchmark_result)) => {
-                        all_results.push(Ok(benchmark_result));
-                    }
-                    Ok(Err(e)) => {
-                        eprintln!("Request failed: {:?}", e);
-                        all_results.push(Err(e.to_string()));
-                    }
-                    Err(tokio_err) => {
-                        eprintln!("Tokio Join Error: {:?}", tokio_err);
-                        all_results.push(Err(format!("Tokio Join Error: {:?}", tokio_err)));
-                    }
-                }
-
-                pb.inc(1);
-
-                if !timeout_occurred && next_request_index < args.max_num_completed_requests {
-                    let prompt = &prompts[next_request_index as usize];
-                    let model_name = model.clone();
-                    let prompt_clone = prompt.clone();
-                    let client_clone = client.clone();
-                    let tokenizer_clone = tokenizer.clone();
-                    let max_tokens = args
-                        .mean_output_tokens
-                        .map(|mean| sample_max_tokens(mean, args.stddev_output_tokens));
-
-                    let request = BenchmarkRequest {
-                        model: model_name,
-                        prompt: prompt_clone,
-                        max_tokens,
-                        tokenizer: tokenizer_clone,
-                        use_server_token_count,
-                    };
-
-                    in_flight.push(tokio::spawn(run_benchmark_task(
-                        client_clone,
-                        api,
-                        request,
-                    )));
-                    next_request_index += 1;
-                }
-            }
-
-            _ = async {}, if in_flight.is_empty() => {
-                break;
-            }
+    match &args.command {
+        Some(Command::Sweep(sweep_args)) => return sweep::run_sweep(sweep_args).await,
+        Some(Command::Compare(compare_args)) => {
+            compare::run_compare(compare_args)?;
+            return Ok(ExitCode::SUCCESS);
         }
-
-        if all_results.len() >= args.max_num_completed_requests as usize {
-            break;
+        Some(Command::Report(report_args)) => {
+            report::run_report(report_args)?;
+            return Ok(ExitCode::SUCCESS);
         }
+        Some(Command::Export(export_args)) => {
+            export::run_export(export_args)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Runs(runs_args)) => {
+            runs::run_runs(runs_args)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::MockServer(mock_args)) => {
+            mock::run_mock_server(mock_args).await?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "self-update")]
+        Some(Command::Update) => {
+            self_update::run_update().await?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        None => {}
     }
 
-    pb.finish_and_clear();
-
-    let overall_end = Instant::now();
-    let overall_end_unix_ns = unix_time_now_ns();
-    if timeout_occurred {
-        eprintln!(
-            "Benchmark terminated due to timeout after {} seconds.",
-            args.timeout
-        );
-    }
-
-    let mut successful_results = Vec::new();
-    let mut total_output_tokens = 0_u64;
-    let mut total_reasoning_tokens = 0_u64;
-    let num_errors = all_results.iter().filter(|r| r.is_err()).count();
-
-    for br in all_results.iter().flatten() {
-        total_output_tokens += br.output_tokens as u64;
-        total_reasoning_tokens += br.reasoning_tokens as u64;
-        successful_results.push(br.clone());
-    }
-
-    let config = BenchmarkConfig {
-        model: &model,
-        tokenizer: &tokenizer,
-        mean_input_tokens: args.mean_input_tokens,
-        stddev_input_tokens: args.stddev_input_tokens,
-        mean_output_tokens: args.mean_output_tokens,
-        stddev_output_tokens: args.stddev_output_tokens,
-        num_concurrent_requests: args.num_concurrent_requests,
+    let args = &args.benchmark;
+    let target = match BenchmarkTarget::from_args(args) {
+        Ok(target) => target,
+        Err(err) => match err.downcast::<clap::Error>() {
+            Ok(usage) => usage.exit(),
+            Err(err) => return Err(err),
+        },
     };
+    let gate = Gate::from_args(args)?;
+    #[cfg(feature = "prometheus")]
+    runner::start_metrics_listener(args).await?;
+    let prompts = prompt_source(args, &target)?;
+    let load = LoadProfile::from_args(args);
+
+    let run = run_load(args, &target, &prompts, load).await?;
 
+    let config = benchmark_config(args, &target, load, gate.as_ref());
     let written_results = write_results_json(
+        &default_results_dir()?,
         &config,
-        &all_results,
-        overall_start,
-        overall_end,
-        overall_start_unix_ns,
-        overall_end_unix_ns,
+        &run.warmup_results,
+        &run.results,
+        &run.drained_results,
+        run.duration(),
+        run.start_unix_ns,
+        run.end_unix_ns,
     )?;
+    export_run(&written_results.dir, &args.export)?;
 
-    match output_format {
+    match args.effective_output_format() {
         OutputFormat::Table => {
-            print_summary_to_stdout(
-                &successful_results,
-                num_errors,
-                total_output_tokens,
-                total_reasoning_tokens,
-                overall_start,
-                overall_end,
-            );
+            run.print_summary(&args.slos);
+            if let Some(verdict) = &written_results.summary.verdict {
+                println!();
+                print_verdict(verdict);
+            }
         }
         OutputFormat::Json => {
             println!("{}", serde_json::to_string(&written_results.summary)?);
         }
         OutputFormat::None => {}
     }
-    Ok(())
+
+    let passed = written_results
+        .summary
+        .verdict
+        .as_ref()
+        .is_none_or(|verdict| verdict.passed);
+    Ok(if passed {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
 }
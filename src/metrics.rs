@@ -0,0 +1,376 @@
+use crate::benchmark::BenchmarkResult;
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Bucket bounds in seconds for time to first token and end-to-end latency.
+const LATENCY_BUCKETS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+/// Bucket bounds in seconds for inter-token latency.
+const INTER_TOKEN_BUCKETS: [f64; 10] =
+    [0.001, 0.0025, 0.005, 0.01, 0.02, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+/// Set once `--metrics-listen` starts the exporter; recording is a no-op before that.
+static LIVE_METRICS: OnceLock<Arc<LiveMetrics>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    bounds: &'static [f64],
+    /// Per-bucket counts, not cumulative; the last slot is `+Inf`.
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let index = self
+            .bounds
+            .iter()
+            .position(|&bound| value <= bound)
+            .unwrap_or(self.bounds.len());
+        self.counts[index] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} histogram");
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum {}\n{name}_count {}", self.sum, self.count);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Registry {
+    requests_started: u64,
+    requests_completed: u64,
+    requests_errored: u64,
+    retries: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    time_to_first_token: Histogram,
+    inter_token_latency: Histogram,
+    request_latency: Histogram,
+}
+
+/// Counters and histograms updated as requests finish, for scraping during long runs.
+#[derive(Debug)]
+pub struct LiveMetrics {
+    registry: Mutex<Registry>,
+}
+
+impl Default for LiveMetrics {
+    fn default() -> Self {
+        Self {
+            registry: Mutex::new(Registry {
+                requests_started: 0,
+                requests_completed: 0,
+                requests_errored: 0,
+                retries: 0,
+                input_tokens: 0,
+                output_tokens: 0,
+                time_to_first_token: Histogram::new(&LATENCY_BUCKETS),
+                inter_token_latency: Histogram::new(&INTER_TOKEN_BUCKETS),
+                request_latency: Histogram::new(&LATENCY_BUCKETS),
+            }),
+        }
+    }
+}
+
+impl LiveMetrics {
+    fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+        // A panic while holding the lock leaves plain counters behind, which are still usable.
+        self.registry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn request_started(&self) {
+        self.registry().requests_started += 1;
+    }
+
+    fn request_finished<E>(&self, result: &Result<BenchmarkResult, E>) {
+        let mut registry = self.registry();
+        match result {
+            Ok(br) => {
+                registry.requests_completed += 1;
+                registry.input_tokens += u64::from(br.input_tokens);
+                registry.output_tokens += u64::from(br.output_tokens + br.reasoning_tokens);
+                registry.time_to_first_token.observe(br.ttft.as_secs_f64());
+                // Every gap, not the per-request average, so stalls inside a stream show up.
+                for &gap_s in &br.inter_event_gaps_s {
+                    registry.inter_token_latency.observe(gap_s);
+                }
+                registry
+                    .request_latency
+                    .observe(br.total_latency.as_secs_f64());
+            }
+            Err(_) => registry.requests_errored += 1,
+        }
+    }
+
+    fn request_retried(&self) {
+        self.registry().retries += 1;
+    }
+
+    /// A request aborted at a deadline never reports a result, so it is counted as errored here.
+    fn request_abandoned(&self) {
+        self.registry().requests_errored += 1;
+    }
+
+    /// Prometheus text exposition format, version 0.0.4.
+    fn render(&self) -> String {
+        let registry = self.registry().clone();
+        let in_flight = registry
+            .requests_started
+            .saturating_sub(registry.requests_completed + registry.requests_errored);
+
+        let mut out = String::new();
+        for (name, help, value) in [
+            (
+                "llmnop_requests_started_total",
+                "Requests sent, including warmup requests.",
+                registry.requests_started,
+            ),
+            (
+                "llmnop_requests_completed_total",
+                "Requests that finished successfully.",
+                registry.requests_completed,
+            ),
+            (
+                "llmnop_requests_errored_total",
+                "Requests that failed, including ones abandoned at a deadline.",
+                registry.requests_errored,
+            ),
+            (
+                "llmnop_retries_total",
+                "Attempts that failed and were retried, not counted as requests.",
+                registry.retries,
+            ),
+            (
+                "llmnop_input_tokens_total",
+                "Input tokens of successful requests.",
+                registry.input_tokens,
+            ),
+            (
+                "llmnop_output_tokens_total",
+                "Output and reasoning tokens of successful requests.",
+                registry.output_tokens,
+            ),
+        ] {
+            let _ = writeln!(
+                out,
+                "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP llmnop_requests_in_flight Requests sent and not yet finished.\n\
+             # TYPE llmnop_requests_in_flight gauge\nllmnop_requests_in_flight {in_flight}"
+        );
+
+        registry.time_to_first_token.render(
+            &mut out,
+            "llmnop_time_to_first_token_seconds",
+            "Time to first token of successful requests.",
+        );
+        registry.inter_token_latency.render(
+            &mut out,
+            "llmnop_inter_token_latency_seconds",
+            "Gaps between stream events of successful requests, one observation per gap.",
+        );
+        registry.request_latency.render(
+            &mut out,
+            "llmnop_request_latency_seconds",
+            "End-to-end latency of successful requests.",
+        );
+        out
+    }
+}
+
+/// Starts serving `/metrics` on `addr` for the rest of the process.
+pub async fn listen(addr: SocketAddr) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to listen on {addr}"))?;
+    let local_addr = listener.local_addr()?;
+    let metrics = LIVE_METRICS.get_or_init(Default::default).clone();
+    tokio::spawn(serve(listener, metrics));
+    Ok(local_addr)
+}
+
+pub fn request_started() {
+    if let Some(metrics) = LIVE_METRICS.get() {
+        metrics.request_started();
+    }
+}
+
+pub fn request_finished<E>(result: &Result<BenchmarkResult, E>) {
+    if let Some(metrics) = LIVE_METRICS.get() {
+        metrics.request_finished(result);
+    }
+}
+
+pub fn request_retried() {
+    if let Some(metrics) = LIVE_METRICS.get() {
+        metrics.request_retried();
+    }
+}
+
+pub fn request_abandoned() {
+    if let Some(metrics) = LIVE_METRICS.get() {
+        metrics.request_abandoned();
+    }
+}
+
+async fn serve(listener: TcpListener, metrics: Arc<LiveMetrics>) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            // Scrapes are best effort; a dropped connection only loses that scrape.
+            let _ = respond(stream, &metrics).await;
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, metrics: &LiveMetrics) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0_u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 8192 {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    let request_line = String::from_utf8_lossy(&request);
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", "Not Found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn result(ttft_ms: u64, e2e_ms: u64) -> BenchmarkResult {
+        BenchmarkResult {
+            ttft: Duration::from_millis(ttft_ms),
+            ttfo: None,
+            total_latency: Duration::from_millis(e2e_ms),
+            throughput: 50.0,
+            input_tokens: 100,
+            output_tokens: 40,
+            reasoning_tokens: 10,
+            inter_token_latency_s: 0.015,
+            inter_event_latency_s: 0.015,
+            inter_event_gaps_s: vec![0.01, 0.02, 0.4],
+            total_tokens: 150,
+            request_start_unix_ns: 0,
+            request_end_unix_ns: 0,
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: None,
+            requested_output_tokens: None,
+            finish_reason: None,
+            attempts: 1,
+            generated_text: String::new(),
+        }
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::new(&[0.1, 1.0]);
+        for value in [0.05, 0.1, 0.5, 3.0] {
+            histogram.observe(value);
+        }
+        let mut out = String::new();
+        histogram.render(&mut out, "x_seconds", "Test.");
+
+        assert!(out.contains("x_seconds_bucket{le=\"0.1\"} 2\n"));
+        assert!(out.contains("x_seconds_bucket{le=\"1\"} 3\n"));
+        assert!(out.contains("x_seconds_bucket{le=\"+Inf\"} 4\n"));
+        assert!(out.contains("x_seconds_sum 3.65\n"));
+        assert!(out.contains("x_seconds_count 4\n"));
+    }
+
+    #[test]
+    fn renders_counters_and_in_flight_requests() {
+        let metrics = LiveMetrics::default();
+        for _ in 0..3 {
+            metrics.request_started();
+        }
+        metrics.request_finished::<String>(&Ok(result(200, 900)));
+        metrics.request_retried();
+        metrics.request_finished(&Err("boom"));
+        metrics.request_abandoned();
+
+        let text = metrics.render();
+        assert!(text.contains("llmnop_requests_started_total 3\n"));
+        assert!(text.contains("llmnop_requests_completed_total 1\n"));
+        assert!(text.contains("llmnop_requests_errored_total 2\n"));
+        assert!(text.contains("llmnop_requests_in_flight 0\n"));
+        assert!(text.contains("llmnop_retries_total 1\n"));
+        assert!(text.contains("llmnop_output_tokens_total 50\n"));
+        assert!(text.contains("llmnop_time_to_first_token_seconds_bucket{le=\"0.25\"} 1\n"));
+        assert!(text.contains("llmnop_request_latency_seconds_count 1\n"));
+        assert!(text.contains("llmnop_inter_token_latency_seconds_count 3\n"));
+        assert!(text.contains("llmnop_inter_token_latency_seconds_bucket{le=\"0.25\"} 2\n"));
+    }
+
+    #[tokio::test]
+    async fn serves_metrics_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let metrics = Arc::new(LiveMetrics::default());
+        metrics.request_started();
+        tokio::spawn(serve(listener, metrics));
+
+        let fetch = |path: &'static str| async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = fetch("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("llmnop_requests_started_total 1\n"));
+        assert!(fetch("/").await.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
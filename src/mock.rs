@@ -0,0 +1,586 @@
+use crate::args::MockServerArgs;
+use anyhow::{Context, Result};
+use rand::prelude::*;
+use serde_json::{Value, json};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpListener, TcpStream};
+
+/// Words cycled through as streamed tokens; each is a single token in common tokenizers.
+const WORDS: [&str; 8] = [
+    " the", " quick", " brown", " fox", " jumps", " over", " lazy", " dog",
+];
+const MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// How the mock server paces and shapes its streamed responses.
+#[derive(Debug, Clone)]
+pub struct MockConfig {
+    pub ttft: Duration,
+    pub itl: Duration,
+    pub jitter: f64,
+    pub output_tokens: u32,
+    pub reasoning_tokens: u32,
+    pub usage: bool,
+    pub error_rate: f64,
+    pub error_status: u16,
+    pub seed: Option<u64>,
+}
+
+impl MockConfig {
+    pub fn from_args(args: &MockServerArgs) -> Self {
+        Self {
+            ttft: args.ttft,
+            itl: args.itl,
+            jitter: args.jitter,
+            output_tokens: args.output_tokens,
+            reasoning_tokens: args.reasoning_tokens,
+            usage: !args.no_usage,
+            error_rate: args.error_rate,
+            error_status: args.error_status,
+            seed: args.seed,
+        }
+    }
+}
+
+struct MockState {
+    config: MockConfig,
+    rng: Mutex<StdRng>,
+}
+
+impl MockState {
+    fn delay(&self, base: Duration) -> Duration {
+        if self.config.jitter == 0.0 {
+            return base;
+        }
+        let jitter = self.config.jitter;
+        let factor = 1.0 + self.rng().random_range(-jitter..=jitter);
+        base.mul_f64(factor)
+    }
+
+    fn inject_error(&self) -> bool {
+        self.config.error_rate > 0.0 && self.rng().random_bool(self.config.error_rate)
+    }
+
+    fn rng(&self) -> std::sync::MutexGuard<'_, StdRng> {
+        self.rng
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Endpoint {
+    ChatCompletions,
+    Responses,
+}
+
+/// What one streamed response contains, resolved from the request body and config.
+#[derive(Debug, Clone, PartialEq)]
+struct StreamPlan {
+    endpoint: Endpoint,
+    model: String,
+    input_tokens: u32,
+    reasoning_tokens: u32,
+    output_tokens: u32,
+    /// True when the request's token limit cut the output short.
+    truncated: bool,
+    usage: bool,
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+    close: bool,
+}
+
+pub async fn run_mock_server(args: &MockServerArgs) -> Result<()> {
+    let addr = start(args.listen, MockConfig::from_args(args)).await?;
+    println!("Mock server listening on http://{addr}/v1 (Ctrl-C to stop)");
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
+
+/// Binds `addr` and serves in the background, returning the bound address.
+pub async fn start(addr: SocketAddr, config: MockConfig) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to listen on {addr}"))?;
+    let local_addr = listener.local_addr()?;
+    let rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(&mut rand::rng()),
+    };
+    let state = Arc::new(MockState {
+        config,
+        rng: Mutex::new(rng),
+    });
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let state = state.clone();
+            tokio::spawn(async move {
+                // A client that hangs up mid-stream only ends its own connection.
+                let _ = handle_connection(stream, &state).await;
+            });
+        }
+    });
+    Ok(local_addr)
+}
+
+async fn handle_connection(stream: TcpStream, state: &MockState) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    while let Some(request) = read_request(&mut reader).await? {
+        respond(&mut writer, state, &request).await?;
+        if request.close {
+            break;
+        }
+    }
+    Ok(())
+}
+
+async fn read_request<R: AsyncBufReadExt + Unpin>(
+    reader: &mut R,
+) -> io::Result<Option<HttpRequest>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    let mut close = false;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .parse()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "bad content-length"))?;
+        } else if name.eq_ignore_ascii_case("connection") {
+            close = value.eq_ignore_ascii_case("close");
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "request body too large",
+        ));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(HttpRequest {
+        method,
+        path,
+        body,
+        close,
+    }))
+}
+
+async fn respond(
+    writer: &mut OwnedWriteHalf,
+    state: &MockState,
+    request: &HttpRequest,
+) -> io::Result<()> {
+    let path = request.path.split('?').next().unwrap_or_default();
+    let endpoint = if path.ends_with("/chat/completions") {
+        Endpoint::ChatCompletions
+    } else if path.ends_with("/responses") {
+        Endpoint::Responses
+    } else {
+        return write_error(writer, 404, "not_found", "Unknown endpoint").await;
+    };
+    if request.method != "POST" {
+        return write_error(writer, 405, "invalid_request_error", "Use POST").await;
+    }
+
+    let body: Value = match serde_json::from_slice(&request.body) {
+        Ok(body) => body,
+        Err(err) => {
+            let message = format!("Invalid JSON body: {err}");
+            return write_error(writer, 400, "invalid_request_error", &message).await;
+        }
+    };
+    if state.inject_error() {
+        let status = state.config.error_status;
+        return write_error(writer, status, "server_error", "Injected mock error").await;
+    }
+
+    let plan = stream_plan(endpoint, &body, &state.config);
+    writer
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+              Transfer-Encoding: chunked\r\n\r\n",
+        )
+        .await?;
+
+    if plan.endpoint == Endpoint::Responses {
+        let created = json!({"type": "response.created", "response": {"id": "resp_mock", "status": "in_progress"}});
+        write_chunk(writer, &sse_event(&plan, &created)).await?;
+    }
+    for index in 0..plan.reasoning_tokens + plan.output_tokens {
+        let base = if index == 0 {
+            state.config.ttft
+        } else {
+            state.config.itl
+        };
+        tokio::time::sleep(state.delay(base)).await;
+        let reasoning = index < plan.reasoning_tokens;
+        let event = token_event(&plan, WORDS[index as usize % WORDS.len()], reasoning);
+        write_chunk(writer, &sse_event(&plan, &event)).await?;
+    }
+    for event in final_events(&plan) {
+        write_chunk(writer, &sse_event(&plan, &event)).await?;
+    }
+    if plan.endpoint == Endpoint::ChatCompletions {
+        write_chunk(writer, "data: [DONE]\n\n").await?;
+    }
+    writer.write_all(b"0\r\n\r\n").await
+}
+
+fn stream_plan(endpoint: Endpoint, body: &Value, config: &MockConfig) -> StreamPlan {
+    let limit_field = match endpoint {
+        Endpoint::ChatCompletions => ["max_completion_tokens", "max_tokens"].as_slice(),
+        Endpoint::Responses => ["max_output_tokens"].as_slice(),
+    };
+    let max_tokens = limit_field
+        .iter()
+        .find_map(|field| body.get(*field).and_then(Value::as_u64))
+        .map(|tokens| tokens.min(u64::from(u32::MAX)) as u32);
+
+    // The "model" stops on its own after `--output-tokens` unless the limit comes first,
+    // or `min_tokens` / `ignore_eos` keep it going as they do on vLLM.
+    let min_tokens = body
+        .get("min_tokens")
+        .and_then(Value::as_u64)
+        .map_or(0, |tokens| tokens.min(u64::from(u32::MAX)) as u32);
+    let ignore_eos = body
+        .get("ignore_eos")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let natural_tokens = config.output_tokens.max(min_tokens);
+    let (output_tokens, truncated) = match max_tokens {
+        Some(max_tokens) if ignore_eos || natural_tokens >= max_tokens => (max_tokens, true),
+        _ => (natural_tokens, false),
+    };
+
+    let input_text = match endpoint {
+        Endpoint::ChatCompletions => body.get("messages").map(message_text),
+        Endpoint::Responses => body.get("input").map(|input| match input {
+            Value::Array(_) => message_text(input),
+            other => content_text(other),
+        }),
+    }
+    .unwrap_or_default();
+
+    // Chat completions only report usage when asked, like OpenAI; Responses always do.
+    let usage = config.usage
+        && match endpoint {
+            Endpoint::ChatCompletions => body
+                .pointer("/stream_options/include_usage")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            Endpoint::Responses => true,
+        };
+
+    StreamPlan {
+        endpoint,
+        model: body
+            .get("model")
+            .and_then(Value::as_str)
+            .unwrap_or("mock")
+            .to_string(),
+        input_tokens: input_text.split_whitespace().count() as u32,
+        reasoning_tokens: config.reasoning_tokens,
+        output_tokens,
+        truncated,
+        usage,
+    }
+}
+
+/// Text of every message's content in a chat or Responses message list.
+fn message_text(messages: &Value) -> String {
+    messages
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|message| message.get("content"))
+        .map(content_text)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Content is either a string or a list of parts with `text` fields.
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => String::new(),
+    }
+}
+
+fn token_event(plan: &StreamPlan, word: &str, reasoning: bool) -> Value {
+    match (plan.endpoint, reasoning) {
+        (Endpoint::ChatCompletions, true) => {
+            chat_chunk(plan, json!({"reasoning_content": word}), Value::Null)
+        }
+        (Endpoint::ChatCompletions, false) => {
+            chat_chunk(plan, json!({"content": word}), Value::Null)
+        }
+        (Endpoint::Responses, true) => {
+            json!({"type": "response.reasoning_text.delta", "delta": word})
+        }
+        (Endpoint::Responses, false) => {
+            json!({"type": "response.output_text.delta", "delta": word})
+        }
+    }
+}
+
+fn final_events(plan: &StreamPlan) -> Vec<Value> {
+    let completion_tokens = plan.reasoning_tokens + plan.output_tokens;
+    match plan.endpoint {
+        Endpoint::ChatCompletions => {
+            let finish_reason = if plan.truncated { "length" } else { "stop" };
+            let mut events = vec![chat_chunk(plan, json!({}), json!(finish_reason))];
+            if plan.usage {
+                events.push(json!({
+                    "id": "chatcmpl-mock",
+                    "object": "chat.completion.chunk",
+                    "created": 0,
+                    "model": plan.model,
+                    "choices": [],
+                    "usage": {
+                        "prompt_tokens": plan.input_tokens,
+                        "completion_tokens": completion_tokens,
+                        "total_tokens": plan.input_tokens + completion_tokens,
+                        "completion_tokens_details": {"reasoning_tokens": plan.reasoning_tokens},
+                    },
+                }));
+            }
+            events
+        }
+        Endpoint::Responses => {
+            // A truncated response ends with `response.incomplete`, as on OpenAI.
+            let (event_type, mut response) = if plan.truncated {
+                (
+                    "response.incomplete",
+                    json!({
+                        "id": "resp_mock",
+                        "status": "incomplete",
+                        "incomplete_details": {"reason": "max_output_tokens"},
+                    }),
+                )
+            } else {
+                (
+                    "response.completed",
+                    json!({"id": "resp_mock", "status": "completed"}),
+                )
+            };
+            if plan.usage {
+                response["usage"] = json!({
+                    "input_tokens": plan.input_tokens,
+                    "output_tokens": completion_tokens,
+                    "total_tokens": plan.input_tokens + completion_tokens,
+                    "output_tokens_details": {"reasoning_tokens": plan.reasoning_tokens},
+                });
+            }
+            vec![json!({"type": event_type, "response": response})]
+        }
+    }
+}
+
+fn chat_chunk(plan: &StreamPlan, delta: Value, finish_reason: Value) -> Value {
+    json!({
+        "id": "chatcmpl-mock",
+        "object": "chat.completion.chunk",
+        "created": 0,
+        "model": plan.model,
+        "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}],
+    })
+}
+
+/// Responses events are named after their `type`; chat chunks are bare `data:` lines.
+fn sse_event(plan: &StreamPlan, event: &Value) -> String {
+    match (plan.endpoint, event.get("type").and_then(Value::as_str)) {
+        (Endpoint::Responses, Some(name)) => format!("event: {name}\ndata: {event}\n\n"),
+        _ => format!("data: {event}\n\n"),
+    }
+}
+
+async fn write_chunk(writer: &mut OwnedWriteHalf, data: &str) -> io::Result<()> {
+    writer
+        .write_all(format!("{:x}\r\n{data}\r\n", data.len()).as_bytes())
+        .await
+}
+
+async fn write_error(
+    writer: &mut OwnedWriteHalf,
+    status: u16,
+    error_type: &str,
+    message: &str,
+) -> io::Result<()> {
+    let body = json!({"error": {"message": message, "type": error_type, "code": null}}).to_string();
+    let reason = match status {
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        429 => "Too Many Requests",
+        503 => "Service Unavailable",
+        _ => "Error",
+    };
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+    writer.write_all(response.as_bytes()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> MockConfig {
+        MockConfig {
+            ttft: Duration::ZERO,
+            itl: Duration::ZERO,
+            jitter: 0.0,
+            output_tokens: 16,
+            reasoning_tokens: 2,
+            usage: true,
+            error_rate: 0.0,
+            error_status: 500,
+            seed: Some(7),
+        }
+    }
+
+    #[test]
+    fn chat_plan_reads_limit_input_and_usage_option() {
+        let body = json!({
+            "model": "m",
+            "messages": [{"role": "user", "content": "one two three"}],
+            "max_completion_tokens": 4,
+            "stream_options": {"include_usage": true},
+        });
+        let plan = stream_plan(Endpoint::ChatCompletions, &body, &config());
+        assert_eq!(plan.model, "m");
+        assert_eq!(plan.input_tokens, 3);
+        assert_eq!(plan.output_tokens, 4);
+        assert!(plan.truncated);
+        assert!(plan.usage);
+
+        let body =
+            json!({"messages": [{"role": "user", "content": [{"type": "text", "text": "a b"}]}]});
+        let plan = stream_plan(Endpoint::ChatCompletions, &body, &config());
+        assert_eq!(plan.input_tokens, 2);
+        assert_eq!(plan.output_tokens, 16);
+        assert!(!plan.truncated);
+        assert!(!plan.usage);
+    }
+
+    #[test]
+    fn chat_plan_stops_early_unless_length_is_pinned() {
+        let plan = |extra: Value| {
+            let mut body = json!({"messages": [], "max_tokens": 20});
+            body.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            let plan = stream_plan(Endpoint::ChatCompletions, &body, &config());
+            (plan.output_tokens, plan.truncated)
+        };
+        assert_eq!(plan(json!({})), (16, false));
+        assert_eq!(plan(json!({"min_tokens": 18})), (18, false));
+        assert_eq!(plan(json!({"min_tokens": 20})), (20, true));
+        assert_eq!(plan(json!({"ignore_eos": true})), (20, true));
+    }
+
+    #[test]
+    fn responses_plan_accepts_text_or_message_input() {
+        let text = json!({"input": "one two", "max_output_tokens": 3});
+        let plan = stream_plan(Endpoint::Responses, &text, &config());
+        assert_eq!((plan.input_tokens, plan.output_tokens), (2, 3));
+        assert!(plan.usage);
+
+        let messages = json!({"input": [{"role": "user", "content": "a"}, {"role": "assistant", "content": "b c"}]});
+        assert_eq!(
+            stream_plan(Endpoint::Responses, &messages, &config()).input_tokens,
+            3
+        );
+    }
+
+    #[test]
+    fn usage_counts_reasoning_as_completion_tokens() {
+        let body = json!({"messages": [{"role": "user", "content": "hi"}], "stream_options": {"include_usage": true}});
+        let plan = stream_plan(Endpoint::ChatCompletions, &body, &config());
+        let events = final_events(&plan);
+        assert_eq!(events[0]["choices"][0]["finish_reason"], "stop");
+        assert_eq!(events[1]["usage"]["completion_tokens"], 18);
+        assert_eq!(
+            events[1]["usage"]["completion_tokens_details"]["reasoning_tokens"],
+            2
+        );
+
+        let plan = StreamPlan {
+            usage: false,
+            ..plan
+        };
+        assert_eq!(final_events(&plan).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn serves_errors_and_unknown_paths() {
+        let addr = start(
+            "127.0.0.1:0".parse().unwrap(),
+            MockConfig {
+                error_rate: 1.0,
+                error_status: 429,
+                ..config()
+            },
+        )
+        .await
+        .unwrap();
+
+        let send = |path: &'static str| async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let body = r#"{"model":"m","messages":[]}"#;
+            let request = format!(
+                "POST {path} HTTP/1.1\r\nHost: x\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = send("/v1/chat/completions").await;
+        assert!(response.starts_with("HTTP/1.1 429 Too Many Requests\r\n"));
+        assert!(response.contains("Injected mock error"));
+        assert!(send("/v1/embeddings").await.starts_with("HTTP/1.1 404"));
+    }
+}
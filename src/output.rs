@@ -1,14 +1,19 @@
 use crate::benchmark::BenchmarkResult;
+use crate::error::RequestFailure;
+use crate::gate::{Gate, Verdict};
+use crate::slo::{Slo, meets_slos};
 use comfy_table::{
     Attribute, Cell, CellAlignment, Color, ContentArrangement, Table, presets::UTF8_FULL_CONDENSED,
 };
 use directories::ProjectDirs;
+use rand::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::collections::BTreeMap;
 use std::fs::{File, create_dir_all};
 use std::io::{Error, ErrorKind, Write};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 pub struct BenchmarkConfig<'a> {
     pub model: &'a str,
@@ -18,9 +23,50 @@ pub struct BenchmarkConfig<'a> {
     pub mean_output_tokens: Option<u32>,
     pub stddev_output_tokens: u32,
     pub num_concurrent_requests: u32,
+    pub request_rate: Option<f64>,
+    pub arrival_distribution: Option<&'a str>,
+    pub warmup_requests: u32,
+    pub duration: Option<Duration>,
+    pub dataset: Option<&'a Path>,
+    pub num_turns: u32,
+    pub prefix_tokens: Option<u32>,
+    pub num_prefixes: u32,
+    pub prefix_skew: f64,
+    pub slos: &'a [Slo],
+    pub extra_body: Option<&'a Map<String, Value>>,
+    pub max_retries: u32,
+    pub gate: Option<&'a Gate>,
+}
+
+/// A plain fixed-count run config for tests to adjust with struct-update syntax.
+#[cfg(test)]
+pub fn test_config() -> BenchmarkConfig<'static> {
+    BenchmarkConfig {
+        model: "qwen/qwen3-4b-2507",
+        tokenizer: "Qwen/Qwen3-4B",
+        mean_input_tokens: 550,
+        stddev_input_tokens: 0,
+        mean_output_tokens: None,
+        stddev_output_tokens: 0,
+        num_concurrent_requests: 1,
+        request_rate: None,
+        arrival_distribution: None,
+        warmup_requests: 0,
+        duration: None,
+        dataset: None,
+        num_turns: 1,
+        prefix_tokens: None,
+        num_prefixes: 1,
+        prefix_skew: 0.0,
+        slos: &[],
+        extra_body: None,
+        max_retries: 0,
+        gate: None,
+    }
 }
 
 pub struct WrittenResults {
+    pub dir: PathBuf,
     pub summary: BenchmarkSummary,
 }
 
@@ -35,6 +81,33 @@ pub struct SummaryInputConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stddev_output_tokens: Option<u32>,
     pub num_concurrent_requests: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrival_distribution: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warmup_requests: Option<u32>,
+    /// Measurement window in seconds for `--duration` runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_s: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_turns: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_prefixes: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix_skew: Option<f64>,
+    /// SLO thresholds in milliseconds, keyed by metric.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slos: Option<BTreeMap<String, f64>>,
+    /// Fields from `--extra-body` that were merged into every request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_body: Option<Map<String, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +143,10 @@ pub struct MetricStats {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorSummaryEntry {
+    /// Error kind, e.g. `rate_limit`; empty in summaries written before errors were classified.
+    #[serde(rename = "type", default)]
+    pub error_type: String,
+    /// HTTP status, or 0 when the failure had none.
     pub code: i32,
     pub message: String,
     pub count: usize,
@@ -91,7 +168,17 @@ pub struct BenchmarkSummary {
     pub successful_request_count: MetricStats,
     pub error_request_count: MetricStats,
     pub error_rate: MetricStats,
+    /// Attempts beyond the first across all requests, when `--max-retries` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_count: Option<MetricStats>,
     pub request_throughput: MetricStats,
+    /// Share of started requests that succeeded and met every SLO.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slo_attainment: Option<MetricStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goodput_request_throughput: Option<MetricStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goodput_token_throughput: Option<MetricStats>,
 
     pub request_latency: MetricStats,
     pub time_to_first_token: MetricStats,
@@ -99,6 +186,20 @@ pub struct BenchmarkSummary {
     pub time_to_first_output_token: Option<MetricStats>,
     pub inter_token_latency: MetricStats,
     pub inter_event_latency: MetricStats,
+    /// Every gap between stream events across all requests, rather than per-request averages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inter_event_gap: Option<MetricStats>,
+    /// Distribution of each request's longest gap between stream events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_stall: Option<MetricStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduler_lag: Option<MetricStats>,
+    /// TTFT for prompts whose shared prefix was already sent by an earlier request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_to_first_token_prefix_hit: Option<MetricStats>,
+    /// TTFT for the first request to send each shared prefix.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_to_first_token_prefix_miss: Option<MetricStats>,
 
     pub output_token_throughput_per_request: MetricStats,
     pub output_token_throughput: MetricStats,
@@ -108,14 +209,121 @@ pub struct BenchmarkSummary {
     pub output_token_count: MetricStats,
     pub reasoning_token_count: MetricStats,
     pub output_sequence_length: MetricStats,
+    /// Generated minus requested output tokens, for requests that set a limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_length_deviation: Option<MetricStats>,
 
     pub total_input_tokens: MetricStats,
     pub total_output_tokens: MetricStats,
     pub total_reasoning_tokens: MetricStats,
     pub total_output_sequence_tokens: MetricStats,
 
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    /// Requests by finish reason, with failed requests counted as `error`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub finish_reasons: BTreeMap<String, usize>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub error_summary: Vec<ErrorSummaryEntry>,
+
+    /// Set by `llmnop report --skip-first`/`--skip-last`. `start_time_unix_ns` and
+    /// `end_time_unix_ns` are then the bounds of the trimmed window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<SummaryWindow>,
+
+    /// Outcome of `--assert` and `--max-regression` checks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<Verdict>,
+}
+
+/// Seconds a report dropped from each end of the run.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SummaryWindow {
+    pub skip_first_s: f64,
+    pub skip_last_s: f64,
+}
+
+/// JSON field names of every metric in [`BenchmarkSummary`], in declaration order.
+pub const SUMMARY_METRICS: [&str; 32] = [
+    "benchmark_duration",
+    "request_count",
+    "successful_request_count",
+    "error_request_count",
+    "error_rate",
+    "retry_count",
+    "request_throughput",
+    "slo_attainment",
+    "goodput_request_throughput",
+    "goodput_token_throughput",
+    "request_latency",
+    "time_to_first_token",
+    "time_to_first_output_token",
+    "inter_token_latency",
+    "inter_event_latency",
+    "inter_event_gap",
+    "max_stall",
+    "scheduler_lag",
+    "time_to_first_token_prefix_hit",
+    "time_to_first_token_prefix_miss",
+    "output_token_throughput_per_request",
+    "output_token_throughput",
+    "total_token_throughput",
+    "input_sequence_length",
+    "output_token_count",
+    "reasoning_token_count",
+    "output_sequence_length",
+    "output_length_deviation",
+    "total_input_tokens",
+    "total_output_tokens",
+    "total_reasoning_tokens",
+    "total_output_sequence_tokens",
+];
+
+impl BenchmarkSummary {
+    /// Every metric in the summary by its JSON field name, in declaration order.
+    pub fn metrics(&self) -> Vec<(&'static str, Option<&MetricStats>)> {
+        let stats = [
+            Some(&self.benchmark_duration),
+            Some(&self.request_count),
+            Some(&self.successful_request_count),
+            Some(&self.error_request_count),
+            Some(&self.error_rate),
+            self.retry_count.as_ref(),
+            Some(&self.request_throughput),
+            self.slo_attainment.as_ref(),
+            self.goodput_request_throughput.as_ref(),
+            self.goodput_token_throughput.as_ref(),
+            Some(&self.request_latency),
+            Some(&self.time_to_first_token),
+            self.time_to_first_output_token.as_ref(),
+            Some(&self.inter_token_latency),
+            Some(&self.inter_event_latency),
+            self.inter_event_gap.as_ref(),
+            self.max_stall.as_ref(),
+            self.scheduler_lag.as_ref(),
+            self.time_to_first_token_prefix_hit.as_ref(),
+            self.time_to_first_token_prefix_miss.as_ref(),
+            Some(&self.output_token_throughput_per_request),
+            Some(&self.output_token_throughput),
+            Some(&self.total_token_throughput),
+            Some(&self.input_sequence_length),
+            Some(&self.output_token_count),
+            Some(&self.reasoning_token_count),
+            Some(&self.output_sequence_length),
+            self.output_length_deviation.as_ref(),
+            Some(&self.total_input_tokens),
+            Some(&self.total_output_tokens),
+            Some(&self.total_reasoning_tokens),
+            Some(&self.total_output_sequence_tokens),
+        ];
+        SUMMARY_METRICS.into_iter().zip(stats).collect()
+    }
+
+    pub fn metric(&self, name: &str) -> Option<&MetricStats> {
+        self.metrics()
+            .into_iter()
+            .find(|(metric, _)| *metric == name)
+            .and_then(|(_, stats)| stats)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,6 +347,25 @@ pub struct RequestMetadata {
     pub request_start_ns: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub request_end_ns: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_scheduled_ns: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_index: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_index: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix_index: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix_cache_hit_likely: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slo_met: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+    /// Attempts made when the request was retried.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attempts: Option<u32>,
     pub benchmark_phase: String,
 }
 
@@ -146,6 +373,10 @@ pub struct RequestMetadata {
 pub struct RequestRecord {
     pub metadata: RequestMetadata,
     pub metrics: BTreeMap<String, MetricValue>,
+    /// Every gap between stream events, in ms, so `llmnop report` can rebuild the pooled
+    /// `inter_event_gap` distribution. Kept apart from the one-value-per-metric `metrics`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inter_event_gaps_ms: Vec<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<RequestError>,
 }
@@ -163,7 +394,7 @@ pub struct Quantiles {
     pub p99: f64,
 }
 
-fn default_results_dir() -> std::io::Result<PathBuf> {
+pub fn default_results_dir() -> std::io::Result<PathBuf> {
     let project_dirs = ProjectDirs::from("", "", "llmnop").ok_or_else(|| {
         Error::new(
             ErrorKind::NotFound,
@@ -178,7 +409,7 @@ fn default_results_dir() -> std::io::Result<PathBuf> {
     Ok(project_dirs.data_local_dir().join("results"))
 }
 
-fn benchmark_slug(config: &BenchmarkConfig) -> String {
+pub fn benchmark_slug(config: &BenchmarkConfig) -> String {
     let output_tokens_str = config
         .mean_output_tokens
         .map(|v| v.to_string())
@@ -192,7 +423,7 @@ fn benchmark_slug(config: &BenchmarkConfig) -> String {
     )
 }
 
-fn generate_run_id() -> std::io::Result<String> {
+pub fn generate_run_id() -> std::io::Result<String> {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     let now = SystemTime::now()
@@ -207,133 +438,106 @@ fn run_results_dir(base_results_dir: &Path, config: &BenchmarkConfig, run_id: &s
 
 pub fn print_summary_to_stdout(
     successful_results: &[BenchmarkResult],
-    num_errors: usize,
+    failures: &[RequestFailure],
     total_output_tokens: u64,
     total_reasoning_tokens: u64,
-    start_time: std::time::Instant,
-    end_time: std::time::Instant,
+    benchmark_duration: Duration,
+    slos: &[Slo],
+    percentiles: &[f64],
 ) {
-    let total_time_s = end_time.duration_since(start_time).as_secs_f64();
+    let total_time_s = benchmark_duration.as_secs_f64();
 
     let mut inter_token_vec = Vec::new();
     let mut inter_event_vec = Vec::new();
+    let mut inter_event_gap_vec = Vec::new();
+    let mut max_stall_vec = Vec::new();
     let mut ttft_vec = Vec::new();
     let mut ttfo_vec = Vec::new();
+    let mut ttft_prefix_hit_vec = Vec::new();
+    let mut ttft_prefix_miss_vec = Vec::new();
     let mut e2e_vec = Vec::new();
     let mut throughput_vec = Vec::new();
     let mut in_tokens_vec = Vec::new();
     let mut reasoning_tokens_vec = Vec::new();
     let mut out_tokens_vec = Vec::new();
     let mut total_tokens_vec = Vec::new();
+    let mut length_deviation_vec = Vec::new();
 
     for br in successful_results {
         inter_token_vec.push(br.inter_token_latency_s);
         inter_event_vec.push(br.inter_event_latency_s);
+        inter_event_gap_vec.extend_from_slice(&br.inter_event_gaps_s);
+        if let Some(max_stall_s) = br.max_stall_s() {
+            max_stall_vec.push(max_stall_s);
+        }
         ttft_vec.push(br.ttft.as_secs_f64());
         if let Some(ttfo) = br.ttfo {
             ttfo_vec.push(ttfo.as_secs_f64());
         }
+        match br.prefix {
+            Some(prefix) if prefix.cache_hit_likely => {
+                ttft_prefix_hit_vec.push(br.ttft.as_secs_f64())
+            }
+            Some(_) => ttft_prefix_miss_vec.push(br.ttft.as_secs_f64()),
+            None => {}
+        }
         e2e_vec.push(br.total_latency.as_secs_f64());
         throughput_vec.push(br.throughput);
         in_tokens_vec.push(br.input_tokens as f64);
         reasoning_tokens_vec.push(br.reasoning_tokens as f64);
         out_tokens_vec.push(br.output_tokens as f64);
         total_tokens_vec.push(br.total_tokens as f64);
+        if let Some(deviation) = br.output_length_deviation() {
+            length_deviation_vec.push(deviation as f64);
+        }
     }
 
-    let inter_stats = compute_stats(&inter_token_vec);
-    let inter_event_stats = compute_stats(&inter_event_vec);
-    let ttft_stats = compute_stats(&ttft_vec);
-    let ttfo_stats = compute_stats(&ttfo_vec);
-    let e2e_stats = compute_stats(&e2e_vec);
-    let thr_stats = compute_stats(&throughput_vec);
-    let in_stats = compute_stats(&in_tokens_vec);
-    let reasoning_stats = compute_stats(&reasoning_tokens_vec);
-    let out_stats = compute_stats(&out_tokens_vec);
-    let total_stats = compute_stats(&total_tokens_vec);
+    let mut table = stats_table(percentiles);
+    let mut row = |name: &str, values: &[f64], fmt: fn(f64) -> String| {
+        add_row(&mut table, name, &stat_cells(values, percentiles), fmt);
+    };
 
-    let mut table = Table::new();
-    table.load_preset(UTF8_FULL_CONDENSED);
-    table.set_content_arrangement(ContentArrangement::Dynamic);
-    table.set_header(vec![
-        Cell::new("Metric").add_attribute(Attribute::Bold),
-        Cell::new("avg").add_attribute(Attribute::Bold),
-        Cell::new("min").add_attribute(Attribute::Bold),
-        Cell::new("max").add_attribute(Attribute::Bold),
-        Cell::new("p99").add_attribute(Attribute::Bold),
-        Cell::new("p90").add_attribute(Attribute::Bold),
-        Cell::new("p50").add_attribute(Attribute::Bold),
-        Cell::new("std").add_attribute(Attribute::Bold),
-    ]);
-
-    fn fmt_ms(s: f64) -> String {
-        format!("{:.2}", s * 1000.0)
-    }
-
-    fn fmt_f64(v: f64) -> String {
-        format!("{:.2}", v)
-    }
-
-    fn fmt_int(v: f64) -> String {
-        format!("{}", v as u32)
-    }
-
-    fn add_row(table: &mut Table, name: &str, stats: &StatSet, fmt: fn(f64) -> String) {
-        table.add_row(vec![
-            Cell::new(name).fg(Color::Cyan),
-            Cell::new(fmt(stats.mean))
-                .set_alignment(CellAlignment::Right)
-                .fg(Color::Green),
-            Cell::new(fmt(stats.min))
-                .set_alignment(CellAlignment::Right)
-                .fg(Color::Green),
-            Cell::new(fmt(stats.max))
-                .set_alignment(CellAlignment::Right)
-                .fg(Color::Green),
-            Cell::new(fmt(stats.quantiles.p99))
-                .set_alignment(CellAlignment::Right)
-                .fg(Color::Green),
-            Cell::new(fmt(stats.quantiles.p90))
-                .set_alignment(CellAlignment::Right)
-                .fg(Color::Green),
-            Cell::new(fmt(stats.quantiles.p50))
-                .set_alignment(CellAlignment::Right)
-                .fg(Color::Green),
-            Cell::new(fmt(stats.stddev))
-                .set_alignment(CellAlignment::Right)
-                .fg(Color::Green),
-        ]);
-    }
-
-    add_row(&mut table, "Inter Token Latency (ms)", &inter_stats, fmt_ms);
-    add_row(
-        &mut table,
-        "Inter Event Latency (ms)",
-        &inter_event_stats,
+    row("Inter Token Latency (ms)", &inter_token_vec, fmt_ms);
+    row("Inter Event Latency (ms)", &inter_event_vec, fmt_ms);
+    row(
+        "Inter Event Gap, All Gaps (ms)",
+        &inter_event_gap_vec,
         fmt_ms,
     );
-    add_row(&mut table, "Time to First Token (ms)", &ttft_stats, fmt_ms);
-    if !ttfo_vec.is_empty() {
-        add_row(
-            &mut table,
-            "Time to First Output Token (ms)",
-            &ttfo_stats,
+    row("Max Stall Per Request (ms)", &max_stall_vec, fmt_ms);
+    row("Time to First Token (ms)", &ttft_vec, fmt_ms);
+    if !ttft_prefix_hit_vec.is_empty() {
+        row("  Shared Prefix Cached (ms)", &ttft_prefix_hit_vec, fmt_ms);
+    }
+    if !ttft_prefix_miss_vec.is_empty() {
+        row(
+            "  Shared Prefix First Use (ms)",
+            &ttft_prefix_miss_vec,
             fmt_ms,
         );
     }
-    add_row(&mut table, "End to End Latency (ms)", &e2e_stats, fmt_ms);
-    add_row(
-        &mut table,
+    if !ttfo_vec.is_empty() {
+        row("Time to First Output Token (ms)", &ttfo_vec, fmt_ms);
+    }
+    row("End to End Latency (ms)", &e2e_vec, fmt_ms);
+    row(
         "Output Throughput Per Request (tokens/s)",
-        &thr_stats,
+        &throughput_vec,
         fmt_f64,
     );
-    add_row(&mut table, "Input Tokens", &in_stats, fmt_int);
-    if reasoning_stats.max > 0.0 {
-        add_row(&mut table, "Reasoning Tokens", &reasoning_stats, fmt_int);
+    row("Input Tokens", &in_tokens_vec, fmt_int);
+    if reasoning_tokens_vec.iter().any(|&tokens| tokens > 0.0) {
+        row("Reasoning Tokens", &reasoning_tokens_vec, fmt_int);
+    }
+    row("Output Tokens", &out_tokens_vec, fmt_int);
+    if !length_deviation_vec.is_empty() {
+        row(
+            "  Deviation From Requested",
+            &length_deviation_vec,
+            fmt_signed_int,
+        );
     }
-    add_row(&mut table, "Output Tokens", &out_stats, fmt_int);
-    add_row(&mut table, "Total Tokens", &total_stats, fmt_int);
+    row("Total Tokens", &total_tokens_vec, fmt_int);
 
     println!();
     println!("{table}");
@@ -352,165 +556,595 @@ pub fn print_summary_to_stdout(
         0.0
     };
 
-    const CYAN: &str = "\x1b[36m";
-    const GREEN: &str = "\x1b[32m";
-    const RESET: &str = "\x1b[0m";
+    println!();
+    print_stat_line(
+        "Overall Output Throughput",
+        format!("{:.2} tokens/s", overall_output_throughput),
+    );
+    print_stat_line("Completed Requests", num_completed_requests.to_string());
+    print_stat_line(
+        "Requests Per Minute",
+        format!("{:.2}", completed_requests_per_min),
+    );
+    let num_errors = failures.len();
+    let mut errors_by_type: BTreeMap<String, usize> = BTreeMap::new();
+    for failure in failures {
+        *errors_by_type
+            .entry(failure.kind.as_str().to_string())
+            .or_default() += 1;
+    }
+    print_stat_line("Errors", format_errors(num_errors, &errors_by_type));
+    let retries = retry_count(successful_results, failures);
+    if retries > 0 {
+        print_stat_line("Retries", retries.to_string());
+    }
+    print_finish_reasons(&finish_reason_counts(successful_results, num_errors));
+
+    if !slos.is_empty() {
+        let slo_met: Vec<&BenchmarkResult> = successful_results
+            .iter()
+            .filter(|br| meets_slos(slos, br) == Some(true))
+            .collect();
+        let slo_met_tokens: u64 = slo_met
+            .iter()
+            .map(|br| (br.output_tokens + br.reasoning_tokens) as u64)
+            .sum();
+        let num_started = num_completed_requests + num_errors;
+        let attainment = if num_started > 0 {
+            slo_met.len() as f64 / num_started as f64
+        } else {
+            0.0
+        };
+        let (goodput_requests, goodput_tokens) = if total_time_s > 0.0 {
+            (
+                slo_met.len() as f64 / total_time_s,
+                slo_met_tokens as f64 / total_time_s,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+
+        print_stat_line(
+            "SLO Attainment",
+            format!(
+                "{:.1}% ({}/{})",
+                attainment * 100.0,
+                slo_met.len(),
+                num_started
+            ),
+        );
+        print_stat_line(
+            "Goodput",
+            format!(
+                "{:.2} requests/s, {:.2} tokens/s",
+                goodput_requests, goodput_tokens
+            ),
+        );
+    }
+}
+
+/// Re-renders the stdout summary of a saved run from its `summary.json` alone, for runs
+/// whose `individual_responses.jsonl` is missing; `llmnop runs show` otherwise rebuilds the
+/// table from the records.
+///
+/// Values are already in display units. The per-request total token row is not
+/// stored in summaries, so it is left out.
+pub fn print_saved_summary(summary: &BenchmarkSummary) {
+    type Row<'a> = (&'static str, Option<&'a MetricStats>, fn(f64) -> String);
+
+    let mut table = stats_table(&DEFAULT_TABLE_PERCENTILES);
+    let rows: [Row; 14] = [
+        (
+            "Inter Token Latency (ms)",
+            Some(&summary.inter_token_latency),
+            fmt_f64,
+        ),
+        (
+            "Inter Event Latency (ms)",
+            Some(&summary.inter_event_latency),
+            fmt_f64,
+        ),
+        (
+            "Inter Event Gap, All Gaps (ms)",
+            summary.inter_event_gap.as_ref(),
+            fmt_f64,
+        ),
+        (
+            "Max Stall Per Request (ms)",
+            summary.max_stall.as_ref(),
+            fmt_f64,
+        ),
+        (
+            "Time to First Token (ms)",
+            Some(&summary.time_to_first_token),
+            fmt_f64,
+        ),
+        (
+            "  Shared Prefix Cached (ms)",
+            summary.time_to_first_token_prefix_hit.as_ref(),
+            fmt_f64,
+        ),
+        (
+            "  Shared Prefix First Use (ms)",
+            summary.time_to_first_token_prefix_miss.as_ref(),
+            fmt_f64,
+        ),
+        (
+            "Time to First Output Token (ms)",
+            summary.time_to_first_output_token.as_ref(),
+            fmt_f64,
+        ),
+        (
+            "End to End Latency (ms)",
+            Some(&summary.request_latency),
+            fmt_f64,
+        ),
+        (
+            "Output Throughput Per Request (tokens/s)",
+            Some(&summary.output_token_throughput_per_request),
+            fmt_f64,
+        ),
+        (
+            "Input Tokens",
+            Some(&summary.input_sequence_length),
+            fmt_int,
+        ),
+        (
+            "Reasoning Tokens",
+            Some(&summary.reasoning_token_count).filter(|stats| stats.max.unwrap_or(0.0) > 0.0),
+            fmt_int,
+        ),
+        ("Output Tokens", Some(&summary.output_token_count), fmt_int),
+        (
+            "  Deviation From Requested",
+            summary.output_length_deviation.as_ref(),
+            fmt_signed_int,
+        ),
+    ];
+    for (name, stats, fmt) in rows {
+        if let Some(stats) = stats.filter(|stats| stats.avg.is_some()) {
+            add_row(&mut table, name, &saved_stat_cells(stats), fmt);
+        }
+    }
 
     println!();
-    println!(
-        "{CYAN}Overall Output Throughput:{RESET} {GREEN}{:.2} tokens/s{RESET}",
-        overall_output_throughput
+    println!("{table}");
+
+    let avg = |stats: &MetricStats| stats.avg.unwrap_or(0.0);
+    let duration_s = avg(&summary.benchmark_duration);
+    let completed = avg(&summary.successful_request_count);
+    let requests_per_min = if duration_s > 0.0 {
+        completed / duration_s * 60.0
+    } else {
+        0.0
+    };
+
+    println!();
+    print_stat_line(
+        "Overall Output Throughput",
+        format!("{:.2} tokens/s", avg(&summary.output_token_throughput)),
     );
-    println!(
-        "{CYAN}Completed Requests:{RESET} {GREEN}{}{RESET}",
-        num_completed_requests
+    print_stat_line("Completed Requests", format!("{}", completed as u64));
+    print_stat_line("Requests Per Minute", format!("{:.2}", requests_per_min));
+    let mut errors_by_type: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in &summary.error_summary {
+        *errors_by_type.entry(entry.error_type.clone()).or_default() += entry.count;
+    }
+    // Summaries written before errors were classified have no types to break down.
+    errors_by_type.remove("");
+    print_stat_line(
+        "Errors",
+        format_errors(avg(&summary.error_request_count) as usize, &errors_by_type),
     );
-    println!(
-        "{CYAN}Requests Per Minute:{RESET} {GREEN}{:.2}{RESET}",
-        completed_requests_per_min
+    if let Some(retries) = summary.retry_count.as_ref().map(avg).filter(|&r| r > 0.0) {
+        print_stat_line("Retries", format!("{}", retries as u64));
+    }
+    print_finish_reasons(&summary.finish_reasons);
+
+    if let Some(attainment) = &summary.slo_attainment {
+        let started = avg(&summary.request_count);
+        let attainment = avg(attainment);
+        print_stat_line(
+            "SLO Attainment",
+            format!(
+                "{:.1}% ({}/{})",
+                attainment * 100.0,
+                (attainment * started).round() as u64,
+                started as u64
+            ),
+        );
+    }
+    if let (Some(requests), Some(tokens)) = (
+        &summary.goodput_request_throughput,
+        &summary.goodput_token_throughput,
+    ) {
+        print_stat_line(
+            "Goodput",
+            format!(
+                "{:.2} requests/s, {:.2} tokens/s",
+                avg(requests),
+                avg(tokens)
+            ),
+        );
+    }
+}
+
+/// Percentile columns of the stdout table, in display order.
+pub const DEFAULT_TABLE_PERCENTILES: [f64; 3] = [99.0, 90.0, 50.0];
+
+fn stats_table(percentiles: &[f64]) -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    let mut header = vec![
+        "Metric".to_string(),
+        "avg".into(),
+        "min".into(),
+        "max".into(),
+    ];
+    header.extend(percentiles.iter().map(|pct| format!("p{}", pct)));
+    header.push("std".into());
+    table.set_header(
+        header
+            .into_iter()
+            .map(|name| Cell::new(name).add_attribute(Attribute::Bold)),
+    );
+    table
+}
+
+/// Row values matching `stats_table`: avg, min, max, each percentile, std.
+fn stat_cells(values: &[f64], percentiles: &[f64]) -> Vec<f64> {
+    let stats = compute_stats(values);
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut cells = vec![stats.mean, stats.min, stats.max];
+    cells.extend(
+        percentiles
+            .iter()
+            .map(|pct| percentile(&sorted, pct / 100.0)),
     );
-    println!("{CYAN}Errors:{RESET} {GREEN}{}{RESET}", num_errors);
+    cells.push(stats.stddev);
+    cells
+}
+
+/// Row values for `DEFAULT_TABLE_PERCENTILES` from a saved summary.
+fn saved_stat_cells(stats: &MetricStats) -> Vec<f64> {
+    [
+        stats.avg, stats.min, stats.max, stats.p99, stats.p90, stats.p50, stats.std,
+    ]
+    .into_iter()
+    .map(Option::unwrap_or_default)
+    .collect()
+}
+
+fn fmt_ms(s: f64) -> String {
+    format!("{:.2}", s * 1000.0)
+}
+
+fn fmt_f64(v: f64) -> String {
+    format!("{:.2}", v)
+}
+
+fn fmt_int(v: f64) -> String {
+    format!("{}", v as u32)
 }
 
+fn fmt_signed_int(v: f64) -> String {
+    format!("{}", v.round() as i64)
+}
+
+fn add_row(table: &mut Table, name: &str, cells: &[f64], fmt: fn(f64) -> String) {
+    let mut row = vec![Cell::new(name).fg(Color::Cyan)];
+    row.extend(cells.iter().map(|&value| {
+        Cell::new(fmt(value))
+            .set_alignment(CellAlignment::Right)
+            .fg(Color::Green)
+    }));
+    table.add_row(row);
+}
+
+fn finish_reason_counts(
+    successful_results: &[BenchmarkResult],
+    num_errors: usize,
+) -> BTreeMap<String, usize> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for finish_reason in successful_results
+        .iter()
+        .filter_map(|br| br.finish_reason.clone())
+    {
+        *counts.entry(finish_reason).or_default() += 1;
+    }
+    if num_errors > 0 {
+        *counts.entry("error".to_string()).or_default() += num_errors;
+    }
+    counts
+}
+
+/// Attempts beyond the first, summed over every request.
+fn retry_count(successful_results: &[BenchmarkResult], failures: &[RequestFailure]) -> u64 {
+    successful_results
+        .iter()
+        .map(|br| br.attempts)
+        .chain(failures.iter().map(|failure| failure.attempts))
+        .map(|attempts| attempts.saturating_sub(1) as u64)
+        .sum()
+}
+
+/// Formats e.g. `3 (rate_limit 2, http_status 1)`, most common type first.
+fn format_errors(num_errors: usize, errors_by_type: &BTreeMap<String, usize>) -> String {
+    if errors_by_type.is_empty() {
+        return num_errors.to_string();
+    }
+    let mut counts: Vec<(&String, &usize)> = errors_by_type.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1));
+    let breakdown = counts
+        .iter()
+        .map(|(error_type, count)| format!("{error_type} {count}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{num_errors} ({breakdown})")
+}
+
+/// Prints e.g. `stop 95 (95.0%), length 3 (3.0%), error 2 (2.0%)`, most common first.
+fn print_finish_reasons(counts: &BTreeMap<String, usize>) {
+    if counts.is_empty() {
+        return;
+    }
+    let total: usize = counts.values().sum();
+    let mut counts: Vec<(&String, &usize)> = counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1));
+    let breakdown = counts
+        .iter()
+        .map(|(reason, count)| {
+            format!(
+                "{reason} {count} ({:.1}%)",
+                **count as f64 / total as f64 * 100.0
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    print_stat_line("Finish Reasons", breakdown);
+}
+
+fn print_stat_line(label: &str, value: String) {
+    const CYAN: &str = "\x1b[36m";
+    const GREEN: &str = "\x1b[32m";
+    const RESET: &str = "\x1b[0m";
+
+    println!("{CYAN}{label}:{RESET} {GREEN}{value}{RESET}");
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn write_results_json(
+    base_results_dir: &Path,
     config: &BenchmarkConfig,
-    all_results: &[Result<BenchmarkResult, String>],
-    total_start_time: std::time::Instant,
-    total_end_time: std::time::Instant,
+    warmup_results: &[Result<BenchmarkResult, RequestFailure>],
+    all_results: &[Result<BenchmarkResult, RequestFailure>],
+    drained_results: &[Result<BenchmarkResult, RequestFailure>],
+    benchmark_duration: Duration,
     start_time_unix_ns: u64,
     end_time_unix_ns: u64,
 ) -> std::io::Result<WrittenResults> {
-    let base_results_dir = default_results_dir()?;
     let run_id = generate_run_id()?;
-    let run_results_dir = run_results_dir(&base_results_dir, config, &run_id);
+    let run_results_dir = run_results_dir(base_results_dir, config, &run_id);
     create_dir_all(&run_results_dir)?;
 
+    // One counter across phases, so `request_index` is unique within the run.
+    let phases = [
+        (warmup_results, "warmup"),
+        (all_results, "profiling"),
+        (drained_results, "drain"),
+    ];
+    let per_request_records: Vec<RequestRecord> = phases
+        .into_iter()
+        .flat_map(|(results, phase)| results.iter().map(move |result| (result, phase)))
+        .enumerate()
+        .map(|(request_index, (result, phase))| {
+            request_record(request_index, result, phase, config.slos)
+        })
+        .collect();
+
+    {
+        let path = run_results_dir.join("individual_responses.jsonl");
+        let mut file = File::create(&path)?;
+        for record in &per_request_records {
+            let line = serde_json::to_string(record)?;
+            file.write_all(line.as_bytes())?;
+            file.write_all(b"\n")?;
+        }
+    }
+
+    let summary = summarize_results(
+        &run_id,
+        config,
+        all_results,
+        benchmark_duration,
+        start_time_unix_ns,
+        end_time_unix_ns,
+    );
+    write_summary(&run_results_dir.join("summary.json"), &summary)?;
+
+    Ok(WrittenResults {
+        dir: run_results_dir,
+        summary,
+    })
+}
+
+/// Builds the run summary from the profiling results, failed requests included.
+pub fn summarize_results(
+    run_id: &str,
+    config: &BenchmarkConfig,
+    all_results: &[Result<BenchmarkResult, RequestFailure>],
+    benchmark_duration: Duration,
+    start_time_unix_ns: u64,
+    end_time_unix_ns: u64,
+) -> BenchmarkSummary {
     let mut total_output_tokens = 0_u64;
     let mut total_reasoning_tokens = 0_u64;
     let mut total_input_tokens = 0_u64;
     let mut successful_results = Vec::new();
-    let mut error_counts_by_message: BTreeMap<String, usize> = BTreeMap::new();
-    let mut per_request_records = Vec::with_capacity(all_results.len());
+    let mut failures = Vec::new();
 
-    for (request_index, result) in all_results.iter().enumerate() {
+    for result in all_results {
         match result {
             Ok(br) => {
                 total_output_tokens += br.output_tokens as u64;
                 total_reasoning_tokens += br.reasoning_tokens as u64;
                 total_input_tokens += br.input_tokens as u64;
                 successful_results.push(br.clone());
-
-                let mut metrics = BTreeMap::new();
-                metrics.insert(
-                    "time_to_first_token".to_string(),
-                    metric_value_f64(br.ttft.as_secs_f64() * 1000.0, "ms"),
-                );
-                if let Some(ttfo) = br.ttfo {
-                    metrics.insert(
-                        "time_to_first_output_token".to_string(),
-                        metric_value_f64(ttfo.as_secs_f64() * 1000.0, "ms"),
-                    );
-                }
-                metrics.insert(
-                    "request_latency".to_string(),
-                    metric_value_f64(br.total_latency.as_secs_f64() * 1000.0, "ms"),
-                );
-                metrics.insert(
-                    "inter_token_latency".to_string(),
-                    metric_value_f64(br.inter_token_latency_s * 1000.0, "ms"),
-                );
-                metrics.insert(
-                    "inter_event_latency".to_string(),
-                    metric_value_f64(br.inter_event_latency_s * 1000.0, "ms"),
-                );
-                metrics.insert(
-                    "output_token_throughput_per_request".to_string(),
-                    metric_value_f64(br.throughput, "tokens/sec/request"),
-                );
-                metrics.insert(
-                    "input_sequence_length".to_string(),
-                    metric_value_u64(br.input_tokens as u64, "tokens"),
-                );
-                metrics.insert(
-                    "output_token_count".to_string(),
-                    metric_value_u64(br.output_tokens as u64, "tokens"),
-                );
-                metrics.insert(
-                    "reasoning_token_count".to_string(),
-                    metric_value_u64(br.reasoning_tokens as u64, "tokens"),
-                );
-                metrics.insert(
-                    "output_sequence_length".to_string(),
-                    metric_value_u64((br.output_tokens + br.reasoning_tokens) as u64, "tokens"),
-                );
-
-                let record = RequestRecord {
-                    metadata: RequestMetadata {
-                        request_index,
-                        request_start_ns: Some(br.request_start_unix_ns),
-                        request_end_ns: Some(br.request_end_unix_ns),
-                        benchmark_phase: "profiling".to_string(),
-                    },
-                    metrics,
-                    error: None,
-                };
-                per_request_records.push(record);
-            }
-            Err(msg) => {
-                *error_counts_by_message.entry(msg.clone()).or_default() += 1;
-
-                let record = RequestRecord {
-                    metadata: RequestMetadata {
-                        request_index,
-                        request_start_ns: None,
-                        request_end_ns: None,
-                        benchmark_phase: "profiling".to_string(),
-                    },
-                    metrics: BTreeMap::new(),
-                    error: Some(RequestError {
-                        code: 1,
-                        error_type: "RequestError".to_string(),
-                        message: msg.clone(),
-                    }),
-                };
-                per_request_records.push(record);
             }
+            Err(failure) => failures.push(failure.clone()),
         }
     }
 
-    {
-        let path = run_results_dir.join("individual_responses.jsonl");
-        let mut file = File::create(&path)?;
-        for record in &per_request_records {
-            let line = serde_json::to_string(record)?;
-            file.write_all(line.as_bytes())?;
-            file.write_all(b"\n")?;
-        }
-    }
-
-    let summary = build_summary(
-        &run_id,
+    build_summary(
+        run_id,
         config,
         &successful_results,
         all_results.len(),
         total_input_tokens,
         total_output_tokens,
         total_reasoning_tokens,
-        &error_counts_by_message,
-        total_start_time,
-        total_end_time,
+        &failures,
+        benchmark_duration,
         start_time_unix_ns,
         end_time_unix_ns,
-    );
+    )
+}
 
-    {
-        let summary_path = run_results_dir.join("summary.json");
-        let mut file = File::create(&summary_path)?;
-        let summary_json = serde_json::to_string_pretty(&summary)?;
-        file.write_all(summary_json.as_bytes())?;
-    }
+pub fn write_summary(path: &Path, summary: &BenchmarkSummary) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    let summary_json = serde_json::to_string_pretty(summary)?;
+    file.write_all(summary_json.as_bytes())
+}
+
+fn request_record(
+    request_index: usize,
+    result: &Result<BenchmarkResult, RequestFailure>,
+    benchmark_phase: &str,
+    slos: &[Slo],
+) -> RequestRecord {
+    match result {
+        Ok(br) => {
+            let mut metrics = BTreeMap::new();
+            metrics.insert(
+                "time_to_first_token".to_string(),
+                metric_value_f64(br.ttft.as_secs_f64() * 1000.0, "ms"),
+            );
+            if let Some(ttfo) = br.ttfo {
+                metrics.insert(
+                    "time_to_first_output_token".to_string(),
+                    metric_value_f64(ttfo.as_secs_f64() * 1000.0, "ms"),
+                );
+            }
+            metrics.insert(
+                "request_latency".to_string(),
+                metric_value_f64(br.total_latency.as_secs_f64() * 1000.0, "ms"),
+            );
+            metrics.insert(
+                "inter_token_latency".to_string(),
+                metric_value_f64(br.inter_token_latency_s * 1000.0, "ms"),
+            );
+            metrics.insert(
+                "inter_event_latency".to_string(),
+                metric_value_f64(br.inter_event_latency_s * 1000.0, "ms"),
+            );
+            if let Some(max_stall_s) = br.max_stall_s() {
+                metrics.insert(
+                    "max_stall".to_string(),
+                    metric_value_f64(max_stall_s * 1000.0, "ms"),
+                );
+            }
+            metrics.insert(
+                "output_token_throughput_per_request".to_string(),
+                metric_value_f64(br.throughput, "tokens/sec/request"),
+            );
+            metrics.insert(
+                "input_sequence_length".to_string(),
+                metric_value_u64(br.input_tokens as u64, "tokens"),
+            );
+            metrics.insert(
+                "output_token_count".to_string(),
+                metric_value_u64(br.output_tokens as u64, "tokens"),
+            );
+            metrics.insert(
+                "reasoning_token_count".to_string(),
+                metric_value_u64(br.reasoning_tokens as u64, "tokens"),
+            );
+            metrics.insert(
+                "output_sequence_length".to_string(),
+                metric_value_u64((br.output_tokens + br.reasoning_tokens) as u64, "tokens"),
+            );
+            if let Some(requested) = br.requested_output_tokens {
+                metrics.insert(
+                    "requested_output_tokens".to_string(),
+                    metric_value_u64(requested as u64, "tokens"),
+                );
+            }
+            if let Some(deviation) = br.output_length_deviation() {
+                metrics.insert(
+                    "output_length_deviation".to_string(),
+                    MetricValue {
+                        value: Value::from(deviation),
+                        unit: "tokens".to_string(),
+                    },
+                );
+            }
+            if let Some(lag_ms) = scheduler_lag_ms(br) {
+                metrics.insert("scheduler_lag".to_string(), metric_value_f64(lag_ms, "ms"));
+            }
 
-    Ok(WrittenResults { summary })
+            RequestRecord {
+                metadata: RequestMetadata {
+                    request_index,
+                    request_start_ns: Some(br.request_start_unix_ns),
+                    request_end_ns: Some(br.request_end_unix_ns),
+                    request_scheduled_ns: br.scheduled_start_unix_ns,
+                    session_index: br.conversation.map(|turn| turn.session_index),
+                    turn_index: br.conversation.map(|turn| turn.turn_index),
+                    context_length: br.conversation.map(|_| br.input_tokens),
+                    prefix_index: br.prefix.map(|prefix| prefix.prefix_index),
+                    prefix_cache_hit_likely: br.prefix.map(|prefix| prefix.cache_hit_likely),
+                    slo_met: meets_slos(slos, br),
+                    finish_reason: br.finish_reason.clone(),
+                    attempts: (br.attempts > 1).then_some(br.attempts),
+                    benchmark_phase: benchmark_phase.to_string(),
+                },
+                metrics,
+                inter_event_gaps_ms: br
+                    .inter_event_gaps_s
+                    .iter()
+                    .map(|gap_s| gap_s * 1000.0)
+                    .collect(),
+                error: None,
+            }
+        }
+        Err(failure) => RequestRecord {
+            metadata: RequestMetadata {
+                request_index,
+                request_start_ns: failure.request_start_unix_ns,
+                request_end_ns: failure.request_end_unix_ns,
+                request_scheduled_ns: None,
+                session_index: None,
+                turn_index: None,
+                context_length: None,
+                prefix_index: None,
+                prefix_cache_hit_likely: None,
+                slo_met: (!slos.is_empty()).then_some(false),
+                finish_reason: Some("error".to_string()),
+                attempts: (failure.attempts > 1).then_some(failure.attempts),
+                benchmark_phase: benchmark_phase.to_string(),
+            },
+            metrics: BTreeMap::new(),
+            inter_event_gaps_ms: Vec::new(),
+            error: Some(RequestError {
+                code: failure.code(),
+                error_type: failure.kind.as_str().to_string(),
+                message: failure.message.clone(),
+            }),
+        },
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -522,13 +1156,12 @@ fn build_summary(
     total_input_tokens: u64,
     total_output_tokens: u64,
     total_reasoning_tokens: u64,
-    error_counts_by_message: &BTreeMap<String, usize>,
-    start_time: std::time::Instant,
-    end_time: std::time::Instant,
+    failures: &[RequestFailure],
+    benchmark_duration: Duration,
     start_time_unix_ns: u64,
     end_time_unix_ns: u64,
 ) -> BenchmarkSummary {
-    let total_time_s = end_time.duration_since(start_time).as_secs_f64();
+    let total_time_s = benchmark_duration.as_secs_f64();
 
     let mut request_latency_ms = Vec::new();
     let mut ttft_ms = Vec::new();
@@ -540,6 +1173,14 @@ fn build_summary(
     let mut out_tokens = Vec::new();
     let mut reasoning_tokens = Vec::new();
     let mut output_sequence_tokens = Vec::new();
+    let mut scheduler_lag = Vec::new();
+    let mut ttft_prefix_hit_ms = Vec::new();
+    let mut ttft_prefix_miss_ms = Vec::new();
+    let mut inter_event_gap_ms = Vec::new();
+    let mut max_stall_ms = Vec::new();
+    let mut output_length_deviation = Vec::new();
+    let mut slo_met_requests = 0_usize;
+    let mut slo_met_output_tokens = 0_u64;
 
     for br in successful_results {
         request_latency_ms.push(br.total_latency.as_secs_f64() * 1000.0);
@@ -549,15 +1190,37 @@ fn build_summary(
         }
         inter_token_ms.push(br.inter_token_latency_s * 1000.0);
         inter_event_ms.push(br.inter_event_latency_s * 1000.0);
+        inter_event_gap_ms.extend(br.inter_event_gaps_s.iter().map(|gap| gap * 1000.0));
+        if let Some(max_stall_s) = br.max_stall_s() {
+            max_stall_ms.push(max_stall_s * 1000.0);
+        }
         throughput_per_request.push(br.throughput);
         in_tokens.push(br.input_tokens as f64);
         out_tokens.push(br.output_tokens as f64);
         reasoning_tokens.push(br.reasoning_tokens as f64);
         output_sequence_tokens.push((br.output_tokens + br.reasoning_tokens) as f64);
+        if let Some(deviation) = br.output_length_deviation() {
+            output_length_deviation.push(deviation as f64);
+        }
+        if let Some(lag_ms) = scheduler_lag_ms(br) {
+            scheduler_lag.push(lag_ms);
+        }
+        if meets_slos(config.slos, br) == Some(true) {
+            slo_met_requests += 1;
+            slo_met_output_tokens += (br.output_tokens + br.reasoning_tokens) as u64;
+        }
+        match br.prefix {
+            Some(prefix) if prefix.cache_hit_likely => {
+                ttft_prefix_hit_ms.push(br.ttft.as_secs_f64() * 1000.0)
+            }
+            Some(_) => ttft_prefix_miss_ms.push(br.ttft.as_secs_f64() * 1000.0),
+            None => {}
+        }
     }
 
     let completed_requests = successful_results.len();
     let num_errors = num_requests_started.saturating_sub(completed_requests);
+    let finish_reasons = finish_reason_counts(successful_results, num_errors);
     let error_rate = if num_requests_started == 0 {
         0.0
     } else {
@@ -583,16 +1246,39 @@ fn build_summary(
         0.0
     };
 
-    let error_summary = error_counts_by_message
-        .iter()
-        .map(|(message, count)| ErrorSummaryEntry {
-            code: 1,
-            message: message.clone(),
-            count: *count,
+    let has_slos = !config.slos.is_empty();
+    let slo_attainment = if num_requests_started == 0 {
+        0.0
+    } else {
+        slo_met_requests as f64 / num_requests_started as f64
+    };
+    let (goodput_request_throughput, goodput_token_throughput) = if total_time_s > 0.0 {
+        (
+            slo_met_requests as f64 / total_time_s,
+            slo_met_output_tokens as f64 / total_time_s,
+        )
+    } else {
+        (0.0, 0.0)
+    };
+
+    let mut error_counts: BTreeMap<(&str, i32, &str), usize> = BTreeMap::new();
+    for failure in failures {
+        *error_counts
+            .entry((failure.kind.as_str(), failure.code(), &failure.message))
+            .or_default() += 1;
+    }
+    let error_summary = error_counts
+        .into_iter()
+        .map(|((error_type, code, message), count)| ErrorSummaryEntry {
+            error_type: error_type.to_string(),
+            code,
+            message: message.to_string(),
+            count,
         })
         .collect();
+    let retries = retry_count(successful_results, failures);
 
-    BenchmarkSummary {
+    let mut summary = BenchmarkSummary {
         version: "2026-02-19".to_string(),
         schema_version: "2.0".to_string(),
         llmnop_version: env!("CARGO_PKG_VERSION").to_string(),
@@ -610,13 +1296,43 @@ fn build_summary(
                 .mean_output_tokens
                 .map(|_| config.stddev_output_tokens),
             num_concurrent_requests: config.num_concurrent_requests,
+            request_rate: config.request_rate,
+            arrival_distribution: config.arrival_distribution.map(str::to_string),
+            warmup_requests: (config.warmup_requests > 0).then_some(config.warmup_requests),
+            duration_s: config.duration.map(|duration| duration.as_secs_f64()),
+            dataset: config.dataset.map(|path| path.display().to_string()),
+            num_turns: (config.num_turns > 1).then_some(config.num_turns),
+            prefix_tokens: config.prefix_tokens,
+            num_prefixes: config.prefix_tokens.map(|_| config.num_prefixes),
+            prefix_skew: config.prefix_tokens.map(|_| config.prefix_skew),
+            slos: (!config.slos.is_empty()).then(|| {
+                config
+                    .slos
+                    .iter()
+                    .map(|slo| {
+                        (
+                            slo.metric.as_str().to_string(),
+                            slo.threshold.as_secs_f64() * 1000.0,
+                        )
+                    })
+                    .collect()
+            }),
+            extra_body: config.extra_body.cloned(),
+            max_retries: (config.max_retries > 0).then_some(config.max_retries),
         },
         benchmark_duration: metric_stats_avg_only("sec", total_time_s),
         request_count: metric_stats_avg_only("requests", num_requests_started as f64),
         successful_request_count: metric_stats_avg_only("requests", completed_requests as f64),
         error_request_count: metric_stats_avg_only("requests", num_errors as f64),
         error_rate: metric_stats_avg_only("ratio", error_rate),
+        retry_count: (config.max_retries > 0)
+            .then(|| metric_stats_avg_only("requests", retries as f64)),
         request_throughput: metric_stats_avg_only("requests/sec", request_throughput),
+        slo_attainment: has_slos.then(|| metric_stats_avg_only("ratio", slo_attainment)),
+        goodput_request_throughput: has_slos
+            .then(|| metric_stats_avg_only("requests/sec", goodput_request_throughput)),
+        goodput_token_throughput: has_slos
+            .then(|| metric_stats_avg_only("tokens/sec", goodput_token_throughput)),
         request_latency: metric_stats_from_values(&request_latency_ms, "ms"),
         time_to_first_token: metric_stats_from_values(&ttft_ms, "ms"),
         time_to_first_output_token: if ttfo_ms.is_empty() {
@@ -626,6 +1342,25 @@ fn build_summary(
         },
         inter_token_latency: metric_stats_from_values(&inter_token_ms, "ms"),
         inter_event_latency: metric_stats_from_values(&inter_event_ms, "ms"),
+        inter_event_gap: (!inter_event_gap_ms.is_empty())
+            .then(|| metric_stats_from_values(&inter_event_gap_ms, "ms")),
+        max_stall: (!max_stall_ms.is_empty())
+            .then(|| metric_stats_from_values(&max_stall_ms, "ms")),
+        scheduler_lag: if scheduler_lag.is_empty() {
+            None
+        } else {
+            Some(metric_stats_from_values(&scheduler_lag, "ms"))
+        },
+        time_to_first_token_prefix_hit: if ttft_prefix_hit_ms.is_empty() {
+            None
+        } else {
+            Some(metric_stats_from_values(&ttft_prefix_hit_ms, "ms"))
+        },
+        time_to_first_token_prefix_miss: if ttft_prefix_miss_ms.is_empty() {
+            None
+        } else {
+            Some(metric_stats_from_values(&ttft_prefix_miss_ms, "ms"))
+        },
         output_token_throughput_per_request: metric_stats_from_values(
             &throughput_per_request,
             "tokens/sec/request",
@@ -636,6 +1371,8 @@ fn build_summary(
         output_token_count: metric_stats_from_values(&out_tokens, "tokens"),
         reasoning_token_count: metric_stats_from_values(&reasoning_tokens, "tokens"),
         output_sequence_length: metric_stats_from_values(&output_sequence_tokens, "tokens"),
+        output_length_deviation: (!output_length_deviation.is_empty())
+            .then(|| metric_stats_from_values(&output_length_deviation, "tokens")),
         total_input_tokens: metric_stats_avg_only("tokens", total_input_tokens as f64),
         total_output_tokens: metric_stats_avg_only("tokens", total_output_tokens as f64),
         total_reasoning_tokens: metric_stats_avg_only("tokens", total_reasoning_tokens as f64),
@@ -643,8 +1380,13 @@ fn build_summary(
             "tokens",
             total_output_sequence_tokens as f64,
         ),
+        finish_reasons,
         error_summary,
-    }
+        window: None,
+        verdict: None,
+    };
+    summary.verdict = config.gate.map(|gate| gate.evaluate(&summary));
+    summary
 }
 
 #[derive(Default)]
@@ -712,6 +1454,97 @@ fn compute_stats(values: &[f64]) -> StatSet {
     }
 }
 
+const BOOTSTRAP_RESAMPLES: usize = 1000;
+/// Larger sample sets skip bootstrapping to keep `llmnop compare` quick.
+const BOOTSTRAP_MAX_VALUES: usize = 10_000;
+
+/// 95% percentile-bootstrap confidence interval for the `pct` quantile of `values`.
+pub fn bootstrap_ci(values: &[f64], pct: f64) -> Option<[f64; 2]> {
+    if values.len() < 2 || values.len() > BOOTSTRAP_MAX_VALUES {
+        return None;
+    }
+
+    // A fixed seed keeps the interval reproducible for the same samples.
+    let mut rng = StdRng::seed_from_u64(0x6c6c_6d6e_6f70);
+    let idx = ((values.len() - 1) as f64 * pct).floor() as usize;
+    let mut resample = vec![0.0; values.len()];
+    let mut estimates = Vec::with_capacity(BOOTSTRAP_RESAMPLES);
+    for _ in 0..BOOTSTRAP_RESAMPLES {
+        for slot in resample.iter_mut() {
+            *slot = values[rng.random_range(0..values.len())];
+        }
+        let (_, estimate, _) = resample.select_nth_unstable_by(idx, f64::total_cmp);
+        estimates.push(*estimate);
+    }
+
+    estimates.sort_by(f64::total_cmp);
+    Some([percentile(&estimates, 0.025), percentile(&estimates, 0.975)])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MannWhitney {
+    pub u: f64,
+    /// Two-sided p-value from the tie-corrected normal approximation.
+    pub p_value: f64,
+}
+
+/// Mann-Whitney U test of whether `a` and `b` come from the same distribution.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<MannWhitney> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    let n1 = a.len() as f64;
+    let n2 = b.len() as f64;
+    let mut combined: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&v| (v, true))
+        .chain(b.iter().map(|&v| (v, false)))
+        .collect();
+    combined.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut start = 0;
+    while start < combined.len() {
+        let mut end = start;
+        while end + 1 < combined.len() && combined[end + 1].0 == combined[start].0 {
+            end += 1;
+        }
+        let ties = (end - start + 1) as f64;
+        let avg_rank = (start + end) as f64 / 2.0 + 1.0;
+        let from_a = combined[start..=end]
+            .iter()
+            .filter(|(_, in_a)| *in_a)
+            .count();
+        rank_sum_a += from_a as f64 * avg_rank;
+        tie_term += ties.powi(3) - ties;
+        start = end + 1;
+    }
+
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        // Every value is tied, so there is no evidence of a difference.
+        return Some(MannWhitney { u, p_value: 1.0 });
+    }
+
+    let z = ((u - n1 * n2 / 2.0).abs() - 0.5).max(0.0) / variance.sqrt();
+    let p_value = erfc(z / std::f64::consts::SQRT_2).min(1.0);
+    Some(MannWhitney { u, p_value })
+}
+
+/// Complementary error function for `x >= 0` (Abramowitz and Stegun 7.1.26).
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    poly * (-x * x).exp()
+}
+
 fn metric_stats_from_values(values: &[f64], unit: &str) -> MetricStats {
     if values.is_empty() {
         return MetricStats {
@@ -784,6 +1617,13 @@ fn metric_value_u64(value: u64, unit: &str) -> MetricValue {
     }
 }
 
+/// Delay between when an open-loop request was due and when it was actually sent.
+fn scheduler_lag_ms(br: &BenchmarkResult) -> Option<f64> {
+    br.scheduled_start_unix_ns.map(|scheduled| {
+        (i128::from(br.request_start_unix_ns) - i128::from(scheduled)) as f64 / 1_000_000.0
+    })
+}
+
 fn percentile(sorted_values: &[f64], pct: f64) -> f64 {
     if sorted_values.is_empty() {
         return 0.0;
@@ -795,6 +1635,10 @@ fn percentile(sorted_values: &[f64], pct: f64) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::benchmark::ConversationTurn;
+    use crate::error::ErrorKind;
+    use crate::prompt::PrefixUse;
+    use crate::slo::SloMetric;
     use std::path::Path;
     use std::time::Duration;
 
@@ -834,6 +1678,36 @@ mod tests {
         assert_eq!(deserialized.p99, 0.99);
     }
 
+    #[test]
+    fn test_bootstrap_ci_brackets_quantile() {
+        let values: Vec<f64> = (1..=200).map(f64::from).collect();
+        let [low, high] = bootstrap_ci(&values, 0.50).unwrap();
+        assert!(low < 100.0 && 100.0 < high, "[{low}, {high}]");
+        assert_eq!(bootstrap_ci(&values, 0.50), Some([low, high]));
+
+        let [low, high] = bootstrap_ci(&values, 0.99).unwrap();
+        assert!(low <= 198.0 && high <= 200.0, "[{low}, {high}]");
+
+        assert_eq!(bootstrap_ci(&[1.0], 0.50), None);
+    }
+
+    #[test]
+    fn test_mann_whitney_u() {
+        // U counts pairs where a exceeds b: only 4 and 5 beat 3.5.
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let b = [3.5, 6.0, 7.0, 8.0, 9.0];
+        let result = mann_whitney_u(&a, &b).unwrap();
+        assert_eq!(result.u, 2.0);
+        assert!(result.p_value < 0.05, "{}", result.p_value);
+
+        let same: Vec<f64> = (0..50).map(|i| f64::from(i % 10)).collect();
+        let result = mann_whitney_u(&same, &same).unwrap();
+        assert!(result.p_value > 0.9, "{}", result.p_value);
+
+        assert_eq!(mann_whitney_u(&[2.0, 2.0], &[2.0]).unwrap().p_value, 1.0);
+        assert_eq!(mann_whitney_u(&[], &[1.0]), None);
+    }
+
     #[test]
     fn test_stats_computation() {
         let values = vec![1.0, 2.0, 3.0, 4.0, 5.0];
@@ -867,13 +1741,8 @@ mod tests {
     #[test]
     fn test_benchmark_slug() {
         let config = BenchmarkConfig {
-            model: "qwen/qwen3-4b-2507",
-            tokenizer: "Qwen/Qwen3-4B",
-            mean_input_tokens: 550,
-            stddev_input_tokens: 0,
             mean_output_tokens: Some(150),
-            stddev_output_tokens: 0,
-            num_concurrent_requests: 1,
+            ..test_config()
         };
 
         assert_eq!(benchmark_slug(&config), "qwen-qwen3-4b-2507_550_150");
@@ -881,15 +1750,7 @@ mod tests {
 
     #[test]
     fn test_benchmark_slug_without_output_tokens() {
-        let config = BenchmarkConfig {
-            model: "qwen/qwen3-4b-2507",
-            tokenizer: "Qwen/Qwen3-4B",
-            mean_input_tokens: 550,
-            stddev_input_tokens: 0,
-            mean_output_tokens: None,
-            stddev_output_tokens: 0,
-            num_concurrent_requests: 1,
-        };
+        let config = test_config();
 
         assert_eq!(benchmark_slug(&config), "qwen-qwen3-4b-2507_550_none");
     }
@@ -911,13 +1772,8 @@ mod tests {
     #[test]
     fn test_run_results_dir_layout() {
         let config = BenchmarkConfig {
-            model: "qwen/qwen3-4b-2507",
-            tokenizer: "Qwen/Qwen3-4B",
-            mean_input_tokens: 550,
-            stddev_input_tokens: 0,
             mean_output_tokens: Some(150),
-            stddev_output_tokens: 0,
-            num_concurrent_requests: 1,
+            ..test_config()
         };
         let path = run_results_dir(Path::new("/tmp/results"), &config, "1700000000_123456789");
         assert_eq!(
@@ -929,13 +1785,8 @@ mod tests {
     #[test]
     fn test_build_summary_has_nested_metrics() {
         let config = BenchmarkConfig {
-            model: "qwen/qwen3-4b-2507",
-            tokenizer: "Qwen/Qwen3-4B",
-            mean_input_tokens: 550,
-            stddev_input_tokens: 0,
             mean_output_tokens: Some(150),
-            stddev_output_tokens: 0,
-            num_concurrent_requests: 1,
+            ..test_config()
         };
 
         let successful_results = vec![BenchmarkResult {
@@ -948,9 +1799,17 @@ mod tests {
             reasoning_tokens: 30,
             inter_token_latency_s: 0.01,
             inter_event_latency_s: 0.02,
+            inter_event_gaps_s: Vec::new(),
             total_tokens: 700,
             request_start_unix_ns: 1_700_000_000_000_000_000,
             request_end_unix_ns: 1_700_000_000_900_000_000,
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: None,
+            requested_output_tokens: None,
+            finish_reason: None,
+            attempts: 1,
+            generated_text: String::new(),
         }];
 
         let summary = build_summary(
@@ -961,9 +1820,8 @@ mod tests {
             550,
             120,
             30,
-            &BTreeMap::new(),
-            std::time::Instant::now(),
-            std::time::Instant::now() + Duration::from_secs(1),
+            &[],
+            Duration::from_secs(1),
             1_700_000_000_000_000_000,
             1_700_000_001_000_000_000,
         );
@@ -975,5 +1833,435 @@ mod tests {
             "tokens/sec/request"
         );
         assert!(summary.time_to_first_output_token.is_some());
+        assert!(summary.scheduler_lag.is_none());
+    }
+
+    #[test]
+    fn test_build_summary_splits_ttft_by_prefix_cache() {
+        let config = BenchmarkConfig {
+            mean_input_tokens: 100,
+            prefix_tokens: Some(2000),
+            ..test_config()
+        };
+
+        let result = |ttft_ms: u64, cache_hit_likely: bool| BenchmarkResult {
+            ttft: Duration::from_millis(ttft_ms),
+            ttfo: None,
+            total_latency: Duration::from_millis(900),
+            throughput: 75.0,
+            input_tokens: 2100,
+            output_tokens: 120,
+            reasoning_tokens: 0,
+            inter_token_latency_s: 0.01,
+            inter_event_latency_s: 0.02,
+            inter_event_gaps_s: Vec::new(),
+            total_tokens: 2220,
+            request_start_unix_ns: 0,
+            request_end_unix_ns: 0,
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: Some(PrefixUse {
+                prefix_index: 0,
+                cache_hit_likely,
+            }),
+            requested_output_tokens: None,
+            finish_reason: None,
+            attempts: 1,
+            generated_text: String::new(),
+        };
+        let successful_results = vec![result(400, false), result(40, true), result(60, true)];
+
+        let summary = build_summary(
+            "1700000000_123456789",
+            &config,
+            &successful_results,
+            3,
+            6300,
+            360,
+            0,
+            &[],
+            Duration::from_secs(1),
+            0,
+            0,
+        );
+
+        let hit = summary.time_to_first_token_prefix_hit.expect("hit stats");
+        let miss = summary.time_to_first_token_prefix_miss.expect("miss stats");
+        assert_eq!(hit.avg, Some(50.0));
+        assert_eq!(miss.avg, Some(400.0));
+        assert_eq!(summary.input_config.prefix_tokens, Some(2000));
+        assert_eq!(summary.input_config.num_prefixes, Some(1));
+    }
+
+    #[test]
+    fn test_build_summary_reports_goodput() {
+        let slos = [Slo {
+            metric: SloMetric::TimeToFirstToken,
+            threshold: Duration::from_millis(200),
+        }];
+        let config = BenchmarkConfig {
+            mean_output_tokens: Some(150),
+            slos: &slos,
+            ..test_config()
+        };
+
+        let result = |ttft_ms: u64| BenchmarkResult {
+            ttft: Duration::from_millis(ttft_ms),
+            ttfo: None,
+            total_latency: Duration::from_millis(900),
+            throughput: 75.0,
+            input_tokens: 550,
+            output_tokens: 100,
+            reasoning_tokens: 0,
+            inter_token_latency_s: 0.01,
+            inter_event_latency_s: 0.01,
+            inter_event_gaps_s: Vec::new(),
+            total_tokens: 650,
+            request_start_unix_ns: 0,
+            request_end_unix_ns: 0,
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: None,
+            requested_output_tokens: None,
+            finish_reason: None,
+            attempts: 1,
+            generated_text: String::new(),
+        };
+        // Three successes, one too slow, plus one failed request.
+        let successful_results = vec![result(100), result(150), result(300)];
+        let summary = build_summary(
+            "1700000000_123456789",
+            &config,
+            &successful_results,
+            4,
+            1650,
+            300,
+            0,
+            &[RequestFailure::new(ErrorKind::Other, "boom")],
+            Duration::from_secs(2),
+            0,
+            0,
+        );
+
+        assert_eq!(summary.slo_attainment.unwrap().avg, Some(0.5));
+        assert_eq!(summary.goodput_request_throughput.unwrap().avg, Some(1.0));
+        assert_eq!(summary.goodput_token_throughput.unwrap().avg, Some(100.0));
+        assert_eq!(
+            summary.input_config.slos,
+            Some(BTreeMap::from([("ttft".to_string(), 200.0)]))
+        );
+
+        let record = request_record(0, &Ok(result(300)), "profiling", &slos);
+        assert_eq!(record.metadata.slo_met, Some(false));
+    }
+
+    #[test]
+    fn test_build_summary_pools_inter_event_gaps() {
+        let config = BenchmarkConfig {
+            mean_output_tokens: Some(150),
+            ..test_config()
+        };
+
+        let result = |inter_event_gaps_s: Vec<f64>| BenchmarkResult {
+            ttft: Duration::from_millis(100),
+            ttfo: None,
+            total_latency: Duration::from_millis(900),
+            throughput: 75.0,
+            input_tokens: 550,
+            output_tokens: 100,
+            reasoning_tokens: 0,
+            inter_token_latency_s: 0.01,
+            inter_event_latency_s: 0.01,
+            inter_event_gaps_s,
+            total_tokens: 650,
+            request_start_unix_ns: 0,
+            request_end_unix_ns: 0,
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: None,
+            requested_output_tokens: None,
+            finish_reason: None,
+            attempts: 1,
+            generated_text: String::new(),
+        };
+        // One request streams smoothly; the other stalls once for half a second.
+        let successful_results = vec![
+            result(vec![0.01, 0.01, 0.01]),
+            result(vec![0.01, 0.5, 0.01]),
+            result(Vec::new()),
+        ];
+        let summary = build_summary(
+            "1700000000_123456789",
+            &config,
+            &successful_results,
+            3,
+            1650,
+            300,
+            0,
+            &[],
+            Duration::from_secs(1),
+            0,
+            0,
+        );
+
+        let gaps = summary.inter_event_gap.expect("gap stats");
+        assert_eq!(gaps.unit, "ms");
+        assert_eq!(gaps.max, Some(500.0));
+        assert_eq!(gaps.p50, Some(10.0));
+
+        let max_stall = summary.max_stall.expect("max stall stats");
+        assert_eq!(max_stall.min, Some(10.0));
+        assert_eq!(max_stall.max, Some(500.0));
+
+        let record = request_record(1, &Ok(successful_results[1].clone()), "profiling", &[]);
+        assert!(record.metrics.contains_key("max_stall"));
+        let record = request_record(2, &Ok(successful_results[2].clone()), "profiling", &[]);
+        assert!(!record.metrics.contains_key("max_stall"));
+    }
+
+    #[test]
+    fn test_output_length_deviation_and_finish_reasons() {
+        let config = BenchmarkConfig {
+            mean_output_tokens: Some(100),
+            ..test_config()
+        };
+
+        let result = |output_tokens: u32, finish_reason: &str| BenchmarkResult {
+            ttft: Duration::from_millis(100),
+            ttfo: None,
+            total_latency: Duration::from_millis(900),
+            throughput: 75.0,
+            input_tokens: 550,
+            output_tokens,
+            reasoning_tokens: 10,
+            inter_token_latency_s: 0.01,
+            inter_event_latency_s: 0.01,
+            inter_event_gaps_s: Vec::new(),
+            total_tokens: 550 + output_tokens + 10,
+            request_start_unix_ns: 0,
+            request_end_unix_ns: 0,
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: None,
+            requested_output_tokens: Some(100),
+            finish_reason: Some(finish_reason.to_string()),
+            attempts: 1,
+            generated_text: String::new(),
+        };
+        let results = vec![
+            Ok(result(90, "length")),
+            Ok(result(30, "stop")),
+            Ok(result(90, "length")),
+            Err(RequestFailure::new(ErrorKind::Stream, "boom")),
+        ];
+        let summary = summarize_results("run", &config, &results, Duration::from_secs(1), 0, 0);
+
+        let deviation = summary.output_length_deviation.expect("deviation stats");
+        assert_eq!(deviation.unit, "tokens");
+        assert_eq!(deviation.min, Some(-60.0));
+        assert_eq!(deviation.max, Some(0.0));
+        assert_eq!(
+            summary.finish_reasons,
+            BTreeMap::from([
+                ("error".to_string(), 1),
+                ("length".to_string(), 2),
+                ("stop".to_string(), 1),
+            ])
+        );
+
+        let record = request_record(0, &results[1], "profiling", &[]);
+        assert_eq!(record.metadata.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(
+            record.metrics["requested_output_tokens"].value,
+            Value::from(100)
+        );
+        assert_eq!(
+            record.metrics["output_length_deviation"].value,
+            Value::from(-60)
+        );
+    }
+
+    #[test]
+    fn test_request_index_is_unique_across_phases() {
+        let config = BenchmarkConfig {
+            warmup_requests: 1,
+            ..test_config()
+        };
+        let failed = || Err(RequestFailure::new(ErrorKind::Other, "boom"));
+        let results_dir =
+            std::env::temp_dir().join(format!("llmnop-output-{}", std::process::id()));
+
+        let written = write_results_json(
+            &results_dir,
+            &config,
+            &[failed()],
+            &[failed(), failed()],
+            &[failed()],
+            Duration::from_secs(1),
+            0,
+            0,
+        )
+        .unwrap();
+        let records =
+            std::fs::read_to_string(written.dir.join("individual_responses.jsonl")).unwrap();
+        let keys: Vec<(usize, String)> = records
+            .lines()
+            .map(|line| {
+                let record: RequestRecord = serde_json::from_str(line).unwrap();
+                (
+                    record.metadata.request_index,
+                    record.metadata.benchmark_phase,
+                )
+            })
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (0, "warmup".to_string()),
+                (1, "profiling".to_string()),
+                (2, "profiling".to_string()),
+                (3, "drain".to_string()),
+            ]
+        );
+
+        std::fs::remove_dir_all(&results_dir).unwrap();
+    }
+
+    #[test]
+    fn test_request_record_phase_and_error() {
+        let failure = RequestFailure {
+            status: Some(429),
+            attempts: 3,
+            request_start_unix_ns: Some(10),
+            request_end_unix_ns: Some(20),
+            ..RequestFailure::new(
+                ErrorKind::RateLimit,
+                "HTTP 429 Too Many Requests: slow down",
+            )
+        };
+        let record = request_record(3, &Err(failure), "warmup", &[]);
+        assert_eq!(record.metadata.request_start_ns, Some(10));
+        assert_eq!(record.metadata.request_end_ns, Some(20));
+
+        assert_eq!(record.metadata.request_index, 3);
+        assert_eq!(record.metadata.benchmark_phase, "warmup");
+        assert_eq!(record.metadata.finish_reason.as_deref(), Some("error"));
+        assert_eq!(record.metadata.attempts, Some(3));
+        assert!(record.metrics.is_empty());
+        let error = record.error.unwrap();
+        assert_eq!(error.code, 429);
+        assert_eq!(error.error_type, "rate_limit");
+        assert_eq!(error.message, "HTTP 429 Too Many Requests: slow down");
+    }
+
+    #[test]
+    fn test_error_summary_groups_by_type_and_counts_retries() {
+        let config = BenchmarkConfig {
+            max_retries: 2,
+            ..test_config()
+        };
+        let rate_limited = RequestFailure {
+            status: Some(429),
+            attempts: 3,
+            ..RequestFailure::new(
+                ErrorKind::RateLimit,
+                "HTTP 429 Too Many Requests: slow down",
+            )
+        };
+        let failures = [
+            rate_limited.clone(),
+            rate_limited,
+            RequestFailure::new(ErrorKind::Stream, "overloaded"),
+        ];
+        let summary = build_summary(
+            "run",
+            &config,
+            &[],
+            3,
+            0,
+            0,
+            0,
+            &failures,
+            Duration::ZERO,
+            0,
+            0,
+        );
+
+        let entries: Vec<(&str, i32, usize)> = summary
+            .error_summary
+            .iter()
+            .map(|entry| (entry.error_type.as_str(), entry.code, entry.count))
+            .collect();
+        assert_eq!(entries, vec![("rate_limit", 429, 2), ("stream", 0, 1)]);
+        assert_eq!(summary.retry_count.unwrap().avg, Some(4.0));
+        assert_eq!(summary.input_config.max_retries, Some(2));
+
+        let by_type = BTreeMap::from([("rate_limit".to_string(), 2), ("stream".to_string(), 1)]);
+        assert_eq!(format_errors(3, &by_type), "3 (rate_limit 2, stream 1)");
+        assert_eq!(format_errors(0, &BTreeMap::new()), "0");
+    }
+
+    #[test]
+    fn test_scheduler_lag_ms() {
+        let mut result = BenchmarkResult {
+            ttft: Duration::from_millis(100),
+            ttfo: None,
+            total_latency: Duration::from_millis(900),
+            throughput: 75.0,
+            input_tokens: 550,
+            output_tokens: 120,
+            reasoning_tokens: 0,
+            inter_token_latency_s: 0.01,
+            inter_event_latency_s: 0.02,
+            inter_event_gaps_s: Vec::new(),
+            total_tokens: 670,
+            request_start_unix_ns: 1_700_000_000_002_500_000,
+            request_end_unix_ns: 1_700_000_000_902_500_000,
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: None,
+            requested_output_tokens: None,
+            finish_reason: None,
+            attempts: 1,
+            generated_text: String::new(),
+        };
+        assert_eq!(scheduler_lag_ms(&result), None);
+
+        result.scheduled_start_unix_ns = Some(1_700_000_000_000_000_000);
+        assert_eq!(scheduler_lag_ms(&result), Some(2.5));
+    }
+
+    #[test]
+    fn test_request_record_conversation_metadata() {
+        let result = BenchmarkResult {
+            ttft: Duration::from_millis(100),
+            ttfo: None,
+            total_latency: Duration::from_millis(900),
+            throughput: 75.0,
+            input_tokens: 1_840,
+            output_tokens: 120,
+            reasoning_tokens: 0,
+            inter_token_latency_s: 0.01,
+            inter_event_latency_s: 0.02,
+            inter_event_gaps_s: Vec::new(),
+            total_tokens: 1_960,
+            request_start_unix_ns: 1,
+            request_end_unix_ns: 2,
+            scheduled_start_unix_ns: None,
+            conversation: Some(ConversationTurn {
+                session_index: 4,
+                turn_index: 2,
+            }),
+            prefix: None,
+            requested_output_tokens: None,
+            finish_reason: None,
+            attempts: 1,
+            generated_text: String::new(),
+        };
+
+        let record = request_record(0, &Ok(result), "profiling", &[]);
+        assert_eq!(record.metadata.session_index, Some(4));
+        assert_eq!(record.metadata.turn_index, Some(2));
+        assert_eq!(record.metadata.context_length, Some(1_840));
     }
 }
@@ -1,7 +1,9 @@
+use crate::dataset::DatasetEntry;
 use crate::tokens;
 use anyhow::Result;
 use rand::prelude::*;
-use rand_distr::Normal;
+use rand_distr::{Normal, Zipf};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::{Arc, LazyLock, Mutex};
 
@@ -17,6 +19,133 @@ pub struct PromptConfig {
     pub stddev_input_tokens: u32,
 }
 
+pub struct SharedPrefixConfig {
+    pub prefix_tokens: u32,
+    pub num_prefixes: u32,
+    pub prefix_skew: f64,
+    pub suffix: PromptConfig,
+}
+
+/// Which shared prefix a prompt starts with, and whether an earlier prompt already sent it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PrefixUse {
+    pub prefix_index: u32,
+    pub cache_hit_likely: bool,
+}
+
+pub struct Prompt {
+    pub text: String,
+    /// Output length requested by the prompt source, overriding the sampled `max_tokens`.
+    pub output_tokens: Option<u32>,
+    pub prefix: Option<PrefixUse>,
+}
+
+pub enum PromptSource {
+    Synthetic(PromptConfig),
+    Dataset(Vec<DatasetEntry>),
+    SharedPrefix(SharedPrefixPrompts),
+}
+
+pub struct SharedPrefixPrompts {
+    prefixes: Vec<String>,
+    suffix: PromptConfig,
+    popularity: Zipf<f64>,
+    // Tracked across phases and sweep steps, since the server cache outlives a single run.
+    sent: Mutex<Vec<bool>>,
+}
+
+impl SharedPrefixPrompts {
+    pub fn new(config: SharedPrefixConfig, tokenizer: &str) -> Result<Self> {
+        let corpus = get_tokenized_corpus(tokenizer)?;
+        let prefixes = (0..config.num_prefixes)
+            .map(|_| {
+                let token_ids = sample_tokens(&corpus, config.prefix_tokens as usize);
+                tokens::decode(&token_ids, tokenizer)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let popularity = Zipf::new(config.num_prefixes as f64, config.prefix_skew)
+            .map_err(|e| anyhow::anyhow!("Invalid prefix popularity: {}", e))?;
+
+        Ok(Self {
+            sent: Mutex::new(vec![false; prefixes.len()]),
+            prefixes,
+            suffix: config.suffix,
+            popularity,
+        })
+    }
+
+    fn next_prefix(&self) -> PrefixUse {
+        // Zipf samples ranks 1..=n; rank 1 is the most popular prefix.
+        let prefix_index =
+            (self.popularity.sample(&mut rand::rng()) as usize - 1).min(self.prefixes.len() - 1);
+        let cache_hit_likely =
+            std::mem::replace(&mut self.sent.lock().unwrap()[prefix_index], true);
+
+        PrefixUse {
+            prefix_index: prefix_index as u32,
+            cache_hit_likely,
+        }
+    }
+
+    fn generate(&self, tokenizer: &str) -> Result<Prompt> {
+        let prefix = self.next_prefix();
+        let suffix = generate_prompt(&self.suffix, tokenizer)?;
+        Ok(Prompt {
+            text: format!(
+                "{}\n\n{}",
+                self.prefixes[prefix.prefix_index as usize], suffix
+            ),
+            output_tokens: None,
+            prefix: Some(prefix),
+        })
+    }
+}
+
+impl PromptSource {
+    pub fn generate(&self, num_prompts: usize, tokenizer: &str) -> Result<Vec<Prompt>> {
+        match self {
+            PromptSource::Synthetic(config) => (0..num_prompts)
+                .map(|_| {
+                    Ok(Prompt {
+                        text: generate_prompt(config, tokenizer)?,
+                        output_tokens: None,
+                        prefix: None,
+                    })
+                })
+                .collect(),
+            PromptSource::Dataset(entries) => Ok(sample_dataset_order(entries.len(), num_prompts)
+                .into_iter()
+                .map(|index| Prompt {
+                    text: entries[index].prompt.clone(),
+                    output_tokens: entries[index].output_tokens,
+                    prefix: None,
+                })
+                .collect()),
+            PromptSource::SharedPrefix(prompts) => (0..num_prompts)
+                .map(|_| prompts.generate(tokenizer))
+                .collect(),
+        }
+    }
+}
+
+/// Walks the dataset in shuffled passes so every row is used before any repeats.
+fn sample_dataset_order(num_entries: usize, num_prompts: usize) -> Vec<usize> {
+    if num_entries == 0 {
+        return Vec::new();
+    }
+
+    let mut rng = rand::rng();
+    let mut order = Vec::with_capacity(num_prompts);
+    while order.len() < num_prompts {
+        let mut pass: Vec<usize> = (0..num_entries).collect();
+        pass.shuffle(&mut rng);
+        pass.truncate(num_prompts - order.len());
+        order.extend(pass);
+    }
+    order
+}
+
 fn build_corpus_chunks() -> Vec<String> {
     let mut chunks = Vec::new();
     let mut current_chunk = String::new();
@@ -142,6 +271,60 @@ mod tests {
         assert!(result.is_empty());
     }
 
+    #[test]
+    fn sample_dataset_order_uses_every_row_before_repeating() {
+        let order = sample_dataset_order(4, 10);
+        assert_eq!(order.len(), 10);
+
+        let mut first_pass = order[..4].to_vec();
+        first_pass.sort_unstable();
+        assert_eq!(first_pass, vec![0, 1, 2, 3]);
+
+        let mut second_pass = order[4..8].to_vec();
+        second_pass.sort_unstable();
+        assert_eq!(second_pass, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn sample_dataset_order_returns_empty_for_empty_dataset() {
+        assert!(sample_dataset_order(0, 5).is_empty());
+    }
+
+    fn shared_prefix_prompts(num_prefixes: usize, prefix_skew: f64) -> SharedPrefixPrompts {
+        SharedPrefixPrompts {
+            prefixes: (0..num_prefixes).map(|i| format!("prefix {i}")).collect(),
+            suffix: PromptConfig {
+                mean_input_tokens: 10,
+                stddev_input_tokens: 0,
+            },
+            popularity: Zipf::new(num_prefixes as f64, prefix_skew).unwrap(),
+            sent: Mutex::new(vec![false; num_prefixes]),
+        }
+    }
+
+    #[test]
+    fn next_prefix_marks_repeat_uses_as_cache_hits() {
+        let prompts = shared_prefix_prompts(1, 0.0);
+
+        let first = prompts.next_prefix();
+        let second = prompts.next_prefix();
+        assert_eq!(first.prefix_index, 0);
+        assert!(!first.cache_hit_likely);
+        assert!(second.cache_hit_likely);
+    }
+
+    #[test]
+    fn next_prefix_skew_favors_first_prefix() {
+        let prompts = shared_prefix_prompts(10, 2.0);
+
+        let mut counts = [0_u32; 10];
+        for _ in 0..2_000 {
+            counts[prompts.next_prefix().prefix_index as usize] += 1;
+        }
+        assert!(counts[0] > counts[1], "counts were {counts:?}");
+        assert!(counts[1] > counts[9], "counts were {counts:?}");
+    }
+
     #[test]
     fn sample_tokens_returns_empty_for_zero_tokens() {
         let corpus: Vec<u32> = (0..100).collect();
@@ -0,0 +1,481 @@
+use crate::args::ReportArgs;
+use crate::benchmark::{BenchmarkResult, ConversationTurn};
+use crate::compare::{load_records, load_summary, resolve_run_dir};
+use crate::error::{ErrorKind, RequestFailure};
+use crate::html::render_html;
+use crate::output::{
+    BenchmarkConfig, RequestRecord, SummaryInputConfig, SummaryWindow, default_results_dir,
+    print_summary_to_stdout, summarize_results, write_summary,
+};
+use crate::prompt::PrefixUse;
+use crate::slo::{Slo, SloMetric};
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The part of a run that a report covers, after `--skip-first` and `--skip-last`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ReportWindow {
+    start_unix_ns: u64,
+    end_unix_ns: u64,
+    duration: Duration,
+}
+
+impl ReportWindow {
+    fn new(
+        start_unix_ns: u64,
+        end_unix_ns: u64,
+        duration: Duration,
+        skip_first: Option<Duration>,
+        skip_last: Option<Duration>,
+    ) -> Result<Self> {
+        let skip_first = skip_first.unwrap_or_default();
+        let skip_last = skip_last.unwrap_or_default();
+        let Some(duration) = duration
+            .checked_sub(skip_first + skip_last)
+            .filter(|remaining| !remaining.is_zero())
+        else {
+            bail!(
+                "--skip-first and --skip-last leave nothing of the {:.1}s run",
+                duration.as_secs_f64()
+            );
+        };
+
+        Ok(Self {
+            start_unix_ns: start_unix_ns.saturating_add(skip_first.as_nanos() as u64),
+            end_unix_ns: end_unix_ns.saturating_sub(skip_last.as_nanos() as u64),
+            duration,
+        })
+    }
+
+    /// A failure is only filtered on the timestamps it has. Requests abandoned at the run
+    /// deadline have no start, and error records from older runs have neither.
+    fn contains(&self, result: &Result<BenchmarkResult, RequestFailure>) -> bool {
+        let (start_unix_ns, end_unix_ns) = match result {
+            Ok(br) => (Some(br.request_start_unix_ns), Some(br.request_end_unix_ns)),
+            Err(failure) => (failure.request_start_unix_ns, failure.request_end_unix_ns),
+        };
+        start_unix_ns.is_none_or(|start| start >= self.start_unix_ns)
+            && end_unix_ns.is_none_or(|end| end <= self.end_unix_ns)
+    }
+}
+
+pub fn run_report(args: &ReportArgs) -> Result<()> {
+    let dir = resolve_run_dir(&args.run, &default_results_dir()?)?;
+    let saved = load_summary(&dir)?;
+
+    let all_results = profiling_results(&load_records(&dir)?)?;
+
+    let window = ReportWindow::new(
+        saved.start_time_unix_ns,
+        saved.end_time_unix_ns,
+        Duration::from_secs_f64(saved.benchmark_duration.avg.unwrap_or_default()),
+        args.skip_first,
+        args.skip_last,
+    )?;
+    let num_recorded = all_results.len();
+    let results: Vec<_> = all_results
+        .into_iter()
+        .filter(|result| window.contains(result))
+        .collect();
+
+    let slos = saved_slos(&saved.input_config)?;
+    let config = saved_config(&saved.input_config, &slos);
+    let mut summary = summarize_results(
+        &saved.benchmark_id,
+        &config,
+        &results,
+        window.duration,
+        window.start_unix_ns,
+        window.end_unix_ns,
+    );
+    summary.verdict = saved.verdict.clone();
+    if args.skip_first.is_some() || args.skip_last.is_some() {
+        summary.window = Some(SummaryWindow {
+            skip_first_s: args.skip_first.unwrap_or_default().as_secs_f64(),
+            skip_last_s: args.skip_last.unwrap_or_default().as_secs_f64(),
+        });
+    }
+
+    let output_path = report_output_path(&dir, args.output.as_deref())?;
+    write_summary(&output_path, &summary)
+        .with_context(|| format!("Failed to write '{}'", output_path.display()))?;
+    let html_path = output_path.with_extension("html");
+    if args.html {
+        fs::write(&html_path, render_html(&summary, &results))
+            .with_context(|| format!("Failed to write '{}'", html_path.display()))?;
+    }
+
+    println!(
+        "{} ({}): {}",
+        summary.benchmark_id,
+        summary.input_config.model,
+        dir.display()
+    );
+    if results.len() < num_recorded {
+        println!(
+            "Kept {} of {} requests in a {:.1}s window",
+            results.len(),
+            num_recorded,
+            window.duration.as_secs_f64()
+        );
+    }
+
+    print_results(&results, window.duration, &slos, &args.percentiles);
+
+    println!();
+    println!("Wrote {}", output_path.display());
+    if args.html {
+        println!("Wrote {}", html_path.display());
+    }
+    Ok(())
+}
+
+/// Where the report summary goes: `--output`, or `report_summary.json` in the run directory.
+///
+/// The run's own `summary.json` is never replaced, so later reports window the original run.
+fn report_output_path(dir: &Path, output: Option<&Path>) -> Result<PathBuf> {
+    let Some(output) = output else {
+        return Ok(dir.join("report_summary.json"));
+    };
+    let run_summary = dir.join("summary.json");
+    let same_file = match (fs::canonicalize(output), fs::canonicalize(&run_summary)) {
+        (Ok(output), Ok(run_summary)) => output == run_summary,
+        _ => false,
+    };
+    if same_file {
+        bail!(
+            "--output would overwrite the run's own '{}'",
+            run_summary.display()
+        );
+    }
+    Ok(output.to_path_buf())
+}
+
+/// Prints the same stdout summary as a live run for results rebuilt from saved records.
+pub fn print_results(
+    results: &[Result<BenchmarkResult, RequestFailure>],
+    benchmark_duration: Duration,
+    slos: &[Slo],
+    percentiles: &[f64],
+) {
+    let successful_results: Vec<BenchmarkResult> = results.iter().flatten().cloned().collect();
+    let failures: Vec<RequestFailure> = results
+        .iter()
+        .filter_map(|result| result.as_ref().err().cloned())
+        .collect();
+    print_summary_to_stdout(
+        &successful_results,
+        &failures,
+        successful_results
+            .iter()
+            .map(|br| br.output_tokens as u64)
+            .sum(),
+        successful_results
+            .iter()
+            .map(|br| br.reasoning_tokens as u64)
+            .sum(),
+        benchmark_duration,
+        slos,
+        percentiles,
+    );
+}
+
+/// Rebuilds the profiling results, in request order, from `individual_responses.jsonl`.
+pub fn profiling_results(
+    records: &[RequestRecord],
+) -> Result<Vec<Result<BenchmarkResult, RequestFailure>>> {
+    records
+        .iter()
+        .filter(|record| record.metadata.benchmark_phase == "profiling")
+        .map(|record| {
+            result_from_record(record)
+                .with_context(|| format!("request {}", record.metadata.request_index))
+        })
+        .collect()
+}
+
+/// Inverts `request_record`. Streamed text is not saved, so it comes back empty.
+fn result_from_record(record: &RequestRecord) -> Result<Result<BenchmarkResult, RequestFailure>> {
+    let attempts = record.metadata.attempts.unwrap_or(1);
+    if let Some(error) = &record.error {
+        return Ok(Err(RequestFailure {
+            status: u16::try_from(error.code)
+                .ok()
+                .filter(|code| (100..=599).contains(code)),
+            attempts,
+            request_start_unix_ns: record.metadata.request_start_ns,
+            request_end_unix_ns: record.metadata.request_end_ns,
+            ..RequestFailure::new(ErrorKind::parse(&error.error_type), &error.message)
+        }));
+    }
+
+    let value = |name: &str| {
+        record
+            .metrics
+            .get(name)
+            .and_then(|metric| metric.value.as_f64())
+    };
+    let required = |name: &str| value(name).with_context(|| format!("missing metric '{name}'"));
+    let millis = |ms: f64| {
+        Duration::try_from_secs_f64(ms / 1000.0).with_context(|| format!("invalid duration {ms}ms"))
+    };
+
+    let metadata = &record.metadata;
+    let input_tokens = required("input_sequence_length")? as u32;
+    let output_tokens = required("output_token_count")? as u32;
+    let reasoning_tokens = value("reasoning_token_count").unwrap_or_default() as u32;
+    let inter_event_gaps_s = record
+        .inter_event_gaps_ms
+        .iter()
+        .map(|ms| ms / 1000.0)
+        .collect();
+
+    Ok(Ok(BenchmarkResult {
+        ttft: millis(required("time_to_first_token")?)?,
+        ttfo: value("time_to_first_output_token")
+            .map(millis)
+            .transpose()?,
+        total_latency: millis(required("request_latency")?)?,
+        throughput: required("output_token_throughput_per_request")?,
+        input_tokens,
+        output_tokens,
+        reasoning_tokens,
+        inter_token_latency_s: required("inter_token_latency")? / 1000.0,
+        inter_event_latency_s: required("inter_event_latency")? / 1000.0,
+        inter_event_gaps_s,
+        // Usage totals are not recorded; providers report input plus all output tokens.
+        total_tokens: input_tokens + output_tokens + reasoning_tokens,
+        request_start_unix_ns: metadata
+            .request_start_ns
+            .context("missing request_start_ns")?,
+        request_end_unix_ns: metadata.request_end_ns.context("missing request_end_ns")?,
+        scheduled_start_unix_ns: metadata.request_scheduled_ns,
+        conversation: metadata.session_index.zip(metadata.turn_index).map(
+            |(session_index, turn_index)| ConversationTurn {
+                session_index,
+                turn_index,
+            },
+        ),
+        prefix: metadata
+            .prefix_index
+            .zip(metadata.prefix_cache_hit_likely)
+            .map(|(prefix_index, cache_hit_likely)| PrefixUse {
+                prefix_index,
+                cache_hit_likely,
+            }),
+        requested_output_tokens: value("requested_output_tokens").map(|tokens| tokens as u32),
+        finish_reason: metadata.finish_reason.clone(),
+        attempts,
+        generated_text: String::new(),
+    }))
+}
+
+pub fn saved_slos(input_config: &SummaryInputConfig) -> Result<Vec<Slo>> {
+    let Some(slos) = &input_config.slos else {
+        return Ok(Vec::new());
+    };
+    slos.iter()
+        .map(|(name, threshold_ms)| {
+            let metric = SloMetric::parse(name)
+                .with_context(|| format!("unknown SLO metric '{name}' in summary"))?;
+            Ok(Slo {
+                metric,
+                threshold: Duration::try_from_secs_f64(threshold_ms / 1000.0)
+                    .with_context(|| format!("invalid SLO threshold {threshold_ms}ms"))?,
+            })
+        })
+        .collect()
+}
+
+/// The config the run was recorded with, so the new summary keeps its input config and slug.
+fn saved_config<'a>(input_config: &'a SummaryInputConfig, slos: &'a [Slo]) -> BenchmarkConfig<'a> {
+    BenchmarkConfig {
+        model: &input_config.model,
+        tokenizer: &input_config.tokenizer,
+        mean_input_tokens: input_config.mean_input_tokens,
+        stddev_input_tokens: input_config.stddev_input_tokens,
+        mean_output_tokens: input_config.mean_output_tokens,
+        stddev_output_tokens: input_config.stddev_output_tokens.unwrap_or_default(),
+        num_concurrent_requests: input_config.num_concurrent_requests,
+        request_rate: input_config.request_rate,
+        arrival_distribution: input_config.arrival_distribution.as_deref(),
+        warmup_requests: input_config.warmup_requests.unwrap_or_default(),
+        duration: input_config.duration_s.map(Duration::from_secs_f64),
+        dataset: input_config.dataset.as_deref().map(Path::new),
+        num_turns: input_config.num_turns.unwrap_or(1),
+        prefix_tokens: input_config.prefix_tokens,
+        num_prefixes: input_config.num_prefixes.unwrap_or_default(),
+        prefix_skew: input_config.prefix_skew.unwrap_or_default(),
+        slos,
+        extra_body: input_config.extra_body.as_ref(),
+        max_retries: input_config.max_retries.unwrap_or_default(),
+        gate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare::{load_summary_file, parse_records};
+    use crate::output::test_config;
+
+    const RECORDS: &str = r#"{"metadata":{"request_index":0,"benchmark_phase":"warmup","request_start_ns":1,"request_end_ns":2},"metrics":{}}
+{"metadata":{"request_index":0,"benchmark_phase":"profiling","request_start_ns":1000000000,"request_end_ns":1900000000,"session_index":2,"turn_index":1,"prefix_index":0,"prefix_cache_hit_likely":true},"metrics":{"time_to_first_token":{"value":100.0,"unit":"ms"},"time_to_first_output_token":{"value":120.0,"unit":"ms"},"request_latency":{"value":900.0,"unit":"ms"},"inter_token_latency":{"value":10.0,"unit":"ms"},"inter_event_latency":{"value":12.0,"unit":"ms"},"max_stall":{"value":40.0,"unit":"ms"},"output_token_throughput_per_request":{"value":50.0,"unit":"tokens/sec/request"},"input_sequence_length":{"value":550,"unit":"tokens"},"output_token_count":{"value":40,"unit":"tokens"},"reasoning_token_count":{"value":5,"unit":"tokens"}},"inter_event_gaps_ms":[5.0,40.0]}
+{"metadata":{"request_index":1,"benchmark_phase":"profiling"},"metrics":{},"error":{"code":1,"type":"RequestError","message":"boom"}}
+{"metadata":{"request_index":2,"benchmark_phase":"profiling","request_start_ns":500000000,"request_end_ns":1500000000,"attempts":3},"metrics":{},"error":{"code":429,"type":"rate_limit","message":"HTTP 429 Too Many Requests: slow down"}}
+"#;
+
+    #[test]
+    fn rebuilds_profiling_results_from_records() {
+        let results = profiling_results(&parse_records(RECORDS).unwrap()).unwrap();
+        assert_eq!(results.len(), 3);
+
+        let br = results[0].as_ref().unwrap();
+        assert_eq!(br.ttft, Duration::from_millis(100));
+        assert_eq!(br.ttfo, Some(Duration::from_millis(120)));
+        assert_eq!(br.total_latency, Duration::from_millis(900));
+        assert_eq!(br.inter_token_latency_s, 0.01);
+        assert_eq!(br.inter_event_gaps_s, vec![0.005, 0.04]);
+        assert_eq!(br.max_stall_s(), Some(0.04));
+        assert_eq!(br.total_tokens, 595);
+        assert_eq!(br.request_start_unix_ns, 1_000_000_000);
+        assert_eq!(br.conversation.map(|turn| turn.session_index), Some(2));
+        assert_eq!(br.prefix.map(|prefix| prefix.cache_hit_likely), Some(true));
+
+        assert_eq!(br.attempts, 1);
+
+        // Records from before errors were classified come back as `other` with no status.
+        let legacy = results[1].as_ref().unwrap_err();
+        assert_eq!(legacy.kind, ErrorKind::Other);
+        assert_eq!(legacy.status, None);
+        assert_eq!(legacy.message, "boom");
+
+        let rate_limited = results[2].as_ref().unwrap_err();
+        assert_eq!(rate_limited.kind, ErrorKind::RateLimit);
+        assert_eq!(rate_limited.code(), 429);
+        assert_eq!(rate_limited.request_start_unix_ns, Some(500_000_000));
+        assert_eq!(rate_limited.request_end_unix_ns, Some(1_500_000_000));
+        assert_eq!(rate_limited.attempts, 3);
+    }
+
+    #[test]
+    fn rejects_records_missing_metrics() {
+        let line = r#"{"metadata":{"request_index":0,"benchmark_phase":"profiling"},"metrics":{}}"#;
+        let err = profiling_results(&parse_records(line).unwrap()).unwrap_err();
+        assert!(format!("{err:#}").contains("request 0"));
+    }
+
+    #[test]
+    fn window_drops_requests_outside_the_skipped_edges() {
+        let window = ReportWindow::new(
+            0,
+            10_000_000_000,
+            Duration::from_secs(10),
+            Some(Duration::from_secs(2)),
+            Some(Duration::from_secs(1)),
+        )
+        .unwrap();
+        assert_eq!(window.duration, Duration::from_secs(7));
+
+        let mut results = profiling_results(&parse_records(RECORDS).unwrap()).unwrap();
+        assert!(!window.contains(&results[0]));
+        // Failures are windowed by their own timestamps; older records without any are kept.
+        assert!(window.contains(&results[1]));
+        assert!(!window.contains(&results[2]));
+        if let Err(failure) = &mut results[2] {
+            failure.request_start_unix_ns = Some(3_000_000_000);
+            failure.request_end_unix_ns = Some(4_000_000_000);
+        }
+        assert!(window.contains(&results[2]));
+        // Requests abandoned at the run deadline only have an end time.
+        let abandoned = |end_unix_ns| {
+            Err(RequestFailure {
+                request_end_unix_ns: Some(end_unix_ns),
+                ..RequestFailure::new(ErrorKind::Timeout, "abandoned at run deadline")
+            })
+        };
+        assert!(!window.contains(&abandoned(10_000_000_000)));
+        assert!(window.contains(&abandoned(8_000_000_000)));
+
+        if let Ok(br) = &mut results[0] {
+            br.request_start_unix_ns = 2_000_000_000;
+            br.request_end_unix_ns = 9_000_000_000;
+        }
+        assert!(window.contains(&results[0]));
+
+        assert!(
+            ReportWindow::new(
+                0,
+                0,
+                Duration::from_secs(3),
+                Some(Duration::from_secs(3)),
+                None
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn report_keeps_the_run_summary_and_its_verdict() {
+        use crate::gate::Verdict;
+        use crate::output::write_results_json;
+
+        let config = test_config();
+        let results_dir =
+            std::env::temp_dir().join(format!("llmnop-report-{}", std::process::id()));
+        let written = write_results_json(
+            &results_dir,
+            &config,
+            &[],
+            &profiling_results(&parse_records(RECORDS).unwrap()).unwrap(),
+            &[],
+            Duration::from_secs(10),
+            0,
+            10_000_000_000,
+        )
+        .unwrap();
+        let run_summary = written.dir.join("summary.json");
+        let mut saved = load_summary(&written.dir).unwrap();
+        saved.verdict = Some(Verdict {
+            passed: false,
+            baseline: None,
+            checks: Vec::new(),
+        });
+        write_summary(&run_summary, &saved).unwrap();
+        let original = fs::read_to_string(&run_summary).unwrap();
+
+        let args = ReportArgs {
+            run: written.dir.to_string_lossy().into_owned(),
+            skip_first: Some(Duration::from_secs(2)),
+            skip_last: None,
+            percentiles: vec![50.0],
+            output: None,
+            html: false,
+        };
+        run_report(&args).unwrap();
+        run_report(&args).unwrap();
+
+        assert_eq!(fs::read_to_string(&run_summary).unwrap(), original);
+        let report = load_summary_file(&written.dir.join("report_summary.json")).unwrap();
+        assert_eq!(report.verdict.map(|verdict| verdict.passed), Some(false));
+        assert_eq!(report.start_time_unix_ns, 2_000_000_000);
+        assert_eq!(
+            report.window,
+            Some(SummaryWindow {
+                skip_first_s: 2.0,
+                skip_last_s: 0.0
+            })
+        );
+        assert_eq!(report.benchmark_duration.avg, Some(8.0));
+
+        let overwrite = ReportArgs {
+            output: Some(run_summary.clone()),
+            ..args
+        };
+        assert!(run_report(&overwrite).is_err());
+
+        fs::remove_dir_all(&results_dir).unwrap();
+    }
+}
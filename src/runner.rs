@@ -0,0 +1,1157 @@
+use crate::args::{ApiType, BenchmarkArgs, OutputFormat};
+use crate::benchmark::{
+    BenchmarkRequest, BenchmarkResult, ConversationTurn, RequestTimeouts, run_benchmark,
+};
+use crate::client::{AnthropicClient, ApiClients, ChatMessage, OpenAiClient};
+use crate::dataset::{InputTokenRange, load_dataset};
+use crate::error::{ErrorKind, RequestFailure};
+use crate::gate::Gate;
+use crate::output::{BenchmarkConfig, DEFAULT_TABLE_PERCENTILES, print_summary_to_stdout};
+use crate::prompt::{Prompt, PromptConfig, PromptSource, SharedPrefixConfig, SharedPrefixPrompts};
+use crate::schedule::ArrivalSchedule;
+use crate::slo::Slo;
+use anyhow::{Context, Result};
+use futures::{StreamExt, stream::FuturesUnordered};
+use indicatif::{ProgressBar, ProgressStyle};
+use rand::prelude::*;
+use rand_distr::Normal;
+use reqwest::header::HeaderMap;
+use std::io::{self, IsTerminal};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio::time;
+
+/// Endpoint, model and tokenizer shared by every run against the same target.
+pub struct BenchmarkTarget {
+    pub client: Arc<ApiClients>,
+    pub model: String,
+    pub tokenizer: String,
+}
+
+impl BenchmarkTarget {
+    /// Missing `--url` or `--model` come back as a `clap::Error`, so callers can exit with usage.
+    pub fn from_args(args: &BenchmarkArgs) -> Result<Self> {
+        let (url, model) = args.require_benchmark_args()?;
+        let model = model.to_string();
+
+        // `--header` values ride along as defaults; headers llmnop sets itself, like auth, win.
+        let headers: HeaderMap = args.headers.iter().cloned().collect();
+        let http = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        let clients = ApiClients {
+            openai: OpenAiClient::new(http.clone(), url, args.api_key.clone()),
+            anthropic: AnthropicClient::new(http, url, args.api_key.clone()),
+            extra_body: args.extra_body.clone().unwrap_or_default(),
+        };
+
+        Ok(Self {
+            client: Arc::new(clients),
+            tokenizer: args.tokenizer.clone().unwrap_or_else(|| model.clone()),
+            model,
+        })
+    }
+}
+
+/// How requests are issued: a closed loop at fixed concurrency or an open loop at a fixed rate.
+#[derive(Debug, Clone, Copy)]
+pub struct LoadProfile {
+    pub num_concurrent_requests: u32,
+    pub request_rate: Option<f64>,
+}
+
+impl LoadProfile {
+    pub fn from_args(args: &BenchmarkArgs) -> Self {
+        Self {
+            num_concurrent_requests: args.num_concurrent_requests,
+            request_rate: args.request_rate,
+        }
+    }
+}
+
+pub struct BenchmarkRun {
+    pub warmup_results: Vec<Result<BenchmarkResult, RequestFailure>>,
+    pub results: Vec<Result<BenchmarkResult, RequestFailure>>,
+    /// Requests that finished during the grace period after a `--duration` window.
+    pub drained_results: Vec<Result<BenchmarkResult, RequestFailure>>,
+    pub start: Instant,
+    pub end: Instant,
+    pub start_unix_ns: u64,
+    pub end_unix_ns: u64,
+}
+
+impl BenchmarkRun {
+    pub fn duration(&self) -> Duration {
+        self.end.duration_since(self.start)
+    }
+
+    pub fn print_summary(&self, slos: &[Slo]) {
+        let mut successful_results = Vec::new();
+        let mut total_output_tokens = 0_u64;
+        let mut total_reasoning_tokens = 0_u64;
+        let failures: Vec<RequestFailure> = self
+            .results
+            .iter()
+            .filter_map(|r| r.as_ref().err().cloned())
+            .collect();
+
+        for br in self.results.iter().flatten() {
+            total_output_tokens += br.output_tokens as u64;
+            total_reasoning_tokens += br.reasoning_tokens as u64;
+            successful_results.push(br.clone());
+        }
+
+        print_summary_to_stdout(
+            &successful_results,
+            &failures,
+            total_output_tokens,
+            total_reasoning_tokens,
+            self.duration(),
+            slos,
+            &DEFAULT_TABLE_PERCENTILES,
+        );
+    }
+}
+
+/// Starts the live metrics endpoint when `--metrics-listen` is set.
+#[cfg(feature = "prometheus")]
+pub async fn start_metrics_listener(args: &BenchmarkArgs) -> Result<()> {
+    if let Some(addr) = args.metrics_listen {
+        let addr = crate::metrics::listen(addr).await?;
+        if args.effective_output_format() != OutputFormat::None {
+            eprintln!("Serving metrics at http://{addr}/metrics");
+        }
+    }
+    Ok(())
+}
+
+/// Loads the dataset when one is given, otherwise samples synthetic prompts from the corpus.
+pub fn prompt_source(args: &BenchmarkArgs, target: &BenchmarkTarget) -> Result<PromptSource> {
+    let synthetic = PromptConfig {
+        mean_input_tokens: args.mean_input_tokens,
+        stddev_input_tokens: args.stddev_input_tokens,
+    };
+
+    if let Some(prefix_tokens) = args.prefix_tokens {
+        let config = SharedPrefixConfig {
+            prefix_tokens,
+            num_prefixes: args.num_prefixes,
+            prefix_skew: args.prefix_skew,
+            suffix: synthetic,
+        };
+        let prompts = SharedPrefixPrompts::new(config, &target.tokenizer)?;
+        return Ok(PromptSource::SharedPrefix(prompts));
+    }
+
+    let Some(path) = &args.dataset else {
+        return Ok(PromptSource::Synthetic(synthetic));
+    };
+
+    let input_tokens = InputTokenRange {
+        min: args.min_input_tokens,
+        max: args.max_input_tokens,
+    };
+    let entries = load_dataset(path, args.dataset_format, input_tokens, &target.tokenizer)?;
+    Ok(PromptSource::Dataset(entries))
+}
+
+pub fn benchmark_config<'a>(
+    args: &'a BenchmarkArgs,
+    target: &'a BenchmarkTarget,
+    load: LoadProfile,
+    gate: Option<&'a Gate>,
+) -> BenchmarkConfig<'a> {
+    BenchmarkConfig {
+        model: &target.model,
+        tokenizer: &target.tokenizer,
+        mean_input_tokens: args.mean_input_tokens,
+        stddev_input_tokens: args.stddev_input_tokens,
+        mean_output_tokens: args.mean_output_tokens,
+        stddev_output_tokens: args.stddev_output_tokens,
+        num_concurrent_requests: load.num_concurrent_requests,
+        request_rate: load.request_rate,
+        arrival_distribution: load
+            .request_rate
+            .map(|_| args.arrival_distribution.as_str()),
+        warmup_requests: args.warmup_requests,
+        duration: args.duration,
+        dataset: args.dataset.as_deref(),
+        num_turns: args.num_turns,
+        prefix_tokens: args.prefix_tokens,
+        num_prefixes: args.num_prefixes,
+        prefix_skew: args.prefix_skew,
+        slos: &args.slos,
+        extra_body: args.extra_body.as_ref(),
+        max_retries: args.max_retries,
+        gate,
+    }
+}
+
+fn unix_time_now_ns() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .and_then(|d| u64::try_from(d.as_nanos()).ok())
+        .unwrap_or_default()
+}
+
+fn sample_max_tokens(mean: u32, stddev: u32) -> u32 {
+    if stddev == 0 {
+        return mean.max(1);
+    }
+
+    let dist = Normal::new(mean as f64, stddev as f64).unwrap();
+    let mut rng = rand::rng();
+
+    loop {
+        let sample = dist.sample(&mut rng);
+        if sample >= 1.0 {
+            return sample.ceil() as u32;
+        }
+    }
+}
+
+/// Prompts generated per batch when a `--duration` run needs more.
+const PROMPT_BATCH_SIZE: usize = 64;
+
+/// When a phase stops sending requests.
+#[derive(Debug, Clone, Copy)]
+enum PhaseLimit {
+    /// A fixed number of requests, cut short by `--timeout`.
+    Requests(u32),
+    /// Requests for a fixed time, then a grace period to drain in-flight requests.
+    Duration {
+        duration: Duration,
+        grace_period: Duration,
+    },
+}
+
+/// Hands out prompts in request order, generating more when an open-ended run uses them up.
+struct PromptQueue<'a> {
+    source: &'a PromptSource,
+    tokenizer: &'a str,
+    prompts: std::vec::IntoIter<Prompt>,
+}
+
+impl<'a> PromptQueue<'a> {
+    fn new(source: &'a PromptSource, tokenizer: &'a str, initial: usize) -> Result<Self> {
+        Ok(Self {
+            source,
+            tokenizer,
+            prompts: source.generate(initial, tokenizer)?.into_iter(),
+        })
+    }
+
+    fn next_prompt(&mut self) -> Result<Prompt> {
+        if let Some(prompt) = self.prompts.next() {
+            return Ok(prompt);
+        }
+        self.prompts = self
+            .source
+            .generate(PROMPT_BATCH_SIZE, self.tokenizer)?
+            .into_iter();
+        self.prompts
+            .next()
+            .context("Prompt source produced no prompts")
+    }
+}
+
+/// Longest wait between attempts, whatever the backoff or `Retry-After` asks for.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// How failed requests are retried; with `--max-retries 0` each request is sent once.
+#[derive(Debug, Clone, Copy)]
+struct RetryPolicy {
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl RetryPolicy {
+    fn from_args(args: &BenchmarkArgs) -> Self {
+        Self {
+            max_retries: args.max_retries,
+            backoff: args.retry_backoff,
+        }
+    }
+
+    /// Exponential backoff after the given attempt, stretched to the server's `Retry-After`.
+    fn delay(&self, attempt: u32, failure: &RequestFailure) -> Duration {
+        let backoff = self
+            .backoff
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        failure
+            .retry_after
+            .map_or(backoff, |retry_after| backoff.max(retry_after))
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+/// Sends a request, retrying retryable failures. Latencies describe the last attempt, while the
+/// start time is the first attempt's, for successes and failures alike.
+async fn run_benchmark_task(
+    client: Arc<ApiClients>,
+    api_type: ApiType,
+    request: BenchmarkRequest,
+    retry: RetryPolicy,
+) -> (BenchmarkRequest, Result<BenchmarkResult, RequestFailure>) {
+    let request_start_unix_ns = unix_time_now_ns();
+    #[cfg(feature = "prometheus")]
+    crate::metrics::request_started();
+    let mut attempts = 0;
+    let result = loop {
+        attempts += 1;
+        match run_benchmark(&client, api_type, &request).await {
+            Ok(mut benchmark_result) => {
+                benchmark_result.attempts = attempts;
+                benchmark_result.request_start_unix_ns = request_start_unix_ns;
+                break Ok(benchmark_result);
+            }
+            Err(failure) if failure.is_retryable() && attempts <= retry.max_retries => {
+                #[cfg(feature = "prometheus")]
+                crate::metrics::request_retried();
+                time::sleep(retry.delay(attempts, &failure)).await;
+            }
+            Err(failure) => {
+                break Err(RequestFailure {
+                    attempts,
+                    request_start_unix_ns: Some(request_start_unix_ns),
+                    request_end_unix_ns: Some(unix_time_now_ns()),
+                    ..failure
+                });
+            }
+        }
+    };
+    // Once per request, not per attempt, and with no await after it so an abort cannot
+    // count the request twice.
+    #[cfg(feature = "prometheus")]
+    crate::metrics::request_finished(&result);
+    (request, result)
+}
+
+/// Aborts every in-flight request and records each one as a timeout, so requests cut off at
+/// a deadline still count as failures. Only the deadline is known, not when each was sent.
+fn abandon_in_flight(
+    in_flight: &FuturesUnordered<
+        JoinHandle<(BenchmarkRequest, Result<BenchmarkResult, RequestFailure>)>,
+    >,
+    results: &mut Vec<Result<BenchmarkResult, RequestFailure>>,
+    message: &str,
+) {
+    let abandoned_unix_ns = unix_time_now_ns();
+    for handle in in_flight.iter() {
+        #[cfg(feature = "prometheus")]
+        if !handle.is_finished() {
+            crate::metrics::request_abandoned();
+        }
+        handle.abort();
+        results.push(Err(RequestFailure {
+            request_end_unix_ns: Some(abandoned_unix_ns),
+            ..RequestFailure::new(ErrorKind::Timeout, message)
+        }));
+    }
+}
+
+/// Assigns the next session index when multi-turn sessions are enabled.
+fn start_session(num_turns: u32, next_session_index: &mut u32) -> Option<ConversationTurn> {
+    if num_turns <= 1 {
+        return None;
+    }
+
+    let conversation = ConversationTurn {
+        session_index: *next_session_index,
+        turn_index: 0,
+    };
+    *next_session_index += 1;
+    Some(conversation)
+}
+
+/// Appends the streamed reply to the session history, or returns `None` once the last turn is done.
+fn follow_up_turn(
+    request: BenchmarkRequest,
+    result: &mut BenchmarkResult,
+    num_turns: u32,
+) -> Option<(Vec<ChatMessage>, ConversationTurn)> {
+    let reply = std::mem::take(&mut result.generated_text);
+    let conversation = request.conversation?;
+    if conversation.turn_index + 1 >= num_turns {
+        return None;
+    }
+
+    let mut messages = request.messages;
+    messages.push(ChatMessage::assistant(reply));
+    Some((
+        messages,
+        ConversationTurn {
+            turn_index: conversation.turn_index + 1,
+            ..conversation
+        },
+    ))
+}
+
+pub async fn run_load(
+    args: &BenchmarkArgs,
+    target: &BenchmarkTarget,
+    prompt_source: &PromptSource,
+    load: LoadProfile,
+) -> Result<BenchmarkRun> {
+    let warmup_results = if args.warmup_requests > 0 {
+        if args.effective_output_format() != OutputFormat::None {
+            eprintln!("Warming up with {} requests...", args.warmup_requests);
+        }
+        let limit = PhaseLimit::Requests(args.warmup_requests);
+        run_phase(args, target, prompt_source, load, limit)
+            .await?
+            .results
+    } else {
+        Vec::new()
+    };
+
+    let limit = match args.duration {
+        Some(duration) => PhaseLimit::Duration {
+            duration,
+            grace_period: args.grace_period,
+        },
+        None => PhaseLimit::Requests(args.max_num_completed_requests),
+    };
+    let mut run = run_phase(args, target, prompt_source, load, limit).await?;
+    run.warmup_results = warmup_results;
+    Ok(run)
+}
+
+async fn run_phase(
+    args: &BenchmarkArgs,
+    target: &BenchmarkTarget,
+    prompt_source: &PromptSource,
+    load: LoadProfile,
+    limit: PhaseLimit,
+) -> Result<BenchmarkRun> {
+    let client = &target.client;
+    let model = &target.model;
+    let tokenizer = &target.tokenizer;
+    let api = args.api;
+    let use_server_token_count = args.use_server_token_count;
+    let retry = RetryPolicy::from_args(args);
+
+    let overall_start = Instant::now();
+    let overall_start_unix_ns = unix_time_now_ns();
+
+    let (num_requests, initial_prompts, stop_after, grace_period) = match limit {
+        PhaseLimit::Requests(num_requests) => (
+            num_requests,
+            num_requests as usize,
+            Duration::from_secs(args.timeout),
+            None,
+        ),
+        PhaseLimit::Duration {
+            duration,
+            grace_period,
+        } => (u32::MAX, PROMPT_BATCH_SIZE, duration, Some(grace_period)),
+    };
+    let mut prompts = PromptQueue::new(prompt_source, tokenizer, initial_prompts)?;
+
+    let mut all_results = Vec::with_capacity(initial_prompts);
+    let mut drained_results = Vec::new();
+
+    let mut in_flight = FuturesUnordered::new();
+    let mut next_request_index = 0;
+
+    let disable_progress =
+        matches!(args.effective_output_format(), OutputFormat::None) || !io::stderr().is_terminal();
+
+    let pb = if disable_progress {
+        ProgressBar::hidden()
+    } else if grace_period.is_some() {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} [{elapsed_precise}] {pos} requests completed")
+                .unwrap(),
+        );
+        pb.enable_steady_tick(Duration::from_millis(100));
+        pb
+    } else {
+        let pb = ProgressBar::new(num_requests as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} ({eta})",
+                )
+                .unwrap()
+                .progress_chars("##-"),
+        );
+        pb.tick();
+        pb
+    };
+
+    // In a fixed-count run this is `--timeout`; in a `--duration` run it closes the
+    // measurement window and starts the grace period.
+    let stop_future = time::sleep(stop_after);
+    let mut dispatch_stopped = false;
+    let mut window_end = None;
+
+    tokio::pin!(stop_future);
+
+    // Each turn of a session sends the full history plus the next prompt as a new user message.
+    let build_request = |prompt: Prompt,
+                         scheduled_start_unix_ns: Option<u64>,
+                         mut messages: Vec<ChatMessage>,
+                         conversation: Option<ConversationTurn>| {
+        messages.push(ChatMessage::user(prompt.text));
+        BenchmarkRequest {
+            model: model.clone(),
+            messages,
+            max_tokens: prompt.output_tokens.or_else(|| {
+                args.mean_output_tokens
+                    .map(|mean| sample_max_tokens(mean, args.stddev_output_tokens))
+            }),
+            tokenizer: tokenizer.clone(),
+            use_server_token_count,
+            exact_output_tokens: args.exact_output_tokens,
+            timeouts: RequestTimeouts::from_args(args),
+            scheduled_start_unix_ns,
+            conversation,
+            prefix: prompt.prefix,
+        }
+    };
+    let num_turns = args.num_turns;
+    let mut next_session_index = 0;
+
+    // Open-loop runs dispatch on a fixed schedule regardless of completions, so queueing
+    // at the server shows up in latency instead of throttling the request rate.
+    let arrival_schedule = load.request_rate.map(|request_rate| ArrivalSchedule {
+        request_rate,
+        distribution: args.arrival_distribution,
+        burstiness: args.burstiness,
+    });
+    let schedule_start = time::Instant::from_std(overall_start);
+    let mut next_send_offset = Duration::ZERO;
+
+    if arrival_schedule.is_none() {
+        while next_request_index < num_requests
+            && in_flight.len() < load.num_concurrent_requests as usize
+        {
+            let conversation = start_session(num_turns, &mut next_session_index);
+            let request = build_request(prompts.next_prompt()?, None, Vec::new(), conversation);
+            in_flight.push(tokio::spawn(run_benchmark_task(
+                client.clone(),
+                api,
+                request,
+                retry,
+            )));
+            next_request_index += 1;
+        }
+    }
+
+    loop {
+        let dispatch_pending =
+            arrival_schedule.is_some() && !dispatch_stopped && next_request_index < num_requests;
+        let drain_deadline = window_end.zip(grace_period).map(|(end, grace)| end + grace);
+
+        tokio::select! {
+            _ = &mut stop_future, if !dispatch_stopped => {
+                dispatch_stopped = true;
+                match grace_period {
+                    Some(grace_period) => {
+                        window_end = Some(time::Instant::now());
+                        if !in_flight.is_empty() && args.effective_output_format() != OutputFormat::None {
+                            pb.suspend(|| {
+                                eprintln!(
+                                    "Duration reached. Waiting up to {:.0}s for {} in-flight requests...",
+                                    grace_period.as_secs_f64(),
+                                    in_flight.len()
+                                )
+                            });
+                        }
+                    }
+                    // The run deadline does not wait for hung requests; per-request timeouts
+                    // are `--ttft-timeout`, `--request-timeout` and `--idle-timeout`.
+                    None => {
+                        eprintln!(
+                            "\nTimeout reached after {} seconds. Abandoning {} in-flight requests.",
+                            args.timeout,
+                            in_flight.len()
+                        );
+                        abandon_in_flight(&in_flight, &mut all_results, "abandoned at run deadline");
+                        break;
+                    }
+                }
+            }
+
+            _ = time::sleep_until(drain_deadline.unwrap_or(schedule_start)), if drain_deadline.is_some() => {
+                if args.effective_output_format() != OutputFormat::None {
+                    pb.suspend(|| {
+                        eprintln!(
+                            "Grace period ended. Abandoning {} in-flight requests.",
+                            in_flight.len()
+                        )
+                    });
+                }
+                abandon_in_flight(&in_flight, &mut drained_results, "abandoned after grace period");
+                break;
+            }
+
+            _ = time::sleep_until(schedule_start + next_send_offset), if dispatch_pending => {
+                let scheduled_start_unix_ns =
+                    overall_start_unix_ns + next_send_offset.as_nanos() as u64;
+                let conversation = start_session(num_turns, &mut next_session_index);
+                let request = build_request(
+                    prompts.next_prompt()?,
+                    Some(scheduled_start_unix_ns),
+                    Vec::new(),
+                    conversation,
+                );
+                in_flight.push(tokio::spawn(run_benchmark_task(client.clone(), api, request, retry)));
+                next_request_index += 1;
+
+                if let Some(schedule) = &arrival_schedule {
+                    next_send_offset += schedule.sample_interval();
+                }
+            }
+
+            Some(done) = in_flight.next(), if !in_flight.is_empty() => {
+                // Only requests that finish inside a `--duration` window count toward the run.
+                let results = if window_end.is_some() {
+                    &mut drained_results
+                } else {
+                    &mut all_results
+                };
+                let mut follow_up = None;
+                match done {
+                    Ok((request, Ok(mut benchmark_result))) => {
+                        follow_up = follow_up_turn(request, &mut benchmark_result, num_turns);
+                        results.push(Ok(benchmark_result));
+                    }
+                    Ok((_, Err(failure))) => {
+                        if failure.attempts > 1 {
+                            eprintln!("Request failed after {} attempts: {failure}", failure.attempts);
+                        } else {
+                            eprintln!("Request failed: {failure}");
+                        }
+                        results.push(Err(failure));
+                    }
+                    Err(tokio_err) => {
+                        eprintln!("Tokio Join Error: {:?}", tokio_err);
+                        results.push(Err(RequestFailure::new(
+                            ErrorKind::Other,
+                            format!("Tokio Join Error: {tokio_err}"),
+                        )));
+                    }
+                }
+
+                pb.inc(1);
+
+                // A session's next turn goes out as soon as its reply lands; otherwise a
+                // closed loop refills the slot with a new session.
+                if !dispatch_stopped && next_request_index < num_requests {
+                    let request = match follow_up {
+                        Some((messages, conversation)) => Some(build_request(
+                            prompts.next_prompt()?,
+                            None,
+                            messages,
+                            Some(conversation),
+                        )),
+                        None if arrival_schedule.is_none() => {
+                            let conversation = start_session(num_turns, &mut next_session_index);
+                            Some(build_request(prompts.next_prompt()?, None, Vec::new(), conversation))
+                        }
+                        None => None,
+                    };
+
+                    if let Some(request) = request {
+                        in_flight.push(tokio::spawn(run_benchmark_task(
+                            client.clone(),
+                            api,
+                            request,
+                            retry,
+                        )));
+                        next_request_index += 1;
+                    }
+                }
+            }
+
+            _ = async {}, if in_flight.is_empty() && !dispatch_pending => {
+                break;
+            }
+        }
+
+        if all_results.len() >= num_requests as usize {
+            break;
+        }
+    }
+
+    pb.finish_and_clear();
+
+    // A `--duration` run is measured over its window, not the grace period after it.
+    let overall_end = window_end.map_or_else(Instant::now, time::Instant::into_std);
+    let overall_end_unix_ns =
+        overall_start_unix_ns + overall_end.duration_since(overall_start).as_nanos() as u64;
+    if dispatch_stopped && grace_period.is_none() {
+        eprintln!(
+            "Benchmark terminated due to timeout after {} seconds.",
+            args.timeout
+        );
+    }
+    if !drained_results.is_empty() && args.effective_output_format() != OutputFormat::None {
+        eprintln!(
+            "Excluded {} requests that finished or were abandoned after the measurement window.",
+            drained_results.len()
+        );
+    }
+
+    Ok(BenchmarkRun {
+        warmup_results: Vec::new(),
+        results: all_results,
+        drained_results,
+        start: overall_start,
+        end: overall_end,
+        start_unix_ns: overall_start_unix_ns,
+        end_unix_ns: overall_end_unix_ns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{Args, Command};
+    use crate::dataset::DatasetEntry;
+    use crate::mock::{self, MockConfig};
+    use crate::output::{summarize_results, test_config};
+    use clap::Parser;
+    use std::time::Duration;
+
+    fn request(conversation: Option<ConversationTurn>) -> BenchmarkRequest {
+        BenchmarkRequest {
+            model: "test-model".to_string(),
+            messages: vec![ChatMessage::user("hello")],
+            max_tokens: None,
+            tokenizer: "test-tokenizer".to_string(),
+            use_server_token_count: false,
+            exact_output_tokens: false,
+            timeouts: RequestTimeouts::default(),
+            scheduled_start_unix_ns: None,
+            conversation,
+            prefix: None,
+        }
+    }
+
+    fn result(generated_text: &str) -> BenchmarkResult {
+        BenchmarkResult {
+            ttft: Duration::from_millis(100),
+            ttfo: None,
+            total_latency: Duration::from_millis(500),
+            throughput: 50.0,
+            input_tokens: 10,
+            output_tokens: 20,
+            reasoning_tokens: 0,
+            inter_token_latency_s: 0.01,
+            inter_event_latency_s: 0.01,
+            inter_event_gaps_s: Vec::new(),
+            total_tokens: 30,
+            request_start_unix_ns: 0,
+            request_end_unix_ns: 0,
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: None,
+            requested_output_tokens: None,
+            finish_reason: None,
+            attempts: 1,
+            generated_text: generated_text.to_string(),
+        }
+    }
+
+    #[test]
+    fn prompt_queue_generates_more_prompts_when_exhausted() {
+        let source = PromptSource::Dataset(vec![DatasetEntry {
+            prompt: "hello".to_string(),
+            output_tokens: Some(8),
+        }]);
+        let mut prompts = PromptQueue::new(&source, "test-tokenizer", 1).unwrap();
+
+        for _ in 0..PROMPT_BATCH_SIZE + 2 {
+            let prompt = prompts.next_prompt().unwrap();
+            assert_eq!(prompt.text, "hello");
+            assert_eq!(prompt.output_tokens, Some(8));
+        }
+    }
+
+    /// Runs a benchmark against the mock server started with `mock_flags`.
+    async fn run_against_mock(mock_flags: &[&str], benchmark_flags: &[&str]) -> BenchmarkRun {
+        let mock_args = Args::try_parse_from(
+            ["llmnop", "mock-server", "--listen", "127.0.0.1:0"]
+                .iter()
+                .chain(mock_flags),
+        )
+        .unwrap();
+        let Some(Command::MockServer(mock_args)) = mock_args.command else {
+            panic!("expected mock-server command");
+        };
+        let addr = mock::start(mock_args.listen, MockConfig::from_args(&mock_args))
+            .await
+            .unwrap();
+
+        let url = format!("http://{addr}/v1");
+        let base = [
+            "llmnop",
+            "--url",
+            url.as_str(),
+            "--model",
+            "mock",
+            "--use-server-token-count",
+            "--num-concurrent-requests",
+            "2",
+            "--quiet",
+        ];
+        let args = Args::try_parse_from(base.iter().chain(benchmark_flags)).unwrap();
+        let target = BenchmarkTarget::from_args(&args.benchmark).unwrap();
+        let prompts = PromptSource::Dataset(vec![DatasetEntry {
+            prompt: "one two three".to_string(),
+            output_tokens: Some(5),
+        }]);
+        let load = LoadProfile::from_args(&args.benchmark);
+        run_load(&args.benchmark, &target, &prompts, load)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn target_reports_missing_url_as_a_usage_error() {
+        let args = Args::try_parse_from(["llmnop", "--model", "mock"]).unwrap();
+        let err = BenchmarkTarget::from_args(&args.benchmark)
+            .err()
+            .expect("missing --url");
+        assert!(err.downcast_ref::<clap::Error>().is_some());
+    }
+
+    #[tokio::test]
+    async fn chat_run_against_mock_server_measures_configured_delays() {
+        let run = run_against_mock(
+            &["--ttft", "50ms", "--itl", "10ms"],
+            &["--max-num-completed-requests", "4"],
+        )
+        .await;
+
+        assert_eq!(run.results.len(), 4);
+        for br in run.results.iter().map(|r| r.as_ref().unwrap()) {
+            assert_eq!(br.input_tokens, 3);
+            assert_eq!(br.output_tokens, 5);
+            assert_eq!(br.reasoning_tokens, 0);
+            assert!(br.ttft >= Duration::from_millis(50), "ttft {:?}", br.ttft);
+            assert!(br.ttft < Duration::from_secs(2), "ttft {:?}", br.ttft);
+            assert!(br.inter_token_latency_s >= 0.009);
+            assert!(br.total_latency >= Duration::from_millis(90));
+        }
+
+        let config = BenchmarkConfig {
+            model: "mock",
+            tokenizer: "mock",
+            mean_input_tokens: 3,
+            mean_output_tokens: Some(5),
+            num_concurrent_requests: 2,
+            num_prefixes: 0,
+            ..test_config()
+        };
+        let summary = summarize_results(
+            "mock",
+            &config,
+            &run.results,
+            run.duration(),
+            run.start_unix_ns,
+            run.end_unix_ns,
+        );
+        assert_eq!(summary.successful_request_count.avg, Some(4.0));
+        assert_eq!(summary.error_request_count.avg, Some(0.0));
+        assert!(summary.time_to_first_token.min.unwrap() >= 50.0);
+    }
+
+    #[tokio::test]
+    async fn responses_run_against_mock_server_counts_reasoning_tokens() {
+        let run = run_against_mock(
+            &["--ttft", "0", "--itl", "1ms", "--reasoning-tokens", "3"],
+            &[
+                "--api",
+                "responses",
+                "--warmup-requests",
+                "1",
+                "--max-num-completed-requests",
+                "4",
+            ],
+        )
+        .await;
+
+        assert_eq!(run.warmup_results.len(), 1);
+        assert_eq!(run.results.len(), 4);
+        for br in run.results.iter().map(|r| r.as_ref().unwrap()) {
+            assert_eq!(br.reasoning_tokens, 3);
+            assert_eq!(br.output_tokens, 5);
+            assert_eq!(br.total_tokens, 11);
+            assert!(br.ttfo.unwrap() > br.ttft);
+            // The mock stops at the dataset's limit, so the stream ends with `response.incomplete`.
+            assert_eq!(br.finish_reason.as_deref(), Some("length"));
+        }
+    }
+
+    #[tokio::test]
+    async fn exact_output_tokens_closes_the_gap_to_the_requested_length() {
+        let mock = ["--ttft", "0", "--itl", "0", "--output-tokens", "3"];
+        let early = run_against_mock(&mock, &["--max-num-completed-requests", "2"]).await;
+        let exact = run_against_mock(
+            &mock,
+            &["--max-num-completed-requests", "2", "--exact-output-tokens"],
+        )
+        .await;
+
+        for (run, output_tokens, deviation, finish_reason) in
+            [(early, 3, -2, "stop"), (exact, 5, 0, "length")]
+        {
+            for br in run.results.iter().map(|r| r.as_ref().unwrap()) {
+                assert_eq!(br.output_tokens, output_tokens);
+                assert_eq!(br.requested_output_tokens, Some(5));
+                assert_eq!(br.output_length_deviation(), Some(deviation));
+                assert_eq!(br.finish_reason.as_deref(), Some(finish_reason));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn injected_errors_and_missing_usage_are_recorded_as_failures() {
+        let run = run_against_mock(&["--ttft", "0", "--itl", "0", "--error-rate", "1"], &[]).await;
+        assert_eq!(run.results.len(), 10);
+        for failure in run.results.iter().map(|r| r.as_ref().unwrap_err()) {
+            assert_eq!(failure.kind, ErrorKind::HttpStatus);
+            assert_eq!(failure.code(), 500);
+            assert_eq!(failure.attempts, 1);
+        }
+
+        let run = run_against_mock(&["--ttft", "0", "--itl", "0", "--no-usage"], &[]).await;
+        let error = run.results[0].as_ref().unwrap_err();
+        assert!(
+            error.message.contains("did not return token usage"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limits_and_records_attempts() {
+        let mock_flags = [
+            "--ttft",
+            "0",
+            "--itl",
+            "0",
+            "--error-status",
+            "429",
+            "--error-rate",
+        ];
+        let retry_flags = ["--max-retries", "2", "--retry-backoff", "0"];
+
+        let run = run_against_mock(&[&mock_flags[..], &["1"]].concat(), &retry_flags).await;
+        for failure in run.results.iter().map(|r| r.as_ref().unwrap_err()) {
+            assert_eq!(failure.kind, ErrorKind::RateLimit);
+            assert_eq!(failure.code(), 429);
+            assert_eq!(failure.attempts, 3);
+        }
+
+        // Half the attempts fail, so with enough retries every request eventually succeeds.
+        let run = run_against_mock(
+            &[&mock_flags[..], &["0.5", "--seed", "7"]].concat(),
+            &["--max-retries", "40", "--retry-backoff", "0"],
+        )
+        .await;
+        let attempts: Vec<u32> = run
+            .results
+            .iter()
+            .map(|r| r.as_ref().unwrap().attempts)
+            .collect();
+        assert_eq!(attempts.len(), 10);
+        assert!(
+            attempts.iter().any(|&attempts| attempts > 1),
+            "{attempts:?}"
+        );
+        // A retried request's start covers its failed attempts, like a failure's does.
+        for br in run.results.iter().map(|r| r.as_ref().unwrap()) {
+            let elapsed = br.request_end_unix_ns - br.request_start_unix_ns;
+            assert!(br.first_token_unix_ns() >= br.request_start_unix_ns);
+            if br.attempts > 1 {
+                assert!(elapsed as u128 >= br.total_latency.as_nanos(), "{br:?}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn per_request_timeouts_cancel_slow_streams() {
+        for (mock_flags, timeout_flag, message) in [
+            (
+                ["--ttft", "2s", "--itl", "0"],
+                "--ttft-timeout",
+                "no first token within 50ms",
+            ),
+            (
+                ["--ttft", "0", "--itl", "2s"],
+                "--idle-timeout",
+                "no stream event within 50ms",
+            ),
+            (
+                ["--ttft", "0", "--itl", "20ms"],
+                "--request-timeout",
+                "request did not finish within 50ms",
+            ),
+        ] {
+            let run = run_against_mock(&mock_flags, &[timeout_flag, "50ms"]).await;
+            assert_eq!(run.results.len(), 10);
+            for failure in run.results.iter().map(|r| r.as_ref().unwrap_err()) {
+                assert_eq!(failure.kind, ErrorKind::Timeout);
+                assert_eq!(failure.message, message);
+            }
+        }
+
+        // Generous limits leave healthy streams alone.
+        let run = run_against_mock(
+            &["--ttft", "0", "--itl", "0"],
+            &[
+                "--ttft-timeout",
+                "5s",
+                "--request-timeout",
+                "10s",
+                "--idle-timeout",
+                "5s",
+            ],
+        )
+        .await;
+        assert!(run.results.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn run_deadline_records_abandoned_requests_as_timeouts() {
+        let run = run_against_mock(&["--ttft", "10s", "--itl", "0"], &["--timeout", "1"]).await;
+        assert_eq!(run.results.len(), 2);
+        for failure in run.results.iter().map(|r| r.as_ref().unwrap_err()) {
+            assert_eq!(failure.kind, ErrorKind::Timeout);
+            assert_eq!(failure.message, "abandoned at run deadline");
+            assert_eq!(failure.request_start_unix_ns, None);
+            assert!(
+                failure
+                    .request_end_unix_ns
+                    .is_some_and(|end| end >= run.start_unix_ns)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn headers_and_extra_body_reach_the_server() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let capture = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0_u8; 4096];
+            while !String::from_utf8_lossy(&request).contains("\"ignore_eos\"") {
+                let n = stream.read(&mut buf).await.unwrap();
+                assert!(n > 0, "connection closed before the body arrived");
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request).to_lowercase()
+        });
+
+        let args = Args::try_parse_from([
+            "llmnop",
+            "--url",
+            url.as_str(),
+            "--model",
+            "mock",
+            "--header",
+            "X-Tenant: team-a",
+            "--extra-body",
+            r#"{"ignore_eos":true,"temperature":0}"#,
+        ])
+        .unwrap();
+        let target = BenchmarkTarget::from_args(&args.benchmark).unwrap();
+        let request = request(None);
+        let result = run_benchmark(&target.client, ApiType::Chat, &request).await;
+        assert!(result.is_err());
+
+        let request = capture.await.unwrap();
+        assert!(request.contains("x-tenant: team-a"), "{request}");
+        assert!(request.contains("\"temperature\":0"), "{request}");
+    }
+
+    #[tokio::test]
+    async fn duration_run_drains_requests_after_the_window() {
+        // Each request streams 5 tokens 100ms apart, so both slots are busy when the window closes.
+        let mock_flags = ["--ttft", "0", "--itl", "100ms"];
+        let run =
+            run_against_mock(&mock_flags, &["--duration", "1s", "--grace-period", "5s"]).await;
+
+        assert!(run.results.len() >= 2);
+        assert_eq!(run.drained_results.len(), 2);
+        assert!(run.drained_results.iter().all(Result::is_ok));
+        assert!(run.duration() >= Duration::from_secs(1));
+        assert!(run.duration() < Duration::from_millis(1400));
+
+        let run =
+            run_against_mock(&mock_flags, &["--duration", "1s", "--grace-period", "50ms"]).await;
+        // Requests cut off by the grace period are drain records too, as timeouts.
+        assert_eq!(run.drained_results.len(), 2);
+        for failure in run.drained_results.iter().map(|r| r.as_ref().unwrap_err()) {
+            assert_eq!(failure.kind, ErrorKind::Timeout);
+            assert_eq!(failure.message, "abandoned after grace period");
+        }
+    }
+
+    #[test]
+    fn start_session_is_disabled_for_single_turn_runs() {
+        let mut next_session_index = 0;
+        assert_eq!(start_session(1, &mut next_session_index), None);
+        assert_eq!(next_session_index, 0);
+
+        let first = start_session(3, &mut next_session_index).unwrap();
+        let second = start_session(3, &mut next_session_index).unwrap();
+        assert_eq!((first.session_index, first.turn_index), (0, 0));
+        assert_eq!((second.session_index, second.turn_index), (1, 0));
+    }
+
+    #[test]
+    fn follow_up_turn_appends_reply_to_history() {
+        let conversation = ConversationTurn {
+            session_index: 2,
+            turn_index: 0,
+        };
+        let mut benchmark_result = result("hi there");
+
+        let (messages, next) =
+            follow_up_turn(request(Some(conversation)), &mut benchmark_result, 3).unwrap();
+
+        assert_eq!(
+            messages,
+            vec![
+                ChatMessage::user("hello"),
+                ChatMessage::assistant("hi there")
+            ]
+        );
+        assert_eq!(next.session_index, 2);
+        assert_eq!(next.turn_index, 1);
+        assert!(benchmark_result.generated_text.is_empty());
+    }
+
+    #[test]
+    fn follow_up_turn_ends_after_last_turn() {
+        let conversation = ConversationTurn {
+            session_index: 0,
+            turn_index: 2,
+        };
+        let mut benchmark_result = result("bye");
+
+        assert!(follow_up_turn(request(Some(conversation)), &mut benchmark_result, 3).is_none());
+        assert!(follow_up_turn(request(None), &mut result("bye"), 3).is_none());
+        assert!(benchmark_result.generated_text.is_empty());
+    }
+}
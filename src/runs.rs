@@ -0,0 +1,507 @@
+use crate::args::{RunsArgs, RunsCommand, RunsPruneArgs, RunsShowArgs};
+use crate::compare::{load_records, load_summary, resolve_run_dir};
+use crate::gate::print_verdict;
+use crate::output::{
+    BenchmarkSummary, DEFAULT_TABLE_PERCENTILES, default_results_dir, print_saved_summary,
+};
+use crate::report::{print_results, profiling_results, saved_slos};
+use anyhow::{Context, Result};
+use comfy_table::{
+    Attribute, Cell, CellAlignment, Color, ContentArrangement, Table, presets::UTF8_FULL_CONDENSED,
+};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A run or sweep directory at `<results>/<benchmark_slug>/<id>`.
+#[derive(Debug, Clone, PartialEq)]
+struct SavedEntry {
+    dir: PathBuf,
+    slug: String,
+    id: String,
+    /// Creation time taken from the `<secs>_<nanos>` id.
+    created_unix_s: u64,
+    /// Step run directories listed in a sweep's `sweep.json`; empty for plain runs.
+    steps: Vec<PathBuf>,
+}
+
+pub fn run_runs(args: &RunsArgs) -> Result<()> {
+    let results_dir = default_results_dir()?;
+    match &args.command {
+        RunsCommand::List => list_runs(&results_dir),
+        RunsCommand::Show(show_args) => show_run(show_args, &results_dir),
+        RunsCommand::Prune(prune_args) => prune_runs(prune_args, &results_dir),
+    }
+}
+
+fn list_runs(results_dir: &Path) -> Result<()> {
+    let mut runs: Vec<(SavedEntry, BenchmarkSummary)> = Vec::new();
+    for entry in scan_results(results_dir)? {
+        // Sweep directories only hold sweep.json; their steps are listed as runs.
+        if !entry.dir.join("summary.json").is_file() {
+            continue;
+        }
+        match load_summary(&entry.dir) {
+            Ok(summary) => runs.push((entry, summary)),
+            Err(err) => eprintln!("Skipping {}: {:#}", entry.dir.display(), err),
+        }
+    }
+
+    if runs.is_empty() {
+        println!("No saved runs in {}", results_dir.display());
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    table.set_header(vec![
+        Cell::new("Started (UTC)").add_attribute(Attribute::Bold),
+        Cell::new("Run ID").add_attribute(Attribute::Bold),
+        Cell::new("Benchmark").add_attribute(Attribute::Bold),
+        Cell::new("Model").add_attribute(Attribute::Bold),
+        Cell::new("Load").add_attribute(Attribute::Bold),
+        Cell::new("Requests").add_attribute(Attribute::Bold),
+        Cell::new("TTFT p50 (ms)").add_attribute(Attribute::Bold),
+        Cell::new("ITL p50 (ms)").add_attribute(Attribute::Bold),
+        Cell::new("Output tokens/s").add_attribute(Attribute::Bold),
+        Cell::new("Errors").add_attribute(Attribute::Bold),
+    ]);
+
+    fn fmt_opt(value: Option<f64>) -> String {
+        value
+            .map(|v| format!("{:.2}", v))
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    for (entry, summary) in &runs {
+        let config = &summary.input_config;
+        let load = match config.request_rate {
+            Some(rate) => format!("{:.2} req/s", rate),
+            None => format!("{} concurrent", config.num_concurrent_requests),
+        };
+        let values = [
+            format!("{}", summary.request_count.avg.unwrap_or(0.0) as u64),
+            fmt_opt(summary.time_to_first_token.p50),
+            fmt_opt(summary.inter_token_latency.p50),
+            fmt_opt(summary.output_token_throughput.avg),
+            format!("{}", summary.error_request_count.avg.unwrap_or(0.0) as u64),
+        ];
+
+        let mut row = vec![
+            Cell::new(format_unix_time(entry.created_unix_s)),
+            Cell::new(&entry.id).fg(Color::Cyan),
+            Cell::new(&entry.slug),
+            Cell::new(&config.model),
+            Cell::new(load),
+        ];
+        row.extend(values.into_iter().map(|value| {
+            Cell::new(value)
+                .set_alignment(CellAlignment::Right)
+                .fg(Color::Green)
+        }));
+        table.add_row(row);
+    }
+
+    println!("{table}");
+    Ok(())
+}
+
+fn show_run(args: &RunsShowArgs, results_dir: &Path) -> Result<()> {
+    let dir = resolve_run_dir(&args.run, results_dir)?;
+    let summary = load_summary(&dir)?;
+
+    println!(
+        "{} ({}): {}",
+        summary.benchmark_id,
+        summary.input_config.model,
+        dir.display()
+    );
+    if dir.join("individual_responses.jsonl").is_file() {
+        let slos = saved_slos(&summary.input_config)?;
+        print_results(
+            &profiling_results(&load_records(&dir)?)?,
+            Duration::from_secs_f64(summary.benchmark_duration.avg.unwrap_or_default()),
+            &slos,
+            &DEFAULT_TABLE_PERCENTILES,
+        );
+    } else {
+        // Runs copied around without their per-request records only have the summary to show.
+        print_saved_summary(&summary);
+    }
+    if let Some(verdict) = &summary.verdict {
+        println!();
+        print_verdict(verdict);
+    }
+    Ok(())
+}
+
+fn prune_runs(args: &RunsPruneArgs, results_dir: &Path) -> Result<()> {
+    let entries = group_sweep_steps(scan_results(results_dir)?, results_dir)?;
+    let now_unix_s = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let doomed = select_for_pruning(&entries, now_unix_s, args.older_than, args.keep);
+
+    for entry in &doomed {
+        // A sweep goes with its steps, so no sweep.json is left pointing at deleted runs.
+        for dir in entry.steps.iter().chain([&entry.dir]) {
+            if args.dry_run {
+                println!("Would delete {}", dir.display());
+                continue;
+            }
+            if !dir.is_dir() {
+                continue;
+            }
+            fs::remove_dir_all(dir)?;
+            println!("Deleted {}", dir.display());
+
+            // Drop the benchmark directory once its last run is gone.
+            if let Some(slug_dir) = dir.parent() {
+                if fs::read_dir(slug_dir)?.next().is_none() {
+                    fs::remove_dir(slug_dir)?;
+                }
+            }
+        }
+    }
+
+    let verb = if args.dry_run {
+        "Would delete"
+    } else {
+        "Deleted"
+    };
+    println!(
+        "{} {} of {} saved runs in {}",
+        verb,
+        doomed.len(),
+        entries.len(),
+        results_dir.display()
+    );
+    Ok(())
+}
+
+/// Every run and sweep directory under `results_dir`, newest first.
+///
+/// Directories whose names are not llmnop run ids are left alone.
+fn scan_results(results_dir: &Path) -> Result<Vec<SavedEntry>> {
+    let mut entries = Vec::new();
+    if !results_dir.is_dir() {
+        return Ok(entries);
+    }
+
+    for slug_entry in fs::read_dir(results_dir)? {
+        let slug_dir = slug_entry?.path();
+        if !slug_dir.is_dir() {
+            continue;
+        }
+        let slug = slug_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        for run_entry in fs::read_dir(&slug_dir)? {
+            let dir = run_entry?.path();
+            let id = dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let Some(created_unix_s) = run_id_timestamp(&id) else {
+                continue;
+            };
+            if dir.is_dir() {
+                entries.push(SavedEntry {
+                    dir,
+                    slug: slug.clone(),
+                    id,
+                    created_unix_s,
+                    steps: Vec::new(),
+                });
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| {
+        b.created_unix_s
+            .cmp(&a.created_unix_s)
+            .then_with(|| b.id.cmp(&a.id))
+    });
+    Ok(entries)
+}
+
+/// Attaches each sweep's step runs to the sweep and drops them as separate entries, so a
+/// sweep is pruned as a whole at the age and rank of the sweep itself.
+fn group_sweep_steps(entries: Vec<SavedEntry>, results_dir: &Path) -> Result<Vec<SavedEntry>> {
+    let mut sweeps = Vec::new();
+    for entry in &entries {
+        let path = entry.dir.join("sweep.json");
+        if !path.is_file() {
+            continue;
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let sweep: Value = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse '{}'", path.display()))?;
+        let steps: Vec<PathBuf> = sweep["steps"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|step| {
+                let summary = &step["summary"];
+                Some(
+                    results_dir
+                        .join(summary["benchmark_slug"].as_str()?)
+                        .join(summary["benchmark_id"].as_str()?),
+                )
+            })
+            .collect();
+        sweeps.push((entry.dir.clone(), steps));
+    }
+
+    Ok(entries
+        .into_iter()
+        .filter(|entry| !sweeps.iter().any(|(_, steps)| steps.contains(&entry.dir)))
+        .map(|mut entry| {
+            if let Some((_, steps)) = sweeps.iter().find(|(dir, _)| *dir == entry.dir) {
+                entry.steps = steps.clone();
+            }
+            entry
+        })
+        .collect())
+}
+
+/// Parses the seconds part of ids written by `generate_run_id`, e.g. `1700000000_123456789`.
+fn run_id_timestamp(id: &str) -> Option<u64> {
+    let (secs, nanos) = id.split_once('_')?;
+    if nanos.len() != 9 || !nanos.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    secs.parse().ok()
+}
+
+/// Entries beyond the newest `keep` that are also older than `older_than`, when given; `entries`
+/// must be newest first. The newest `keep` entries are never selected.
+fn select_for_pruning(
+    entries: &[SavedEntry],
+    now_unix_s: u64,
+    older_than: Option<Duration>,
+    keep: Option<usize>,
+) -> Vec<&SavedEntry> {
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(index, entry)| {
+            let within_keep = keep.is_some_and(|keep| *index < keep);
+            let too_old = older_than.is_some_and(|age| {
+                now_unix_s.saturating_sub(entry.created_unix_s) >= age.as_secs()
+            });
+            !within_keep && (too_old || older_than.is_none())
+        })
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+/// Formats unix seconds as `YYYY-MM-DD HH:MM` in UTC.
+pub fn format_unix_time(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let seconds_of_day = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60
+    )
+}
+
+/// Converts days since the unix epoch to a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, created_unix_s: u64) -> SavedEntry {
+        SavedEntry {
+            dir: PathBuf::from(id),
+            slug: "model_550_150".to_string(),
+            id: id.to_string(),
+            created_unix_s,
+            steps: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parses_run_id_timestamps() {
+        assert_eq!(
+            run_id_timestamp("1700000000_123456789"),
+            Some(1_700_000_000)
+        );
+        assert_eq!(run_id_timestamp("1700000000_12345"), None);
+        assert_eq!(run_id_timestamp("baseline"), None);
+    }
+
+    #[test]
+    fn formats_unix_time_in_utc() {
+        assert_eq!(format_unix_time(0), "1970-01-01 00:00");
+        assert_eq!(format_unix_time(1_700_000_000), "2023-11-14 22:13");
+        assert_eq!(format_unix_time(951_782_400), "2000-02-29 00:00");
+    }
+
+    #[test]
+    fn selects_old_runs_beyond_the_newest_kept() {
+        let day = 86_400;
+        let now = 100 * day;
+        let entries = [
+            entry("c", now - day),
+            entry("b", now - 10 * day),
+            entry("a", now - 40 * day),
+        ];
+
+        let ids = |selected: Vec<&SavedEntry>| -> Vec<String> {
+            selected.into_iter().map(|e| e.id.clone()).collect()
+        };
+        assert_eq!(
+            ids(select_for_pruning(
+                &entries,
+                now,
+                Some(Duration::from_secs(30 * day)),
+                None
+            )),
+            vec!["a"]
+        );
+        assert_eq!(
+            ids(select_for_pruning(&entries, now, None, Some(1))),
+            vec!["b", "a"]
+        );
+        // `--keep` protects the newest runs even when they are older than `--older-than`.
+        assert_eq!(
+            ids(select_for_pruning(
+                &entries,
+                now,
+                Some(Duration::from_secs(5 * day)),
+                Some(2)
+            )),
+            vec!["a"]
+        );
+        assert_eq!(
+            ids(select_for_pruning(
+                &entries,
+                now,
+                Some(Duration::from_secs(5 * day)),
+                Some(1)
+            )),
+            vec!["b", "a"]
+        );
+        assert!(select_for_pruning(&entries, now, None, Some(5)).is_empty());
+    }
+
+    #[test]
+    fn shows_runs_with_or_without_their_records() {
+        use crate::error::{ErrorKind, RequestFailure};
+        use crate::output::{test_config, write_results_json};
+
+        let config = test_config();
+        let results_dir =
+            std::env::temp_dir().join(format!("llmnop-runs-show-{}", std::process::id()));
+        let written = write_results_json(
+            &results_dir,
+            &config,
+            &[],
+            &[Err(RequestFailure::new(ErrorKind::Other, "boom"))],
+            &[],
+            Duration::from_secs(1),
+            0,
+            0,
+        )
+        .unwrap();
+        let args = RunsShowArgs {
+            run: written.dir.to_string_lossy().into_owned(),
+        };
+
+        show_run(&args, &results_dir).unwrap();
+        fs::remove_file(written.dir.join("individual_responses.jsonl")).unwrap();
+        show_run(&args, &results_dir).unwrap();
+
+        fs::remove_dir_all(&results_dir).unwrap();
+    }
+
+    #[test]
+    fn prunes_a_sweep_together_with_its_steps() {
+        let results_dir =
+            std::env::temp_dir().join(format!("llmnop-runs-sweep-{}", std::process::id()));
+        let slug_dir = results_dir.join("model_550_150");
+        let ids = [
+            "1700000000_000000001",
+            "1700000001_000000001",
+            "1700000002_000000001",
+            "1700000100_000000001",
+        ];
+        for id in ids {
+            fs::create_dir_all(slug_dir.join(id)).unwrap();
+        }
+        let step = |id: &str| serde_json::json!({"value": 1.0, "summary": {"benchmark_slug": "model_550_150", "benchmark_id": id}});
+        fs::write(
+            slug_dir.join(ids[0]).join("sweep.json"),
+            serde_json::json!({"steps": [step(ids[1]), step(ids[2])]}).to_string(),
+        )
+        .unwrap();
+
+        let entries = group_sweep_steps(scan_results(&results_dir).unwrap(), &results_dir).unwrap();
+        let ids_found: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids_found, vec![ids[3], ids[0]]);
+        assert_eq!(
+            entries[1].steps,
+            vec![slug_dir.join(ids[1]), slug_dir.join(ids[2])]
+        );
+
+        prune_runs(
+            &RunsPruneArgs {
+                older_than: None,
+                keep: Some(1),
+                dry_run: false,
+            },
+            &results_dir,
+        )
+        .unwrap();
+        let remaining: Vec<String> = scan_results(&results_dir)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(remaining, vec![ids[3]]);
+
+        fs::remove_dir_all(&results_dir).unwrap();
+    }
+
+    #[test]
+    fn scans_run_directories_newest_first() {
+        let results_dir = std::env::temp_dir().join(format!("llmnop-runs-{}", std::process::id()));
+        let slug_dir = results_dir.join("model_550_150");
+        for id in ["1700000000_000000001", "1700000500_000000001", "notes"] {
+            fs::create_dir_all(slug_dir.join(id)).unwrap();
+        }
+
+        let entries = scan_results(&results_dir).unwrap();
+        let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["1700000500_000000001", "1700000000_000000001"]);
+        assert_eq!(entries[0].slug, "model_550_150");
+        assert_eq!(entries[0].created_unix_s, 1_700_000_500);
+
+        fs::remove_dir_all(&results_dir).unwrap();
+        assert!(scan_results(&results_dir).unwrap().is_empty());
+    }
+}
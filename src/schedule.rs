@@ -0,0 +1,80 @@
+use crate::args::ArrivalDistribution;
+use rand::prelude::*;
+use rand_distr::{Exp, Gamma};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ArrivalSchedule {
+    pub request_rate: f64,
+    pub distribution: ArrivalDistribution,
+    pub burstiness: f64,
+}
+
+impl ArrivalSchedule {
+    /// Samples the gap between two consecutive request dispatches.
+    pub fn sample_interval(&self) -> Duration {
+        let mean_interval_s = 1.0 / self.request_rate;
+        let mut rng = rand::rng();
+
+        let interval_s = match self.distribution {
+            ArrivalDistribution::Constant => mean_interval_s,
+            ArrivalDistribution::Poisson => Exp::new(self.request_rate).unwrap().sample(&mut rng),
+            ArrivalDistribution::Gamma => {
+                // Shape k with scale 1/(rate*k) keeps the mean at 1/rate; k < 1 is burstier.
+                let scale = mean_interval_s / self.burstiness;
+                Gamma::new(self.burstiness, scale).unwrap().sample(&mut rng)
+            }
+        };
+
+        Duration::from_secs_f64(interval_s.max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mean_interval_s(schedule: &ArrivalSchedule, samples: usize) -> f64 {
+        let total: f64 = (0..samples)
+            .map(|_| schedule.sample_interval().as_secs_f64())
+            .sum();
+        total / samples as f64
+    }
+
+    #[test]
+    fn constant_schedule_uses_fixed_interval() {
+        let schedule = ArrivalSchedule {
+            request_rate: 4.0,
+            distribution: ArrivalDistribution::Constant,
+            burstiness: 1.0,
+        };
+
+        for _ in 0..10 {
+            assert_eq!(schedule.sample_interval(), Duration::from_millis(250));
+        }
+    }
+
+    #[test]
+    fn poisson_schedule_matches_requested_rate_on_average() {
+        let schedule = ArrivalSchedule {
+            request_rate: 10.0,
+            distribution: ArrivalDistribution::Poisson,
+            burstiness: 1.0,
+        };
+
+        let mean = mean_interval_s(&schedule, 20_000);
+        assert!((mean - 0.1).abs() < 0.01, "mean interval was {mean}");
+    }
+
+    #[test]
+    fn gamma_schedule_matches_requested_rate_on_average() {
+        let schedule = ArrivalSchedule {
+            request_rate: 10.0,
+            distribution: ArrivalDistribution::Gamma,
+            burstiness: 0.5,
+        };
+
+        let mean = mean_interval_s(&schedule, 20_000);
+        assert!((mean - 0.1).abs() < 0.01, "mean interval was {mean}");
+    }
+}
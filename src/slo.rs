@@ -0,0 +1,117 @@
+use crate::benchmark::BenchmarkResult;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SloMetric {
+    TimeToFirstToken,
+    InterTokenLatency,
+    EndToEndLatency,
+}
+
+impl SloMetric {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "ttft" => Some(SloMetric::TimeToFirstToken),
+            "itl" | "tpot" => Some(SloMetric::InterTokenLatency),
+            "e2e" => Some(SloMetric::EndToEndLatency),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SloMetric::TimeToFirstToken => "ttft",
+            SloMetric::InterTokenLatency => "itl",
+            SloMetric::EndToEndLatency => "e2e",
+        }
+    }
+}
+
+/// A latency ceiling a request must stay under to count toward goodput.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slo {
+    pub metric: SloMetric,
+    pub threshold: Duration,
+}
+
+impl Slo {
+    fn is_met(&self, br: &BenchmarkResult) -> bool {
+        let value = match self.metric {
+            SloMetric::TimeToFirstToken => br.ttft,
+            SloMetric::InterTokenLatency => Duration::from_secs_f64(br.inter_token_latency_s),
+            SloMetric::EndToEndLatency => br.total_latency,
+        };
+        value <= self.threshold
+    }
+}
+
+/// Returns `None` when no SLOs are configured, so records and summaries can omit the field.
+pub fn meets_slos(slos: &[Slo], br: &BenchmarkResult) -> Option<bool> {
+    if slos.is_empty() {
+        None
+    } else {
+        Some(slos.iter().all(|slo| slo.is_met(br)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(ttft_ms: u64, itl_ms: f64, e2e_ms: u64) -> BenchmarkResult {
+        BenchmarkResult {
+            ttft: Duration::from_millis(ttft_ms),
+            ttfo: None,
+            total_latency: Duration::from_millis(e2e_ms),
+            throughput: 50.0,
+            input_tokens: 100,
+            output_tokens: 100,
+            reasoning_tokens: 0,
+            inter_token_latency_s: itl_ms / 1000.0,
+            inter_event_latency_s: itl_ms / 1000.0,
+            inter_event_gaps_s: Vec::new(),
+            total_tokens: 200,
+            request_start_unix_ns: 0,
+            request_end_unix_ns: 0,
+            scheduled_start_unix_ns: None,
+            conversation: None,
+            prefix: None,
+            requested_output_tokens: None,
+            finish_reason: None,
+            attempts: 1,
+            generated_text: String::new(),
+        }
+    }
+
+    #[test]
+    fn meets_slos_is_none_without_slos() {
+        assert_eq!(meets_slos(&[], &result(100, 10.0, 1000)), None);
+    }
+
+    #[test]
+    fn meets_slos_requires_every_slo() {
+        let slos = [
+            Slo {
+                metric: SloMetric::TimeToFirstToken,
+                threshold: Duration::from_millis(500),
+            },
+            Slo {
+                metric: SloMetric::InterTokenLatency,
+                threshold: Duration::from_millis(50),
+            },
+        ];
+
+        assert_eq!(meets_slos(&slos, &result(500, 20.0, 9000)), Some(true));
+        assert_eq!(meets_slos(&slos, &result(501, 20.0, 9000)), Some(false));
+        assert_eq!(meets_slos(&slos, &result(100, 60.0, 9000)), Some(false));
+    }
+
+    #[test]
+    fn slo_metric_names_round_trip() {
+        for name in ["ttft", "itl", "e2e"] {
+            assert_eq!(SloMetric::parse(name).unwrap().as_str(), name);
+        }
+        assert_eq!(SloMetric::parse("tpot"), Some(SloMetric::InterTokenLatency));
+        assert_eq!(SloMetric::parse("latency"), None);
+    }
+}
@@ -0,0 +1,457 @@
+use crate::args::{OutputFormat, SweepArgs};
+use crate::export::export_run;
+use crate::gate::{Gate, print_verdict};
+use crate::output::{
+    BenchmarkSummary, benchmark_slug, default_results_dir, generate_run_id, write_results_json,
+};
+use crate::prompt::PromptSource;
+use crate::runner::{BenchmarkTarget, LoadProfile, benchmark_config, prompt_source, run_load};
+use anyhow::Result;
+use comfy_table::{
+    Attribute, Cell, CellAlignment, Color, ContentArrangement, Table, presets::UTF8_FULL_CONDENSED,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, create_dir_all};
+use std::io::Write;
+use std::path::Path;
+use std::process::ExitCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SweepParameter {
+    Concurrency,
+    RequestRate,
+}
+
+impl SweepParameter {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SweepParameter::Concurrency => "num_concurrent_requests",
+            SweepParameter::RequestRate => "request_rate",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            SweepParameter::Concurrency => "Concurrency",
+            SweepParameter::RequestRate => "Request Rate (req/s)",
+        }
+    }
+
+    fn format_value(&self, value: f64) -> String {
+        match self {
+            SweepParameter::Concurrency => format!("{}", value as u32),
+            SweepParameter::RequestRate => format!("{:.2}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepSummary {
+    pub schema_version: String,
+    pub llmnop_version: String,
+    pub sweep_id: String,
+    pub benchmark_slug: String,
+    pub parameter: String,
+    pub curve: Vec<SweepPoint>,
+    pub steps: Vec<SweepStep>,
+}
+
+/// One point on the latency/throughput curve, flattened from a step's summary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepPoint {
+    pub value: f64,
+    pub benchmark_id: String,
+    pub request_throughput: f64,
+    pub output_token_throughput: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_to_first_token_p50: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_to_first_token_p99: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inter_token_latency_p50: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inter_token_latency_p99: Option<f64>,
+    pub error_rate: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goodput_request_throughput: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slo_attainment: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepStep {
+    pub value: f64,
+    pub summary: BenchmarkSummary,
+}
+
+impl SweepPoint {
+    fn from_step(step: &SweepStep) -> Self {
+        let summary = &step.summary;
+        Self {
+            value: step.value,
+            benchmark_id: summary.benchmark_id.clone(),
+            request_throughput: summary.request_throughput.avg.unwrap_or(0.0),
+            output_token_throughput: summary.output_token_throughput.avg.unwrap_or(0.0),
+            time_to_first_token_p50: summary.time_to_first_token.p50,
+            time_to_first_token_p99: summary.time_to_first_token.p99,
+            inter_token_latency_p50: summary.inter_token_latency.p50,
+            inter_token_latency_p99: summary.inter_token_latency.p99,
+            error_rate: summary.error_rate.avg.unwrap_or(0.0),
+            goodput_request_throughput: summary
+                .goodput_request_throughput
+                .as_ref()
+                .and_then(|stats| stats.avg),
+            slo_attainment: summary.slo_attainment.as_ref().and_then(|stats| stats.avg),
+        }
+    }
+}
+
+pub async fn run_sweep(args: &SweepArgs) -> Result<ExitCode> {
+    let benchmark_args = &args.benchmark;
+    let target = match BenchmarkTarget::from_args(benchmark_args) {
+        Ok(target) => target,
+        Err(err) => match err.downcast::<clap::Error>() {
+            Ok(usage) => usage.exit(),
+            Err(err) => return Err(err),
+        },
+    };
+    let gate = Gate::from_args(benchmark_args)?;
+    #[cfg(feature = "prometheus")]
+    crate::runner::start_metrics_listener(benchmark_args).await?;
+    let prompts = prompt_source(benchmark_args, &target)?;
+
+    let sweep = run_steps(
+        args,
+        &target,
+        &prompts,
+        gate.as_ref(),
+        &default_results_dir()?,
+    )
+    .await?;
+    let parameter = sweep_parameter(args).0;
+
+    match benchmark_args.effective_output_format() {
+        OutputFormat::Table => {
+            print_sweep_table(&sweep, parameter);
+            for step in &sweep.steps {
+                if let Some(verdict) = &step.summary.verdict {
+                    println!();
+                    print!(
+                        "{} = {}: ",
+                        parameter.label(),
+                        parameter.format_value(step.value)
+                    );
+                    print_verdict(verdict);
+                }
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string(&sweep)?),
+        OutputFormat::None => {}
+    }
+
+    Ok(if gate_passed(&sweep) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn sweep_parameter(args: &SweepArgs) -> (SweepParameter, &[f64]) {
+    match (&args.concurrency, &args.request_rates) {
+        (Some(levels), _) => (SweepParameter::Concurrency, &levels.0),
+        (None, Some(levels)) => (SweepParameter::RequestRate, &levels.0),
+        (None, None) => unreachable!("clap requires --concurrency or --request-rates"),
+    }
+}
+
+/// Runs one benchmark per level, writing each step and then `sweep.json` under `results_dir`.
+async fn run_steps(
+    args: &SweepArgs,
+    target: &BenchmarkTarget,
+    prompts: &PromptSource,
+    gate: Option<&Gate>,
+    results_dir: &Path,
+) -> Result<SweepSummary> {
+    let benchmark_args = &args.benchmark;
+    let (parameter, levels) = sweep_parameter(args);
+    let output_format = benchmark_args.effective_output_format();
+    let sweep_id = generate_run_id()?;
+    let mut slug = None;
+    let mut steps = Vec::with_capacity(levels.len());
+
+    for (step_index, &value) in levels.iter().enumerate() {
+        let load = match parameter {
+            SweepParameter::Concurrency => LoadProfile {
+                num_concurrent_requests: value as u32,
+                request_rate: None,
+            },
+            SweepParameter::RequestRate => LoadProfile {
+                num_concurrent_requests: benchmark_args.num_concurrent_requests,
+                request_rate: Some(value),
+            },
+        };
+
+        if output_format != OutputFormat::None {
+            eprintln!(
+                "Sweep step {}/{}: {} = {}",
+                step_index + 1,
+                levels.len(),
+                parameter.label(),
+                parameter.format_value(value)
+            );
+        }
+
+        let run = run_load(benchmark_args, target, prompts, load).await?;
+        let config = benchmark_config(benchmark_args, target, load, gate);
+        slug.get_or_insert_with(|| benchmark_slug(&config));
+
+        let written_results = write_results_json(
+            results_dir,
+            &config,
+            &run.warmup_results,
+            &run.results,
+            &run.drained_results,
+            run.duration(),
+            run.start_unix_ns,
+            run.end_unix_ns,
+        )?;
+        export_run(&written_results.dir, &benchmark_args.export)?;
+
+        steps.push(SweepStep {
+            value,
+            summary: written_results.summary,
+        });
+    }
+
+    let sweep = SweepSummary {
+        schema_version: "1.0".to_string(),
+        llmnop_version: env!("CARGO_PKG_VERSION").to_string(),
+        sweep_id,
+        benchmark_slug: slug.unwrap_or_default(),
+        parameter: parameter.as_str().to_string(),
+        curve: steps.iter().map(SweepPoint::from_step).collect(),
+        steps,
+    };
+
+    write_sweep_json(results_dir, &sweep)?;
+    Ok(sweep)
+}
+
+/// Every step must pass the gate for the sweep to succeed.
+fn gate_passed(sweep: &SweepSummary) -> bool {
+    sweep
+        .steps
+        .iter()
+        .filter_map(|step| step.summary.verdict.as_ref())
+        .all(|verdict| verdict.passed)
+}
+
+fn write_sweep_json(results_dir: &Path, sweep: &SweepSummary) -> std::io::Result<()> {
+    let sweep_dir = results_dir
+        .join(&sweep.benchmark_slug)
+        .join(&sweep.sweep_id);
+    create_dir_all(&sweep_dir)?;
+
+    let mut file = File::create(sweep_dir.join("sweep.json"))?;
+    let sweep_json = serde_json::to_string_pretty(sweep)?;
+    file.write_all(sweep_json.as_bytes())?;
+    Ok(())
+}
+
+fn print_sweep_table(sweep: &SweepSummary, parameter: SweepParameter) {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    table.set_header(vec![
+        Cell::new(parameter.label()).add_attribute(Attribute::Bold),
+        Cell::new("Requests/s").add_attribute(Attribute::Bold),
+        Cell::new("Output tokens/s").add_attribute(Attribute::Bold),
+        Cell::new("TTFT p50 (ms)").add_attribute(Attribute::Bold),
+        Cell::new("TTFT p99 (ms)").add_attribute(Attribute::Bold),
+        Cell::new("ITL p50 (ms)").add_attribute(Attribute::Bold),
+        Cell::new("ITL p99 (ms)").add_attribute(Attribute::Bold),
+        Cell::new("Error Rate").add_attribute(Attribute::Bold),
+    ]);
+
+    fn fmt_opt(value: Option<f64>) -> String {
+        value
+            .map(|v| format!("{:.2}", v))
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    for point in &sweep.curve {
+        let values = [
+            format!("{:.2}", point.request_throughput),
+            format!("{:.2}", point.output_token_throughput),
+            fmt_opt(point.time_to_first_token_p50),
+            fmt_opt(point.time_to_first_token_p99),
+            fmt_opt(point.inter_token_latency_p50),
+            fmt_opt(point.inter_token_latency_p99),
+            format!("{:.2}", point.error_rate),
+        ];
+
+        let mut row = vec![Cell::new(parameter.format_value(point.value)).fg(Color::Cyan)];
+        row.extend(values.into_iter().map(|value| {
+            Cell::new(value)
+                .set_alignment(CellAlignment::Right)
+                .fg(Color::Green)
+        }));
+        table.add_row(row);
+    }
+
+    println!();
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{Args, Command};
+    use crate::dataset::DatasetEntry;
+    use crate::gate::Verdict;
+    use crate::mock::{self, MockConfig};
+    use crate::output::{BenchmarkConfig, summarize_results, test_config};
+    use clap::Parser;
+    use std::fs;
+    use std::time::Duration;
+
+    fn step(value: f64, passed: Option<bool>) -> SweepStep {
+        let config = BenchmarkConfig {
+            num_concurrent_requests: value as u32,
+            ..test_config()
+        };
+        let mut summary = summarize_results("run", &config, &[], Duration::from_secs(1), 0, 0);
+        summary.verdict = passed.map(|passed| Verdict {
+            passed,
+            baseline: None,
+            checks: Vec::new(),
+        });
+        SweepStep { value, summary }
+    }
+
+    #[test]
+    fn sweep_point_flattens_the_step_summary() {
+        let mut step = step(4.0, None);
+        step.summary.request_throughput.avg = Some(2.5);
+        step.summary.time_to_first_token.p50 = Some(120.0);
+        step.summary.time_to_first_token.p99 = Some(480.0);
+        step.summary.error_rate.avg = Some(0.25);
+
+        let point = SweepPoint::from_step(&step);
+        assert_eq!(point.value, 4.0);
+        assert_eq!(point.benchmark_id, "run");
+        assert_eq!(point.request_throughput, 2.5);
+        assert_eq!(point.time_to_first_token_p50, Some(120.0));
+        assert_eq!(point.time_to_first_token_p99, Some(480.0));
+        assert_eq!(point.error_rate, 0.25);
+        // Without SLOs there is no goodput, and the fields are left out of sweep.json.
+        assert_eq!(point.goodput_request_throughput, None);
+        let json = serde_json::to_value(&point).unwrap();
+        assert!(json.get("slo_attainment").is_none());
+    }
+
+    #[test]
+    fn sweep_fails_the_gate_if_any_step_fails() {
+        let sweep = |steps: Vec<SweepStep>| SweepSummary {
+            schema_version: "1.0".to_string(),
+            llmnop_version: String::new(),
+            sweep_id: "sweep".to_string(),
+            benchmark_slug: "slug".to_string(),
+            parameter: SweepParameter::Concurrency.as_str().to_string(),
+            curve: steps.iter().map(SweepPoint::from_step).collect(),
+            steps,
+        };
+        assert!(gate_passed(&sweep(vec![step(1.0, None), step(2.0, None)])));
+        assert!(gate_passed(&sweep(vec![
+            step(1.0, Some(true)),
+            step(2.0, Some(true))
+        ])));
+        assert!(!gate_passed(&sweep(vec![
+            step(1.0, Some(true)),
+            step(2.0, Some(false))
+        ])));
+    }
+
+    #[tokio::test]
+    async fn sweep_against_mock_server_writes_each_step_and_sweep_json() {
+        let mock_args = Args::try_parse_from([
+            "llmnop",
+            "mock-server",
+            "--listen",
+            "127.0.0.1:0",
+            "--ttft",
+            "0",
+            "--itl",
+            "0",
+        ])
+        .unwrap();
+        let Some(Command::MockServer(mock_args)) = mock_args.command else {
+            panic!("expected mock-server command");
+        };
+        let addr = mock::start(mock_args.listen, MockConfig::from_args(&mock_args))
+            .await
+            .unwrap();
+
+        let url = format!("http://{addr}/v1");
+        let args = Args::try_parse_from([
+            "llmnop",
+            "sweep",
+            "--url",
+            url.as_str(),
+            "--model",
+            "mock",
+            "--use-server-token-count",
+            "--max-num-completed-requests",
+            "4",
+            "--concurrency",
+            "1,2",
+            "--assert",
+            "error_rate<=0",
+            "--quiet",
+        ])
+        .unwrap();
+        let Some(Command::Sweep(args)) = args.command else {
+            panic!("expected sweep command");
+        };
+        let target = BenchmarkTarget::from_args(&args.benchmark).unwrap();
+        let gate = Gate::from_args(&args.benchmark).unwrap();
+        let prompts = PromptSource::Dataset(vec![DatasetEntry {
+            prompt: "one two three".to_string(),
+            output_tokens: Some(5),
+        }]);
+        let results_dir = std::env::temp_dir().join(format!("llmnop-sweep-{}", std::process::id()));
+
+        let sweep = run_steps(&args, &target, &prompts, gate.as_ref(), &results_dir)
+            .await
+            .unwrap();
+        assert!(gate_passed(&sweep));
+        assert_eq!(sweep.parameter, "num_concurrent_requests");
+        let values: Vec<f64> = sweep.curve.iter().map(|point| point.value).collect();
+        assert_eq!(values, vec![1.0, 2.0]);
+
+        let slug_dir = results_dir.join(&sweep.benchmark_slug);
+        for (point, step) in sweep.curve.iter().zip(&sweep.steps) {
+            assert_eq!(point.benchmark_id, step.summary.benchmark_id);
+            assert_eq!(
+                step.summary.input_config.num_concurrent_requests as f64,
+                step.value
+            );
+            assert_eq!(step.summary.successful_request_count.avg, Some(4.0));
+            assert!(
+                slug_dir
+                    .join(&point.benchmark_id)
+                    .join("summary.json")
+                    .is_file()
+            );
+        }
+
+        let saved: SweepSummary = serde_json::from_str(
+            &fs::read_to_string(slug_dir.join(&sweep.sweep_id).join("sweep.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(saved.curve.len(), 2);
+        assert_eq!(saved.steps.len(), 2);
+
+        fs::remove_dir_all(&results_dir).unwrap();
+    }
+}